@@ -0,0 +1,289 @@
+//! Build-time atlas packing for canvasette.
+//!
+//! A [`Baker`] packs a directory of PNGs into a single atlas from a build script, and generates a module holding the atlas's pixels and a `const` table of its regions, so there is no packing left to do at runtime:
+//!
+//! ```ignore
+//! // build.rs
+//! fn main() {
+//!     canvasette_bake::Baker::new("assets/sprites").bake("sprites").unwrap();
+//! }
+//!
+//! // main.rs
+//! mod sprites {
+//!     include!(concat!(env!("OUT_DIR"), "/sprites.rs"));
+//! }
+//!
+//! let image = canvasette::Image::from_baked(&sprites::ATLAS);
+//! let player = image.region("player/idle_0").unwrap();
+//! ```
+//!
+//! Regions are named after the path of their PNG relative to the directory, without the extension and with `/` as the separator.
+//!
+//! The generated code is for canvasette 0.23, which added [`BakedAtlas`](https://docs.rs/canvasette/0.23/canvasette/region/struct.BakedAtlas.html). This crate's version follows canvasette's, so use the same minor version of both.
+
+use std::path::{Path, PathBuf};
+
+/// Errors that can occur while baking.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    /// Reading the images or writing the output failed.
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// An image could not be decoded.
+    #[error("failed to decode {path}: {source}")]
+    Decode {
+        /// Path of the image.
+        path: PathBuf,
+        /// Error from the decoder.
+        source: image::ImageError,
+    },
+
+    /// The images do not fit in an atlas of the maximum size.
+    #[error("images do not fit in a {0}x{0} atlas")]
+    TooLarge(u32),
+
+    /// [`Baker::bake`] was called outside of a build script.
+    #[error("OUT_DIR is not set")]
+    NoOutDir,
+}
+
+/// Packs a directory of PNGs into an atlas.
+#[derive(Debug, Clone)]
+pub struct Baker {
+    dir: PathBuf,
+    padding: u32,
+    max_size: u32,
+}
+
+impl Baker {
+    /// Creates a new baker for the PNGs in a directory and its subdirectories.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            padding: 1,
+            max_size: 4096,
+        }
+    }
+
+    /// Sets the number of texels each image is padded with, by copying its edge texels, so filtering at the edges does not bleed in neighboring images. Defaults to 1.
+    pub fn padding(self, padding: u32) -> Self {
+        Self { padding, ..self }
+    }
+
+    /// Sets the width and height the atlas may grow to. Defaults to 4096.
+    pub fn max_size(self, max_size: u32) -> Self {
+        Self { max_size, ..self }
+    }
+
+    /// Bakes the atlas into `OUT_DIR`, writing `{name}.rs` and `{name}.rgba`, and tells Cargo to rerun the build script when the images change.
+    pub fn bake(&self, name: &str) -> Result<(), Error> {
+        let out_dir = std::env::var_os("OUT_DIR").ok_or(Error::NoOutDir)?;
+        let files = self.bake_to(Path::new(&out_dir), name)?;
+        println!("cargo:rerun-if-changed={}", self.dir.display());
+        for path in files {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+        Ok(())
+    }
+
+    /// Bakes the atlas into a directory, writing `{name}.rs` and `{name}.rgba`. Returns the paths of the images that were packed.
+    pub fn bake_to(&self, out_dir: &Path, name: &str) -> Result<Vec<PathBuf>, Error> {
+        let mut paths = vec![];
+        find_pngs(&self.dir, &mut paths)?;
+        paths.sort();
+
+        let mut images = paths
+            .iter()
+            .map(|path| {
+                let image = image::open(path)
+                    .map_err(|source| Error::Decode {
+                        path: path.clone(),
+                        source,
+                    })?
+                    .to_rgba8();
+                let name = path
+                    .strip_prefix(&self.dir)
+                    .unwrap_or(path)
+                    .with_extension("")
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                Ok((name, image))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        // Packing the tallest images first leaves fewer gaps between shelves.
+        images.sort_by(|(a_name, a), (b_name, b)| {
+            b.height().cmp(&a.height()).then_with(|| a_name.cmp(b_name))
+        });
+
+        let (size, offsets) = self.pack(&images)?;
+
+        let mut pixels = vec![0u8; size as usize * size as usize * 4];
+        let mut regions = vec![];
+        for ((name, image), offset) in images.iter().zip(offsets) {
+            let (width, height) = image.dimensions();
+            for y in 0..height + self.padding * 2 {
+                for x in 0..width + self.padding * 2 {
+                    let src = image.get_pixel(
+                        x.saturating_sub(self.padding).min(width - 1),
+                        y.saturating_sub(self.padding).min(height - 1),
+                    );
+                    let i = ((offset.1 + y) as usize * size as usize + (offset.0 + x) as usize) * 4;
+                    pixels[i..i + 4].copy_from_slice(&src.0);
+                }
+            }
+            regions.push((
+                name,
+                offset.0 + self.padding,
+                offset.1 + self.padding,
+                width,
+                height,
+            ));
+        }
+        regions.sort_by(|a, b| a.0.cmp(b.0));
+
+        let pixels_path = out_dir.join(format!("{name}.rgba"));
+        std::fs::write(&pixels_path, &pixels)?;
+
+        let mut source = String::new();
+        source.push_str("/// The baked atlas.\n");
+        source.push_str(
+            "pub const ATLAS: canvasette::region::BakedAtlas = canvasette::region::BakedAtlas {\n",
+        );
+        source.push_str(&format!("    width: {size},\n    height: {size},\n"));
+        source.push_str(&format!(
+            "    pixels: include_bytes!({:?}),\n",
+            pixels_path.display().to_string()
+        ));
+        source.push_str("    regions: &[\n");
+        for (name, x, y, width, height) in regions {
+            source.push_str(&format!(
+                "        ({name:?}, canvasette::region::Region::from_rect({x}, {y}, {width}, {height})),\n"
+            ));
+        }
+        source.push_str("    ],\n};\n");
+        std::fs::write(out_dir.join(format!("{name}.rs")), source)?;
+
+        Ok(paths)
+    }
+
+    /// Packs padded images into the smallest power of two square atlas they fit in, returning its size and the offset of each image.
+    fn pack(&self, images: &[(String, image::RgbaImage)]) -> Result<(u32, Vec<(u32, u32)>), Error> {
+        let largest = images
+            .iter()
+            .map(|(_, image)| image.width().max(image.height()) + self.padding * 2)
+            .max()
+            .unwrap_or(1);
+        let mut size = largest.next_power_of_two();
+        while size <= self.max_size {
+            let mut allocator =
+                etagere::AtlasAllocator::new(etagere::size2(size as i32, size as i32));
+            let offsets = images
+                .iter()
+                .map(|(_, image)| {
+                    let allocation = allocator.allocate(etagere::size2(
+                        (image.width() + self.padding * 2) as i32,
+                        (image.height() + self.padding * 2) as i32,
+                    ))?;
+                    Some((
+                        allocation.rectangle.min.x as u32,
+                        allocation.rectangle.min.y as u32,
+                    ))
+                })
+                .collect::<Option<Vec<_>>>();
+            if let Some(offsets) = offsets {
+                return Ok((size, offsets));
+            }
+            size *= 2;
+        }
+        Err(Error::TooLarge(self.max_size))
+    }
+}
+
+/// Collects the paths of the PNGs in a directory and its subdirectories.
+fn find_pngs(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), Error> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_pngs(&path, paths)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("png"))
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bake_to_pads_edges_and_writes_regions() {
+        let dir = std::env::temp_dir().join(format!("canvasette-bake-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("in/ui")).unwrap();
+        image::RgbaImage::from_fn(2, 2, |x, y| {
+            image::Rgba([x as u8 * 100, y as u8 * 100, 7, 255])
+        })
+        .save(dir.join("in/ui/button.png"))
+        .unwrap();
+        image::RgbaImage::from_pixel(1, 3, image::Rgba([255, 0, 0, 255]))
+            .save(dir.join("in/player.png"))
+            .unwrap();
+
+        let files = Baker::new(dir.join("in")).bake_to(&dir, "atlas").unwrap();
+        assert_eq!(files.len(), 2);
+
+        let source = std::fs::read_to_string(dir.join("atlas.rs")).unwrap();
+        let size = source
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("width: "))
+            .and_then(|width| width.trim_end_matches(',').parse::<u32>().ok())
+            .unwrap();
+        assert!(source.contains(&format!("height: {size},")));
+        let regions = source
+            .lines()
+            .filter_map(|line| {
+                let (name, rect) = line.trim().strip_prefix("(\"")?.split_once("\", ")?;
+                let rect = rect
+                    .strip_prefix("canvasette::region::Region::from_rect(")?
+                    .strip_suffix(")),")?
+                    .split(", ")
+                    .map(|n| n.parse::<u32>().unwrap())
+                    .collect::<Vec<_>>();
+                Some((name.to_string(), rect))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            regions
+                .iter()
+                .map(|(name, rect)| (name.as_str(), &rect[2..]))
+                .collect::<Vec<_>>(),
+            [("player", &[1, 3][..]), ("ui/button", &[2, 2][..])]
+        );
+
+        let pixels = std::fs::read(dir.join("atlas.rgba")).unwrap();
+        assert_eq!(pixels.len(), (size * size * 4) as usize);
+        let texel = |x: u32, y: u32| &pixels[(y * size + x) as usize * 4..][..4];
+        let (x, y) = (regions[1].1[0], regions[1].1[1]);
+        // Corners of the padding repeat the corner texels, and edges the edge texels beside them.
+        assert_eq!(texel(x - 1, y - 1), [0, 0, 7, 255]);
+        assert_eq!(texel(x + 2, y - 1), [100, 0, 7, 255]);
+        assert_eq!(texel(x - 1, y + 2), [0, 100, 7, 255]);
+        assert_eq!(texel(x + 2, y + 2), [100, 100, 7, 255]);
+        assert_eq!(texel(x + 1, y - 1), [100, 0, 7, 255]);
+        assert_eq!(texel(x - 1, y + 1), [0, 100, 7, 255]);
+        let (x, y) = (regions[0].1[0], regions[0].1[1]);
+        for y in y - 1..y + 4 {
+            assert_eq!(texel(x - 1, y), [255, 0, 0, 255]);
+            assert_eq!(texel(x + 1, y), [255, 0, 0, 255]);
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
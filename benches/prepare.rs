@@ -0,0 +1,106 @@
+//! Times preparing a frame of 100,000 sprites, which is dominated by per-draw CPU work.
+//!
+//! Run with `cargo bench --bench prepare`. Needs a GPU adapter, but nothing is rendered.
+
+use canvasette::{Canvas, Image, Renderer, TextureSlice};
+use glam::*;
+
+const SPRITES: usize = 100_000;
+const ITERATIONS: usize = 50;
+
+fn image(size: u32) -> Image {
+    Image::new(
+        vec![0xff; (size * size * 4) as usize],
+        wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        },
+    )
+}
+
+/// Draws `SPRITES` sprites scattered over a 1024×1024 target, cycling through the given images if `interleaved`, or else drawing each image in one run.
+fn scene(images: &[Image], interleaved: bool) -> Canvas<'_> {
+    let mut canvas = Canvas::new();
+    for i in 0..SPRITES {
+        let image = if interleaved {
+            i % images.len()
+        } else {
+            i * images.len() / SPRITES
+        };
+        let slice = TextureSlice::from_layer(&images[image], 0).unwrap();
+        let position = Vec2::new((i * 37 % 1000) as f32, (i * 91 % 1000) as f32);
+        canvas.draw(slice, Affine2::from_translation(position));
+    }
+    canvas
+}
+
+fn main() {
+    let instance = wgpu::Instance::default();
+    let Some(adapter) = pollster::block_on(instance.request_adapter(&Default::default())) else {
+        eprintln!("no adapter, skipping");
+        return;
+    };
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            required_limits:
+                wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits()),
+            ..Default::default()
+        },
+        None,
+    ))
+    .unwrap();
+    let mut font_system = canvasette::font::new_font_system(false);
+    let target_size = wgpu::Extent3d {
+        width: 1024,
+        height: 1024,
+        depth_or_array_layers: 1,
+    };
+
+    // Atlased images batch together whatever their order, while standalone images break batches when interleaved, which would swamp the rest.
+    let cases = [
+        ("one atlased image", vec![image(16)], false),
+        (
+            "8 atlased images, interleaved",
+            (0..8).map(|_| image(16)).collect(),
+            true,
+        ),
+        (
+            "8 standalone images, in runs",
+            (0..8).map(|_| image(512)).collect(),
+            false,
+        ),
+    ];
+    for (name, images, interleaved) in &cases {
+        let mut renderer = Renderer::new(&device, &queue, wgpu::TextureFormat::Rgba8UnormSrgb);
+        let canvas = scene(images, *interleaved);
+        // The first frame uploads the images.
+        renderer
+            .prepare(&device, &queue, &mut font_system, target_size, &canvas)
+            .unwrap();
+        // The median is reported, as the GPU driver occasionally stalls a frame for much longer.
+        let mut times = (0..ITERATIONS)
+            .map(|_| {
+                let start = std::time::Instant::now();
+                renderer
+                    .prepare(&device, &queue, &mut font_system, target_size, &canvas)
+                    .unwrap();
+                start.elapsed()
+            })
+            .collect::<Vec<_>>();
+        times.sort();
+        println!(
+            "{name}: {:.2} ms per frame",
+            times[ITERATIONS / 2].as_secs_f64() * 1000.0
+        );
+    }
+}
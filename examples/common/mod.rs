@@ -0,0 +1,295 @@
+//! Window and device setup shared by the examples, so each one only has to draw.
+//!
+//! Examples run natively with `cargo run --example <name>`. They also build for the web with `--target wasm32-unknown-unknown`, where the window is a canvas appended to the page's body, e.g. when served by `wasm-server-runner`.
+
+// Each example only uses some of the helpers.
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use canvasette::{Canvas, Renderer};
+use wgpu::{Adapter, CreateSurfaceError, Device, DeviceDescriptor, PresentMode, Queue, Surface};
+use winit::{
+    application::ApplicationHandler,
+    dpi::PhysicalSize,
+    event::WindowEvent,
+    event_loop::{EventLoop, EventLoopProxy},
+    window::Window,
+};
+
+/// Time between frames passed to [`Example::render`]. A wall clock is not available on the web without extra dependencies, so examples assume 60 frames per second.
+pub const FRAME_TIME: f32 = 1.0 / 60.0;
+
+/// Window, surface and device of a running example.
+pub struct Graphics {
+    pub window: Arc<Window>,
+    pub surface: Surface<'static>,
+    pub surface_config: wgpu::SurfaceConfiguration,
+    pub device: Device,
+    pub adapter: Adapter,
+    pub queue: Queue,
+}
+
+impl Graphics {
+    fn resize(&mut self, size: PhysicalSize<u32>) {
+        self.surface_config.width = size.width.max(1);
+        self.surface_config.height = size.height.max(1);
+        self.surface.configure(&self.device, &self.surface_config);
+        self.window.request_redraw();
+    }
+
+    /// Gets the format of the surface, which renderers should be created with.
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.surface_config.format
+    }
+}
+
+/// An example, created once the window and device are ready.
+pub trait Example: 'static {
+    /// Creates the example's renderer and resources.
+    fn new(gfx: &Graphics, font_system: &mut cosmic_text::FontSystem) -> Self;
+
+    /// Renders a frame into the surface texture.
+    fn render(
+        &mut self,
+        gfx: &Graphics,
+        font_system: &mut cosmic_text::FontSystem,
+        texture: &wgpu::Texture,
+    );
+}
+
+/// Prepares a canvas and renders it into `texture`, cleared to a dark gray first.
+pub fn draw(
+    gfx: &Graphics,
+    font_system: &mut cosmic_text::FontSystem,
+    renderer: &mut Renderer,
+    texture: &wgpu::Texture,
+    canvas: &Canvas,
+) {
+    renderer
+        .prepare(&gfx.device, &gfx.queue, font_system, texture.size(), canvas)
+        .unwrap();
+    let mut encoder = gfx
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    {
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(CLEAR_COLOR),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            ..Default::default()
+        });
+        renderer.render(&mut rpass);
+    }
+    gfx.queue.submit(Some(encoder.finish()));
+}
+
+/// Background color of [`draw`].
+pub const CLEAR_COLOR: wgpu::Color = wgpu::Color {
+    r: 0.05,
+    g: 0.05,
+    b: 0.08,
+    a: 1.0,
+};
+
+/// Loads an image from PNG data.
+pub fn load_image(data: &[u8]) -> canvasette::Image {
+    let img = image::load_from_memory(data).unwrap().to_rgba8();
+    rgba_image(img.width(), img.height(), img.into_raw())
+}
+
+/// Creates an sRGB image from RGBA pixels, e.g. generated by an example.
+pub fn rgba_image(width: u32, height: u32, pixels: Vec<u8>) -> canvasette::Image {
+    canvasette::Image::new(
+        pixels,
+        wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        },
+    )
+}
+
+/// Creates a font system with the bundled font as the default sans-serif family.
+pub fn font_system() -> cosmic_text::FontSystem {
+    // Fall back to the system's fonts for emoji and scripts the bundled font lacks.
+    let mut font_system = canvasette::font::new_font_system(true);
+    font_system
+        .db_mut()
+        .load_font_data(include_bytes!("../NotoSans-Regular.ttf").to_vec());
+    font_system.db_mut().set_sans_serif_family("Noto Sans");
+    font_system
+}
+
+enum UserEvent {
+    Graphics(Graphics),
+}
+
+struct Application<E> {
+    title: &'static str,
+    event_loop_proxy: EventLoopProxy<UserEvent>,
+    font_system: cosmic_text::FontSystem,
+    gfx: Option<Graphics>,
+    example: Option<E>,
+}
+
+async fn create_graphics(window: Arc<Window>) -> Result<Graphics, CreateSurfaceError> {
+    let instance = wgpu::Instance::default();
+
+    let surface = instance.create_surface(window.clone())?;
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            compatible_surface: Some(&surface),
+            ..Default::default()
+        })
+        .await
+        .expect("Failed to find an appropriate adapter");
+
+    let (device, queue) = adapter
+        .request_device(
+            &DeviceDescriptor {
+                required_limits: wgpu::Limits::downlevel_webgl2_defaults()
+                    .using_resolution(adapter.limits()),
+                required_features: wgpu::Features::default(),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .expect("Failed to create device");
+
+    let mut size = window.inner_size();
+    size.width = size.width.max(1);
+    size.height = size.height.max(1);
+
+    let mut config = surface
+        .get_default_config(&adapter, size.width, size.height)
+        .unwrap();
+    config.present_mode = PresentMode::AutoVsync;
+    surface.configure(&device, &config);
+
+    Ok(Graphics {
+        window,
+        surface,
+        surface_config: config,
+        adapter,
+        device,
+        queue,
+    })
+}
+
+impl<E: Example> ApplicationHandler<UserEvent> for Application<E> {
+    fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        let window_attrs = Window::default_attributes().with_title(self.title);
+        #[cfg(target_arch = "wasm32")]
+        let window_attrs = {
+            use winit::platform::web::WindowAttributesExtWebSys as _;
+            window_attrs.with_append(true)
+        };
+
+        let window = event_loop
+            .create_window(window_attrs)
+            .expect("failed to create window");
+
+        let event_loop_proxy = self.event_loop_proxy.clone();
+        let fut = async move {
+            assert!(event_loop_proxy
+                .send_event(UserEvent::Graphics(
+                    create_graphics(Arc::new(window))
+                        .await
+                        .expect("failed to create graphics context")
+                ))
+                .is_ok());
+        };
+
+        // Adapters and devices are requested asynchronously on the web, so the example is only created once they arrive.
+        #[cfg(not(target_arch = "wasm32"))]
+        pollster::block_on(fut);
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(fut);
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &winit::event_loop::ActiveEventLoop,
+        _window_id: winit::window::WindowId,
+        event: WindowEvent,
+    ) {
+        match event {
+            WindowEvent::Resized(size) => {
+                let Some(gfx) = &mut self.gfx else {
+                    return;
+                };
+                gfx.resize(size);
+            }
+            WindowEvent::RedrawRequested => {
+                let Some(gfx) = &mut self.gfx else {
+                    return;
+                };
+
+                let Some(example) = &mut self.example else {
+                    return;
+                };
+
+                let frame = gfx
+                    .surface
+                    .get_current_texture()
+                    .expect("Failed to acquire next swap chain texture");
+                example.render(gfx, &mut self.font_system, &frame.texture);
+                gfx.window.pre_present_notify();
+                frame.present();
+                gfx.window.request_redraw();
+            }
+            WindowEvent::CloseRequested => event_loop.exit(),
+            _ => {}
+        };
+    }
+
+    fn user_event(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop, event: UserEvent) {
+        match event {
+            UserEvent::Graphics(mut gfx) => {
+                gfx.resize(gfx.window.inner_size());
+                self.example = Some(E::new(&gfx, &mut self.font_system));
+                self.gfx = Some(gfx);
+            }
+        }
+    }
+}
+
+/// Opens a window titled `title` and runs an example in it until the window is closed.
+pub fn run<E: Example>(title: &'static str) {
+    let event_loop = EventLoop::with_user_event().build().unwrap();
+
+    let app = Application::<E> {
+        title,
+        gfx: None,
+        example: None,
+        font_system: font_system(),
+        event_loop_proxy: event_loop.create_proxy(),
+    };
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let mut app = app;
+        event_loop.run_app(&mut app).unwrap();
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        use winit::platform::web::EventLoopExtWebSys as _;
+        event_loop.spawn_app(app);
+    }
+}
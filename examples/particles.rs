@@ -0,0 +1,121 @@
+//! Snow drifting over a scrolling scene, and a trail following a moving comet.
+
+mod common;
+
+use canvasette::{
+    camera::Camera, palette, shape::Shape, trail::Trail, weather::Weather, Canvas, Renderer,
+};
+use common::Graphics;
+use glam::*;
+
+struct Particles {
+    renderer: Renderer,
+    camera: Camera,
+    snow: Weather<'static, canvasette::Image>,
+    trail: Trail<'static, canvasette::Image>,
+    time: f32,
+}
+
+/// Generates a soft white disc, `size` pixels across.
+fn disc(size: u32) -> canvasette::Image {
+    let radius = size as f32 * 0.5;
+    let pixels = (0..size * size)
+        .flat_map(|i| {
+            let p = Vec2::new((i % size) as f32 + 0.5, (i / size) as f32 + 0.5);
+            let alpha = (1.0 - p.distance(Vec2::splat(radius)) / radius).clamp(0.0, 1.0);
+            [0xff, 0xff, 0xff, (alpha.sqrt() * 255.0) as u8]
+        })
+        .collect();
+    common::rgba_image(size, size, pixels)
+}
+
+/// Generates an orange strip that turns yellow and opaque towards its left edge, where the head of a trail is.
+fn gradient(width: u32, height: u32) -> canvasette::Image {
+    let pixels = (0..width * height)
+        .flat_map(|i| {
+            let t = 1.0 - (i % width) as f32 / width as f32;
+            [
+                0xff,
+                (0x80 as f32 + t * 127.0) as u8,
+                0x40,
+                (t * 255.0) as u8,
+            ]
+        })
+        .collect();
+    common::rgba_image(width, height, pixels)
+}
+
+impl common::Example for Particles {
+    fn new(gfx: &Graphics, _font_system: &mut cosmic_text::FontSystem) -> Self {
+        // Weather and trails borrow their textures for as long as they live, which here is the whole program.
+        let flake: &'static canvasette::Image = Box::leak(Box::new(disc(8)));
+        let streak: &'static canvasette::Image = Box::leak(Box::new(gradient(64, 8)));
+
+        Self {
+            renderer: Renderer::new(&gfx.device, &gfx.queue, gfx.format()),
+            camera: Camera::new(Vec2::ONE),
+            snow: Weather::snow(canvasette::TextureSlice::from_layer(flake, 0).unwrap())
+                .density(3.0)
+                .seed(7),
+            trail: Trail::new(
+                canvasette::TextureSlice::from_layer(streak, 0).unwrap(),
+                0.75,
+                24.0,
+            ),
+            time: 0.0,
+        }
+    }
+
+    fn render(
+        &mut self,
+        gfx: &Graphics,
+        font_system: &mut cosmic_text::FontSystem,
+        texture: &wgpu::Texture,
+    ) {
+        let dt = common::FRAME_TIME;
+        self.time += dt;
+
+        // The camera drifts right, and the snow keeps its place in the world as it does.
+        self.camera.viewport_size = Vec2::new(texture.width() as f32, texture.height() as f32);
+        self.camera.position = Vec2::new(self.time * 60.0, 0.0);
+        self.snow.set_wind(40.0 * (self.time * 0.3).sin());
+        self.snow.update(dt, &self.camera);
+
+        let comet = self.camera.position
+            + Vec2::new(
+                (self.time * 1.3).sin() * 250.0,
+                (self.time * 2.1).sin() * 150.0,
+            );
+        self.trail.push(comet);
+        self.trail.update(dt);
+
+        let mut canvas = Canvas::new();
+
+        // Layer 0 is the world, seen through the camera.
+        canvas.set_layer_view(0, self.camera.view());
+        let first = (self.camera.position.x / 200.0).floor() as i32 - 5;
+        for i in first..first + 12 {
+            let x = i as f32 * 200.0;
+            let height = 80.0 + (i as f32 * 1.7).sin().abs() * 120.0;
+            canvas.draw(
+                Shape::rect(Vec2::new(120.0, height)).filled(palette::hex(0x0c1216)),
+                Affine2::from_translation(Vec2::new(x, 300.0 - height)),
+            );
+        }
+        canvas.draw(self.trail.clone(), Affine2::IDENTITY);
+        canvas.draw(
+            Shape::circle(10.0).filled(palette::WHITE),
+            Affine2::from_translation(comet),
+        );
+
+        // Weather is drawn in target pixels, so it goes in a layer without a view.
+        canvas.set_layer(1);
+        canvas.draw(self.snow.clone(), Affine2::IDENTITY);
+
+        common::draw(gfx, font_system, &mut self.renderer, texture, &canvas);
+    }
+}
+
+fn main() {
+    common::run::<Particles>("particles");
+}
@@ -0,0 +1,129 @@
+//! A scene rendered through a chain of post effects: chromatic aberration, then a vignette.
+
+mod common;
+
+use canvasette::{
+    palette,
+    post::{PostEffect, PostEffectDescriptor, PostPass},
+    shape::Shape,
+    Canvas, Renderer,
+};
+use common::Graphics;
+use glam::*;
+
+/// Splits the red and blue channels apart towards the edges, by up to `params.x` pixels.
+const ABERRATION: &str = r#"
+fn post_effect(in: PostOutput, color: vec4<f32>) -> vec4<f32> {
+    let offset = (in.uv - 0.5) * 2.0 * post_uniforms.params.x / post_uniforms.size;
+    let r = sample_source(in.uv + offset).r;
+    let b = sample_source(in.uv - offset).b;
+    return vec4(r, color.g, b, color.a);
+}
+"#;
+
+/// Darkens the corners by `params.x`, from 0 for none to 1 for black.
+const VIGNETTE: &str = r#"
+fn post_effect(in: PostOutput, color: vec4<f32>) -> vec4<f32> {
+    let d = distance(in.uv, vec2(0.5));
+    return vec4(color.rgb * (1.0 - smoothstep(0.3, 0.75, d) * post_uniforms.params.x), color.a);
+}
+"#;
+
+struct PostProcessing {
+    renderer: Renderer,
+    texture: canvasette::Image,
+    aberration: PostEffect,
+    vignette: PostEffect,
+    time: f32,
+}
+
+impl common::Example for PostProcessing {
+    fn new(gfx: &Graphics, _font_system: &mut cosmic_text::FontSystem) -> Self {
+        let mut renderer = Renderer::new(&gfx.device, &gfx.queue, gfx.format());
+        let aberration = renderer
+            .create_post_effect(
+                &gfx.device,
+                PostEffectDescriptor {
+                    label: Some("aberration"),
+                    source: ABERRATION,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let vignette = renderer
+            .create_post_effect(
+                &gfx.device,
+                PostEffectDescriptor {
+                    label: Some("vignette"),
+                    source: VIGNETTE,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        Self {
+            renderer,
+            texture: common::load_image(include_bytes!("test.png")),
+            aberration,
+            vignette,
+            time: 0.0,
+        }
+    }
+
+    fn render(
+        &mut self,
+        gfx: &Graphics,
+        font_system: &mut cosmic_text::FontSystem,
+        texture: &wgpu::Texture,
+    ) {
+        self.time += common::FRAME_TIME;
+        let size = Vec2::new(texture.width() as f32, texture.height() as f32);
+
+        // Post effects replace the target, so the scene draws its own background.
+        let mut canvas = Canvas::new();
+        canvas.draw(
+            Shape::rect(size).filled(palette::hex(0x0e1418)),
+            Affine2::IDENTITY,
+        );
+        let slice = canvasette::TextureSlice::from_layer(&self.texture, 0).unwrap();
+        for i in 0..5 {
+            let t = self.time + i as f32 * 1.3;
+            let position = size * Vec2::new(0.5 + 0.35 * (t * 0.7).sin(), 0.5 + 0.3 * t.cos());
+            canvas.draw(
+                slice,
+                Affine2::from_translation(position)
+                    * Affine2::from_angle(t * 0.5)
+                    * Affine2::from_translation(-slice.size().as_vec2() * 0.5),
+            );
+        }
+
+        let passes = [
+            PostPass::new(self.aberration).with_params(Vec4::new(
+                4.0 + 3.0 * self.time.sin(),
+                0.0,
+                0.0,
+                0.0,
+            )),
+            PostPass::new(self.vignette).with_params(Vec4::new(0.8, 0.0, 0.0, 0.0)),
+        ];
+        let mut encoder = gfx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.renderer
+            .render_with_post_effects(
+                &gfx.device,
+                &gfx.queue,
+                font_system,
+                &mut encoder,
+                texture,
+                &canvas,
+                &passes,
+            )
+            .unwrap();
+        gfx.queue.submit(Some(encoder.finish()));
+    }
+}
+
+fn main() {
+    common::run::<PostProcessing>("post_processing");
+}
@@ -0,0 +1,123 @@
+//! A scene rendered once per frame into an offscreen target, which is then drawn several times like any other texture.
+
+mod common;
+
+use canvasette::{palette, shape::Shape, Canvas, Drawable as _, Label, Renderer};
+use common::Graphics;
+use glam::*;
+
+const TARGET_SIZE: u32 = 256;
+
+struct RenderToTexture {
+    renderer: Renderer,
+    target: canvasette::RenderTarget,
+    texture: canvasette::Image,
+    time: f32,
+}
+
+impl common::Example for RenderToTexture {
+    fn new(gfx: &Graphics, _font_system: &mut cosmic_text::FontSystem) -> Self {
+        let renderer = Renderer::new(&gfx.device, &gfx.queue, gfx.format());
+        Self {
+            target: renderer.create_render_target_named(
+                &gfx.device,
+                "screen",
+                TARGET_SIZE,
+                TARGET_SIZE,
+            ),
+            renderer,
+            texture: common::load_image(include_bytes!("test.png")),
+            time: 0.0,
+        }
+    }
+
+    fn render(
+        &mut self,
+        gfx: &Graphics,
+        font_system: &mut cosmic_text::FontSystem,
+        texture: &wgpu::Texture,
+    ) {
+        self.time += common::FRAME_TIME;
+        let mut encoder = gfx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        // The offscreen scene: a spinning sprite with a caption, on a background of its own.
+        let mut screen = Canvas::new();
+        let size = Vec2::splat(TARGET_SIZE as f32);
+        screen.draw(
+            Shape::rect(size).filled(palette::hex(0x050a30)),
+            Affine2::IDENTITY,
+        );
+        let slice = canvasette::TextureSlice::from_layer(&self.texture, 0).unwrap();
+        screen.draw(
+            slice,
+            Affine2::from_translation(size * 0.5)
+                * Affine2::from_angle(self.time)
+                * Affine2::from_scale(Vec2::splat(0.6))
+                * Affine2::from_translation(-slice.size().as_vec2() * 0.5),
+        );
+        screen.draw(
+            Label::new(
+                font_system,
+                &format!("{:.1}s", self.time),
+                canvasette::font::Metrics::new(32.0, 40.0),
+                canvasette::font::Attrs::default(),
+            )
+            .tinted(palette::WHITE),
+            Affine2::from_translation(Vec2::new(12.0, 8.0)),
+        );
+        self.renderer
+            .render_to_target(
+                &gfx.device,
+                &gfx.queue,
+                font_system,
+                &mut encoder,
+                &mut self.target,
+                &screen,
+            )
+            .unwrap();
+
+        // The main scene draws the target like a texture, here as a wall of monitors.
+        let mut canvas = Canvas::new();
+        let screen = canvasette::TextureSlice::from_layer(&self.target, 0).unwrap();
+        for i in 0..6 {
+            let column = (i % 3) as f32;
+            let row = (i / 3) as f32;
+            let tint = palette::with_alpha(palette::WHITE, 0xff - i as u8 * 0x20);
+            canvas.draw(
+                screen.tinted(tint),
+                Affine2::from_translation(Vec2::new(40.0 + column * 280.0, 40.0 + row * 280.0))
+                    * Affine2::from_angle((self.time + i as f32).sin() * 0.05),
+            );
+        }
+        self.renderer
+            .prepare(
+                &gfx.device,
+                &gfx.queue,
+                font_system,
+                texture.size(),
+                &canvas,
+            )
+            .unwrap();
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(common::CLEAR_COLOR),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                ..Default::default()
+            });
+            self.renderer.render(&mut rpass);
+        }
+        gfx.queue.submit(Some(encoder.finish()));
+    }
+}
+
+fn main() {
+    common::run::<RenderToTexture>("render_to_texture");
+}
@@ -0,0 +1,89 @@
+//! A paragraph wrapped to a changing width, in each alignment.
+
+mod common;
+
+use canvasette::{
+    font::{Align, Attrs, Metrics, TextOptions, Wrap},
+    palette,
+    shape::Shape,
+    Canvas, Drawable as _, Label, Renderer,
+};
+use common::Graphics;
+use glam::*;
+
+const TEXT: &str = "Lines wrap at word boundaries to fit the width they are given, and words too long for a line are broken between glyphs. Résumé, naïve and 日本語 shape like any other text.";
+
+const ALIGNMENTS: [(&str, Align); 4] = [
+    ("Left", Align::Left),
+    ("Center", Align::Center),
+    ("Right", Align::Right),
+    ("Justified", Align::Justified),
+];
+
+struct TextWrapping {
+    renderer: Renderer,
+    time: f32,
+}
+
+impl common::Example for TextWrapping {
+    fn new(gfx: &Graphics, _font_system: &mut cosmic_text::FontSystem) -> Self {
+        Self {
+            renderer: Renderer::new(&gfx.device, &gfx.queue, gfx.format()),
+            time: 0.0,
+        }
+    }
+
+    fn render(
+        &mut self,
+        gfx: &Graphics,
+        font_system: &mut cosmic_text::FontSystem,
+        texture: &wgpu::Texture,
+    ) {
+        self.time += common::FRAME_TIME;
+        let column = texture.width() as f32 / ALIGNMENTS.len() as f32;
+        let width = column * (0.6 + 0.3 * (self.time * 0.8).sin());
+
+        let mut canvas = Canvas::new();
+        for (i, (name, align)) in ALIGNMENTS.into_iter().enumerate() {
+            let origin = Vec2::new(i as f32 * column + (column - width) * 0.5, 20.0);
+
+            let heading = Label::new(
+                font_system,
+                name,
+                Metrics::new(24.0, 32.0),
+                Attrs::default(),
+            );
+            canvas.draw(
+                heading.tinted(palette::YELLOW),
+                Affine2::from_translation(origin),
+            );
+
+            // Labels are laid out when created, so a label whose width changes is created again.
+            let paragraph = Label::with_options(
+                font_system,
+                TEXT,
+                Metrics::new(18.0, 24.0),
+                Attrs::default(),
+                TextOptions {
+                    width: Some(width),
+                    align: Some(align),
+                    wrap: Wrap::WordOrGlyph,
+                    ..Default::default()
+                },
+            );
+            let offset = origin + Vec2::new(0.0, 40.0);
+            canvas.draw(
+                Shape::rect(Vec2::new(width, paragraph.size().y))
+                    .filled(palette::with_alpha(palette::WHITE, 0x18)),
+                Affine2::from_translation(offset),
+            );
+            canvas.draw(paragraph, Affine2::from_translation(offset));
+        }
+
+        common::draw(gfx, font_system, &mut self.renderer, texture, &canvas);
+    }
+}
+
+fn main() {
+    common::run::<TextWrapping>("text_wrapping");
+}
@@ -0,0 +1,110 @@
+//! A large tilemap scrolled by a camera. Only the tiles on screen are generated each frame, and when zoomed far out, the map is drawn as downsampled chunks instead.
+
+mod common;
+
+use canvasette::{camera::Camera, palette, tilemap::Tilemap, Canvas, Renderer};
+use common::Graphics;
+use glam::*;
+
+const TILE_SIZE: u32 = 16;
+const MAP_SIZE: u32 = 256;
+
+/// Colors of the generated tiles: water, sand, grass and forest.
+const TILE_COLORS: [u32; 4] = [0x2a6fdb, 0xe3cf8a, 0x4caf50, 0x1b5e20];
+
+struct TilemapExample {
+    renderer: Renderer,
+    tilemap: Tilemap<'static, canvasette::Image>,
+    camera: Camera,
+    time: f32,
+}
+
+/// Generates a tileset with one tile per color, each with a darker border so the grid is visible.
+fn tileset() -> canvasette::Image {
+    let width = TILE_SIZE * TILE_COLORS.len() as u32;
+    let mut pixels = Vec::with_capacity((width * TILE_SIZE * 4) as usize);
+    for y in 0..TILE_SIZE {
+        for x in 0..width {
+            let color = palette::hex(TILE_COLORS[(x / TILE_SIZE) as usize]);
+            let edge = x % TILE_SIZE == 0 || y == 0;
+            let shade = |v: u8| if edge { v / 4 * 3 } else { v };
+            pixels.extend_from_slice(&[shade(color.r), shade(color.g), shade(color.b), 0xff]);
+        }
+    }
+    common::rgba_image(width, TILE_SIZE, pixels)
+}
+
+/// Picks a tile for a cell from overlapping waves, so the map has islands and coastlines.
+fn terrain(x: u32, y: u32) -> u32 {
+    let p = Vec2::new(x as f32, y as f32) * 0.05;
+    let height = (p.x.sin() + (p.y * 1.3).cos() + (p.x * 0.7 + p.y * 0.4).sin()) / 3.0;
+    match height {
+        h if h < -0.1 => 0,
+        h if h < 0.0 => 1,
+        h if h < 0.4 => 2,
+        _ => 3,
+    }
+}
+
+impl common::Example for TilemapExample {
+    fn new(gfx: &Graphics, _font_system: &mut cosmic_text::FontSystem) -> Self {
+        let renderer = Renderer::with_options(
+            &gfx.device,
+            &gfx.queue,
+            gfx.format(),
+            canvasette::RendererOptions {
+                sampler: canvasette::SamplerOptions::NEAREST,
+                ..Default::default()
+            },
+        );
+
+        // The tilemap borrows its tileset for as long as it lives, which here is the whole program.
+        let tileset: &'static canvasette::Image = Box::leak(Box::new(tileset()));
+        let mut tilemap = Tilemap::new(
+            canvasette::TextureSlice::from_layer(tileset, 0).unwrap(),
+            UVec2::splat(TILE_SIZE),
+            MAP_SIZE,
+            MAP_SIZE,
+        )
+        .unwrap();
+        for y in 0..MAP_SIZE {
+            for x in 0..MAP_SIZE {
+                tilemap.set(x, y, Some(terrain(x, y)));
+            }
+        }
+        tilemap.set_lod(Some(canvasette::tilemap::LodOptions::default()));
+
+        Self {
+            renderer,
+            tilemap,
+            camera: Camera::new(Vec2::ONE),
+            time: 0.0,
+        }
+    }
+
+    fn render(
+        &mut self,
+        gfx: &Graphics,
+        font_system: &mut cosmic_text::FontSystem,
+        texture: &wgpu::Texture,
+    ) {
+        self.time += common::FRAME_TIME;
+
+        // Circle around the middle of the map, zooming in and out.
+        let center = Vec2::splat((MAP_SIZE * TILE_SIZE) as f32 * 0.5);
+        self.camera.viewport_size = Vec2::new(texture.width() as f32, texture.height() as f32);
+        self.camera.position = center + Vec2::from_angle(self.time * 0.2) * 800.0;
+        self.camera.zoom = 0.9 + (self.time * 0.5).sin() * 0.75;
+        self.camera.update(common::FRAME_TIME);
+
+        let mut canvas = Canvas::new();
+        canvas.set_view(self.camera.view());
+        canvas.draw(&self.tilemap, Affine2::IDENTITY);
+
+        common::draw(gfx, font_system, &mut self.renderer, texture, &canvas);
+    }
+}
+
+fn main() {
+    common::run::<TilemapExample>("tilemap");
+}
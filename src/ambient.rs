@@ -0,0 +1,125 @@
+//! Ambient color modulation, such as a day/night cycle.
+//!
+//! An [`Ambient`] darkens or tints everything drawn before it by multiplying the target by one color and then adding another. It is drawn as a full-screen overlay, so it affects whatever is below it in draw order: draw it after the world and before the UI, in a layer without a view. A [`DayNight`] curve picks the ambient for a time of day, so the overlay can be updated every frame.
+
+use glam::*;
+
+use crate::{palette, BlendMode, Canvas, Color, Drawable, Shape};
+
+/// Color modulation applied to the whole target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ambient {
+    /// Color the target is multiplied by. White leaves it unchanged.
+    pub multiply: Color,
+
+    /// Color added to the target afterwards, scaled by its alpha. Transparent leaves it unchanged.
+    pub add: Color,
+}
+
+impl Default for Ambient {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+impl Ambient {
+    /// Ambient that leaves the target unchanged.
+    pub const NONE: Self = Self {
+        multiply: palette::WHITE,
+        add: palette::TRANSPARENT,
+    };
+
+    /// Creates an ambient that only multiplies the target.
+    pub fn multiply(color: Color) -> Self {
+        Self {
+            multiply: color,
+            ..Self::NONE
+        }
+    }
+
+    /// Sets the color added to the target.
+    pub fn with_add(self, add: Color) -> Self {
+        Self { add, ..self }
+    }
+
+    /// Interpolates between two ambients in linear light.
+    pub fn mix(a: Self, b: Self, t: f32) -> Self {
+        Self {
+            multiply: palette::mix_linear(a.multiply, b.multiply, t),
+            add: palette::mix_linear(a.add, b.add, t),
+        }
+    }
+
+    /// Returns a drawable covering a rectangle of the given size, typically the target size, with this ambient.
+    pub fn overlay<'a>(&self, size: Vec2) -> impl Drawable<'a> {
+        Overlay {
+            ambient: *self,
+            size,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Overlay {
+    ambient: Ambient,
+    size: Vec2,
+}
+
+impl<'a> Drawable<'a> for Overlay {
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: Affine2) {
+        let rect = Shape::rect(self.size);
+        if self.ambient.multiply != palette::WHITE {
+            rect.filled(self.ambient.multiply)
+                .blended(BlendMode::Multiply)
+                .draw(canvas, tint, transform);
+        }
+        if self.ambient.add.a != 0 {
+            rect.filled(self.ambient.add)
+                .blended(BlendMode::Additive)
+                .draw(canvas, tint, transform);
+        }
+    }
+}
+
+/// A curve of ambients over a repeating time of day.
+///
+/// Times are fractions of a day in `[0, 1)`, e.g. 0.5 for noon. Between keys the ambient is interpolated, wrapping around from the last key to the first.
+#[derive(Debug, Clone, Default)]
+pub struct DayNight {
+    keys: Vec<(f32, Ambient)>,
+}
+
+impl DayNight {
+    /// Creates an empty curve, which evaluates to [`Ambient::NONE`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a key at the given time of day.
+    pub fn key(mut self, time: f32, ambient: Ambient) -> Self {
+        let time = time.rem_euclid(1.0);
+        let i = self.keys.partition_point(|(t, _)| *t <= time);
+        self.keys.insert(i, (time, ambient));
+        self
+    }
+
+    /// Evaluates the curve at the given time of day.
+    pub fn at(&self, time: f32) -> Ambient {
+        let (Some(first), Some(last)) = (self.keys.first(), self.keys.last()) else {
+            return Ambient::NONE;
+        };
+        let time = time.rem_euclid(1.0);
+        let i = self.keys.partition_point(|(t, _)| *t <= time);
+        let ((t0, a), (t1, b)) = match i {
+            0 => ((last.0 - 1.0, last.1), *first),
+            i if i == self.keys.len() => (*last, (first.0 + 1.0, first.1)),
+            i => (self.keys[i - 1], self.keys[i]),
+        };
+        let t = if t1 > t0 {
+            (time - t0) / (t1 - t0)
+        } else {
+            0.0
+        };
+        Ambient::mix(a, b, t)
+    }
+}
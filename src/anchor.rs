@@ -0,0 +1,24 @@
+//! Named anchor points for [`crate::Drawable::anchored`].
+//!
+//! Anchors are normalized coordinates within a drawable's bounds: `(0, 0)` is the top-left corner and `(1, 1)` the bottom-right. Any other point, including ones outside the bounds, can be used directly as a [`Vec2`].
+
+use glam::Vec2;
+
+/// Top-left corner, where drawables are placed by default.
+pub const TOP_LEFT: Vec2 = Vec2::new(0.0, 0.0);
+/// Middle of the top edge.
+pub const TOP_CENTER: Vec2 = Vec2::new(0.5, 0.0);
+/// Top-right corner.
+pub const TOP_RIGHT: Vec2 = Vec2::new(1.0, 0.0);
+/// Middle of the left edge.
+pub const CENTER_LEFT: Vec2 = Vec2::new(0.0, 0.5);
+/// Center.
+pub const CENTER: Vec2 = Vec2::new(0.5, 0.5);
+/// Middle of the right edge.
+pub const CENTER_RIGHT: Vec2 = Vec2::new(1.0, 0.5);
+/// Bottom-left corner.
+pub const BOTTOM_LEFT: Vec2 = Vec2::new(0.0, 1.0);
+/// Middle of the bottom edge, e.g. a character's feet.
+pub const BOTTOM_CENTER: Vec2 = Vec2::new(0.5, 1.0);
+/// Bottom-right corner.
+pub const BOTTOM_RIGHT: Vec2 = Vec2::new(1.0, 1.0);
@@ -0,0 +1,353 @@
+//! Easing and tweening for transitions, and frame-by-frame sprite animation.
+//!
+//! A [`Tween`] interpolates between two values over a duration with an [`Easing`] curve. It can be advanced with [`Tween::update`] once per frame like [`crate::camera::Camera`], or sampled at an arbitrary time with [`Tween::sample`].
+//!
+//! An [`Animation`] cycles through texture slices, taken from the layers of a texture or the cells of a [`crate::grid::Grid`]. It holds no time of its own: [`Animation::frame_at`] picks the frame for any time, so one animation can be shared by many sprites.
+
+use glam::*;
+
+use crate::{grid::Grid, palette, Color, Colorf, Texture, TextureSlice};
+
+/// An easing curve, mapping progress in `[0, 1]` to eased progress.
+///
+/// Every curve maps 0 to 0 and 1 to 1. [`Easing::BackOut`] and [`Easing::ElasticOut`] overshoot in between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Easing {
+    /// Constant speed.
+    #[default]
+    Linear,
+    /// Accelerates from zero speed.
+    QuadIn,
+    /// Decelerates to zero speed.
+    QuadOut,
+    /// Accelerates, then decelerates.
+    QuadInOut,
+    /// Accelerates from zero speed, more sharply than [`Easing::QuadIn`].
+    CubicIn,
+    /// Decelerates to zero speed, more sharply than [`Easing::QuadOut`].
+    CubicOut,
+    /// Accelerates, then decelerates, more sharply than [`Easing::QuadInOut`].
+    CubicInOut,
+    /// Accelerates along a quarter sine wave.
+    SineIn,
+    /// Decelerates along a quarter sine wave.
+    SineOut,
+    /// Accelerates, then decelerates along a half sine wave.
+    SineInOut,
+    /// Overshoots the end slightly, then settles back.
+    BackOut,
+    /// Springs past the end and oscillates into place.
+    ElasticOut,
+    /// Bounces against the end like a dropped ball.
+    BounceOut,
+}
+
+impl Easing {
+    /// Applies the curve to progress `t`, which is clamped to `[0, 1]`.
+    pub fn apply(self, t: f32) -> f32 {
+        use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) * 0.5
+                }
+            }
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) * 0.5
+                }
+            }
+            Easing::SineIn => 1.0 - (t * FRAC_PI_2).cos(),
+            Easing::SineOut => (t * FRAC_PI_2).sin(),
+            Easing::SineInOut => -((PI * t).cos() - 1.0) * 0.5,
+            Easing::BackOut => {
+                const C1: f32 = 1.70158;
+                const C3: f32 = C1 + 1.0;
+                1.0 + C3 * (t - 1.0).powi(3) + C1 * (t - 1.0).powi(2)
+            }
+            Easing::ElasticOut => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    2f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (TAU / 3.0)).sin() + 1.0
+                }
+            }
+            Easing::BounceOut => {
+                const N1: f32 = 7.5625;
+                const D1: f32 = 2.75;
+                if t < 1.0 / D1 {
+                    N1 * t * t
+                } else if t < 2.0 / D1 {
+                    let t = t - 1.5 / D1;
+                    N1 * t * t + 0.75
+                } else if t < 2.5 / D1 {
+                    let t = t - 2.25 / D1;
+                    N1 * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / D1;
+                    N1 * t * t + 0.984375
+                }
+            }
+        }
+    }
+}
+
+/// Values that can be interpolated by a [`Tween`].
+pub trait Lerp {
+    /// Interpolates between two values. `t` of 0 gives `self`, `t` of 1 gives `other`, and values outside `[0, 1]` extrapolate where the type allows it.
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Vec2::lerp(*self, *other, t)
+    }
+}
+
+/// Colors are mixed in sRGB space with [`palette::mix`], which does not extrapolate.
+impl Lerp for Color {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        palette::mix(*self, *other, t)
+    }
+}
+
+/// Unlike [`Color`], components are interpolated without clamping, so they extrapolate.
+impl Lerp for Colorf {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Colorf::new(
+            Lerp::lerp(&self.r, &other.r, t),
+            Lerp::lerp(&self.g, &other.g, t),
+            Lerp::lerp(&self.b, &other.b, t),
+            Lerp::lerp(&self.a, &other.a, t),
+        )
+    }
+}
+
+/// Transforms are decomposed into scale, rotation and translation, which are interpolated separately, with rotation taking the shortest way around. Shear is lost.
+impl Lerp for Affine2 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let (scale_a, angle_a, translation_a) = self.to_scale_angle_translation();
+        let (scale_b, angle_b, translation_b) = other.to_scale_angle_translation();
+        let delta = (angle_b - angle_a + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU)
+            - std::f32::consts::PI;
+        Affine2::from_scale_angle_translation(
+            scale_a.lerp(scale_b, t),
+            angle_a + delta * t,
+            translation_a.lerp(translation_b, t),
+        )
+    }
+}
+
+/// An interpolation between two values over a duration in seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tween<T> {
+    /// Value at the start.
+    pub from: T,
+    /// Value at the end.
+    pub to: T,
+    /// Length of the tween in seconds.
+    pub duration: f32,
+    /// Curve applied to the progress.
+    pub easing: Easing,
+    elapsed: f32,
+}
+
+impl<T> Tween<T>
+where
+    T: Lerp,
+{
+    /// Creates a new linear tween, at its start.
+    pub fn new(from: T, to: T, duration: f32) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            easing: Easing::Linear,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Returns the tween with a different easing curve.
+    pub fn with_easing(self, easing: Easing) -> Self {
+        Self { easing, ..self }
+    }
+
+    /// Advances the tween by `dt` seconds. Time stops at the end of the tween.
+    pub fn update(&mut self, dt: f32) {
+        self.elapsed = (self.elapsed + dt).clamp(0.0, self.duration.max(0.0));
+    }
+
+    /// Restarts the tween from its start.
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+
+    /// Gets the time elapsed since the start, in seconds.
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    /// Gets the progress through the tween, from 0 to 1, before easing.
+    pub fn progress(&self) -> f32 {
+        self.progress_at(self.elapsed)
+    }
+
+    /// Gets whether the tween has reached its end.
+    pub fn is_finished(&self) -> bool {
+        self.progress() >= 1.0
+    }
+
+    /// Gets the value at the current time.
+    pub fn value(&self) -> T {
+        self.sample(self.elapsed)
+    }
+
+    /// Gets the value `time` seconds after the start, regardless of the current time.
+    pub fn sample(&self, time: f32) -> T {
+        self.from
+            .lerp(&self.to, self.easing.apply(self.progress_at(time)))
+    }
+
+    fn progress_at(&self, time: f32) -> f32 {
+        if self.duration > 0.0 {
+            (time / self.duration).clamp(0.0, 1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+/// How an [`Animation`] carries on past its last frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Playback {
+    /// Starts over from the first frame.
+    #[default]
+    Loop,
+    /// Stays on the last frame.
+    Once,
+    /// Plays backwards to the first frame, then forwards again, without repeating the first and last frames.
+    PingPong,
+}
+
+/// A sequence of texture slices shown one after the other, each for the same duration.
+pub struct Animation<'a, T> {
+    frames: Vec<TextureSlice<'a, T>>,
+    /// Time each frame is shown for, in seconds.
+    pub frame_duration: f32,
+    /// What happens after the last frame.
+    pub playback: Playback,
+}
+
+impl<'a, T> Clone for Animation<'a, T> {
+    fn clone(&self) -> Self {
+        Self {
+            frames: self.frames.clone(),
+            frame_duration: self.frame_duration,
+            playback: self.playback,
+        }
+    }
+}
+
+impl<'a, T> Animation<'a, T>
+where
+    T: Texture,
+{
+    /// Creates a new looping animation from its frames.
+    ///
+    /// Returns [`None`] if there are no frames.
+    pub fn new(frames: Vec<TextureSlice<'a, T>>, frame_duration: f32) -> Option<Self> {
+        if frames.is_empty() {
+            return None;
+        }
+        Some(Self {
+            frames,
+            frame_duration,
+            playback: Playback::Loop,
+        })
+    }
+
+    /// Creates a new looping animation with one frame per array layer of a texture, in layer order.
+    pub fn from_layers(texture: &'a T, frame_duration: f32) -> Self {
+        let frames = (0..texture.size().depth_or_array_layers)
+            .filter_map(|layer| TextureSlice::from_layer(texture, layer))
+            .collect();
+        Self {
+            frames,
+            frame_duration,
+            playback: Playback::Loop,
+        }
+    }
+
+    /// Creates a new looping animation with one frame per cell of a grid, in index order, e.g. from a uniform sprite sheet.
+    pub fn from_grid(grid: &Grid<'a, T>, frame_duration: f32) -> Self {
+        Self {
+            frames: grid.cells().collect(),
+            frame_duration,
+            playback: Playback::Loop,
+        }
+    }
+
+    /// Returns the animation with a different playback mode.
+    pub fn with_playback(self, playback: Playback) -> Self {
+        Self { playback, ..self }
+    }
+
+    /// Gets the frames.
+    pub fn frames(&self) -> &[TextureSlice<'a, T>] {
+        &self.frames
+    }
+
+    /// Gets the time it takes to play through the frames once, in seconds. A [`Playback::PingPong`] cycle takes about twice as long.
+    pub fn duration(&self) -> f32 {
+        self.frame_duration * self.frames.len() as f32
+    }
+
+    /// Gets the index of the frame shown `time` seconds after the start. Times before the start show the first frame.
+    pub fn frame_index_at(&self, time: f32) -> usize {
+        let count = self.frames.len();
+        if count <= 1 || self.frame_duration <= 0.0 || time <= 0.0 {
+            return 0;
+        }
+        let step = (time / self.frame_duration) as usize;
+        match self.playback {
+            Playback::Loop => step % count,
+            Playback::Once => step.min(count - 1),
+            Playback::PingPong => {
+                let period = count * 2 - 2;
+                let step = step % period;
+                if step < count {
+                    step
+                } else {
+                    period - step
+                }
+            }
+        }
+    }
+
+    /// Gets the frame shown `time` seconds after the start.
+    pub fn frame_at(&self, time: f32) -> TextureSlice<'a, T> {
+        self.frames[self.frame_index_at(time)]
+    }
+
+    /// Gets whether a [`Playback::Once`] animation has shown its last frame for its full duration by `time` seconds after the start. Other animations never finish.
+    pub fn is_finished_at(&self, time: f32) -> bool {
+        self.playback == Playback::Once && time >= self.duration()
+    }
+}
@@ -3,12 +3,18 @@ use std::{collections::HashMap, hash::Hash};
 use imgref::ImgRef;
 
 pub struct Atlas<K, Pixel> {
+    label: &'static str,
     texture: wgpu::Texture,
+    format: wgpu::TextureFormat,
     allocator: etagere::AtlasAllocator,
+    options: etagere::AllocatorOptions,
+    /// Size the atlas may grow to, before the device's limit is applied.
+    max_size: u32,
     allocations: HashMap<K, etagere::AllocId>,
     _phantom: std::marker::PhantomData<Pixel>,
 }
 
+#[cfg_attr(not(feature = "text"), allow(dead_code))]
 pub trait HasTextureFormat {
     fn texture_format() -> wgpu::TextureFormat;
 }
@@ -30,41 +36,68 @@ where
     K: std::cmp::Eq + Hash + Clone + Copy,
     Pixel: Clone + bytemuck::NoUninit + HasTextureFormat,
 {
-    const INITIAL_SIZE: wgpu::Extent3d = wgpu::Extent3d {
-        width: 1024,
-        height: 1024,
-        depth_or_array_layers: 1,
-    };
-
-    pub fn new(device: &wgpu::Device) -> Self {
-        Self::new_with_initial_size(device, Self::INITIAL_SIZE)
+    #[cfg_attr(not(feature = "text"), allow(dead_code))]
+    pub fn new(
+        device: &wgpu::Device,
+        label: &'static str,
+        size: wgpu::Extent3d,
+        max_size: u32,
+        options: etagere::AllocatorOptions,
+    ) -> Self {
+        Self::with_format(
+            device,
+            label,
+            size,
+            max_size,
+            Pixel::texture_format(),
+            options,
+        )
     }
 
-    pub fn new_with_initial_size(device: &wgpu::Device, size: wgpu::Extent3d) -> Self {
+    /// Creates an atlas stored in a format other than the pixel type's default, which must have the same texel size.
+    pub fn with_format(
+        device: &wgpu::Device,
+        label: &'static str,
+        size: wgpu::Extent3d,
+        max_size: u32,
+        format: wgpu::TextureFormat,
+        options: etagere::AllocatorOptions,
+    ) -> Self {
         Self {
+            label,
             texture: device.create_texture(&wgpu::TextureDescriptor {
-                label: Some("canvasette: Atlas"),
+                label: Some(label),
                 size,
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: wgpu::TextureDimension::D2,
-                format: Pixel::texture_format(),
+                format,
                 usage: wgpu::TextureUsages::TEXTURE_BINDING
                     | wgpu::TextureUsages::COPY_DST
                     | wgpu::TextureUsages::COPY_SRC,
                 view_formats: &[],
             }),
-            allocator: etagere::AtlasAllocator::new(etagere::size2(
-                size.width as i32,
-                size.height as i32,
-            )),
+            format,
+            allocator: etagere::AtlasAllocator::with_options(
+                etagere::size2(size.width as i32, size.height as i32),
+                &options,
+            ),
+            options,
+            max_size,
             allocations: HashMap::new(),
             _phantom: std::marker::PhantomData,
         }
     }
 
     fn resize(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, size: wgpu::Extent3d) -> bool {
-        let mut atlas = Self::new_with_initial_size(device, size);
+        let mut atlas = Self::with_format(
+            device,
+            self.label,
+            size,
+            self.max_size,
+            self.format,
+            self.options,
+        );
 
         let mut enc = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("canvasette: Atlas::resize"),
@@ -128,16 +161,19 @@ where
             if let Some(allocation) = self.try_add_without_resizing(queue, key, img) {
                 return Some(allocation);
             }
+            // Grow up to the maximum size, and give up if already there or if the existing allocations do not fit into the grown atlas.
             let size = self.allocator.size();
-            assert!(self.resize(
-                device,
-                queue,
-                wgpu::Extent3d {
-                    width: size.width as u32 * 2,
-                    height: size.height as u32 * 2,
-                    depth_or_array_layers: 1
-                }
-            ));
+            let max_size = self.max_size.min(device.limits().max_texture_dimension_2d);
+            let new_size = wgpu::Extent3d {
+                width: (size.width as u32 * 2).min(max_size),
+                height: (size.height as u32 * 2).min(max_size),
+                depth_or_array_layers: 1,
+            };
+            if (new_size.width, new_size.height) == (size.width as u32, size.height as u32)
+                || !self.resize(device, queue, new_size)
+            {
+                return None;
+            }
         }
     }
 
@@ -183,7 +219,7 @@ where
     }
 
     pub fn remove(&mut self, queue: &wgpu::Queue, key: &K) {
-        let Some(alloc_id) = self.allocations.remove(&key) else {
+        let Some(alloc_id) = self.allocations.remove(key) else {
             return;
         };
         let allocation = self.allocator.get(alloc_id);
@@ -221,7 +257,128 @@ where
         );
     }
 
+    pub fn describe(
+        &self,
+        kind: crate::debug::AtlasKind,
+        size_class: usize,
+        page: usize,
+    ) -> crate::debug::AtlasPageDescription {
+        crate::debug::AtlasPageDescription {
+            kind,
+            size_class,
+            page,
+            texture: crate::debug::TextureDescription::new(&self.texture),
+            allocations: self.allocations.len(),
+            used_area: self.allocator.allocated_space() as u64,
+            free_area: self.allocator.free_space() as u64,
+        }
+    }
+
     pub fn texture(&self) -> &wgpu::Texture {
         &self.texture
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Gets a device shared by every test case, or [`None`] if there is no adapter to test on.
+    pub(crate) fn device() -> Option<&'static (wgpu::Device, wgpu::Queue)> {
+        static DEVICE: std::sync::OnceLock<Option<(wgpu::Device, wgpu::Queue)>> =
+            std::sync::OnceLock::new();
+        DEVICE
+            .get_or_init(|| {
+                let instance = wgpu::Instance::default();
+                let adapter = pollster::block_on(instance.request_adapter(&Default::default()))?;
+                pollster::block_on(
+                    adapter.request_device(
+                        &wgpu::DeviceDescriptor {
+                            required_limits: wgpu::Limits::downlevel_webgl2_defaults()
+                                .using_resolution(adapter.limits()),
+                            ..Default::default()
+                        },
+                        None,
+                    ),
+                )
+                .ok()
+            })
+            .as_ref()
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Add(u8, u32, u32),
+        Remove(u8),
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            (0..16u8, 1..48u32, 1..48u32)
+                .prop_map(|(key, width, height)| Op::Add(key, width, height)),
+            (0..16u8).prop_map(Op::Remove),
+        ]
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn allocations_match_keys(ops in prop::collection::vec(op(), 1..64)) {
+            let Some((device, queue)) = device() else {
+                return Ok(());
+            };
+            let mut atlas = Atlas::<u8, u8>::new(
+                device,
+                "canvasette: test_atlas",
+                wgpu::Extent3d {
+                    width: 64,
+                    height: 64,
+                    depth_or_array_layers: 1,
+                },
+                256,
+                etagere::DEFAULT_OPTIONS,
+            );
+            let mut expected = HashMap::new();
+            for op in ops {
+                match op {
+                    Op::Add(key, width, height) => {
+                        if expected.contains_key(&key) {
+                            continue;
+                        }
+                        let pixels = vec![0xff; (width * height) as usize];
+                        let img = ImgRef::new(&pixels, width as usize, height as usize);
+                        if atlas.add(device, queue, key, img).is_some() {
+                            expected.insert(key, (width, height));
+                        }
+                    }
+                    Op::Remove(key) => {
+                        atlas.remove(queue, &key);
+                        expected.remove(&key);
+                    }
+                }
+
+                let size = atlas.texture().size();
+                let mut rects = vec![];
+                for key in 0..16 {
+                    let allocation = atlas.get(key);
+                    prop_assert_eq!(allocation.is_some(), expected.contains_key(&key));
+                    let (Some(allocation), Some((width, height))) = (allocation, expected.get(&key)) else {
+                        continue;
+                    };
+                    let rect = allocation.rectangle;
+                    prop_assert!(rect.width() as u32 >= *width && rect.height() as u32 >= *height);
+                    prop_assert!(rect.min.x >= 0 && rect.min.y >= 0);
+                    prop_assert!(rect.max.x as u32 <= size.width && rect.max.y as u32 <= size.height);
+                    rects.push(rect);
+                }
+                for (i, a) in rects.iter().enumerate() {
+                    for b in &rects[i + 1..] {
+                        prop_assert!(!a.intersects(b), "{:?} overlaps {:?}", a, b);
+                    }
+                }
+            }
+        }
+    }
+}
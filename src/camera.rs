@@ -0,0 +1,233 @@
+//! Cameras for viewing a world larger than the screen.
+//!
+//! A [`Camera`] produces a view transform that maps world coordinates to target coordinates. Game code sets a [`Camera::target`] and calls [`Camera::update`] once per frame; the camera takes care of smoothing, dead zones and clamping to world bounds.
+
+use glam::*;
+
+/// How the camera moves towards its target.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Follow {
+    /// Jump to the target immediately.
+    #[default]
+    Snap,
+
+    /// Move a fixed fraction of the remaining distance every update.
+    ///
+    /// This is frame-rate dependent: the fraction is applied once per call to [`Camera::update`], regardless of the elapsed time.
+    Lerp(f32),
+
+    /// Exponentially approach the target such that half of the remaining distance is covered every `half_life` seconds.
+    ///
+    /// This is frame-rate independent.
+    Damped {
+        /// Time in seconds to cover half the remaining distance.
+        half_life: f32,
+    },
+}
+
+/// An axis-aligned region of the world, in world units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    /// Top-left corner.
+    pub min: Vec2,
+    /// Bottom-right corner.
+    pub max: Vec2,
+}
+
+impl Bounds {
+    /// Creates new bounds from two corners.
+    pub fn new(min: Vec2, max: Vec2) -> Self {
+        Self {
+            min: min.min(max),
+            max: min.max(max),
+        }
+    }
+
+    /// Size of the bounds.
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    /// Center of the bounds.
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) * 0.5
+    }
+}
+
+/// A 2D camera.
+#[derive(Debug, Clone)]
+pub struct Camera {
+    /// World position at the center of the viewport.
+    pub position: Vec2,
+
+    /// Zoom factor. Values greater than 1 zoom in.
+    pub zoom: f32,
+
+    /// Rotation in radians.
+    pub rotation: f32,
+
+    /// Size of the viewport in target pixels.
+    pub viewport_size: Vec2,
+
+    /// The world position the camera should follow, if any.
+    pub target: Option<Vec2>,
+
+    /// How the camera moves towards [`Camera::target`].
+    pub follow: Follow,
+
+    /// Half-extents of a region around the camera center, in world units, within which the target can move without the camera moving.
+    pub dead_zone: Vec2,
+
+    /// World bounds the visible area is clamped to, if any.
+    ///
+    /// If the visible area is larger than the bounds along an axis, the camera is centered on the bounds along that axis.
+    pub bounds: Option<Bounds>,
+}
+
+impl Camera {
+    /// Creates a new camera centered at the origin for a viewport of the given size.
+    pub fn new(viewport_size: Vec2) -> Self {
+        Self {
+            position: Vec2::ZERO,
+            zoom: 1.0,
+            rotation: 0.0,
+            viewport_size,
+            target: None,
+            follow: Follow::default(),
+            dead_zone: Vec2::ZERO,
+            bounds: None,
+        }
+    }
+
+    /// Size of the visible area in world units, ignoring rotation.
+    pub fn visible_size(&self) -> Vec2 {
+        self.viewport_size / self.zoom
+    }
+
+    /// Advances the camera towards its target by `dt` seconds and applies bounds clamping.
+    pub fn update(&mut self, dt: f32) {
+        if let Some(target) = self.target {
+            let delta = target - self.position;
+            let goal = self.position
+                + Vec2::new(
+                    dead_zone_excess(delta.x, self.dead_zone.x),
+                    dead_zone_excess(delta.y, self.dead_zone.y),
+                );
+
+            self.position = match self.follow {
+                Follow::Snap => goal,
+                Follow::Lerp(t) => self.position.lerp(goal, t.clamp(0.0, 1.0)),
+                Follow::Damped { half_life } => {
+                    if half_life <= 0.0 {
+                        goal
+                    } else {
+                        self.position
+                            .lerp(goal, 1.0 - (-std::f32::consts::LN_2 * dt / half_life).exp())
+                    }
+                }
+            };
+        }
+
+        self.clamp_to_bounds();
+    }
+
+    /// Clamps the camera position such that the visible area stays within [`Camera::bounds`].
+    pub fn clamp_to_bounds(&mut self) {
+        let Some(bounds) = self.bounds else {
+            return;
+        };
+
+        let half = self.visible_size() * 0.5;
+        let center = bounds.center();
+        let size = bounds.size();
+
+        self.position = Vec2::new(
+            if size.x < half.x * 2.0 {
+                center.x
+            } else {
+                self.position
+                    .x
+                    .clamp(bounds.min.x + half.x, bounds.max.x - half.x)
+            },
+            if size.y < half.y * 2.0 {
+                center.y
+            } else {
+                self.position
+                    .y
+                    .clamp(bounds.min.y + half.y, bounds.max.y - half.y)
+            },
+        );
+    }
+
+    /// Gets the view transform, mapping world coordinates to target coordinates.
+    ///
+    /// This is suitable for passing to [`crate::Canvas::set_layer_view`].
+    pub fn view(&self) -> Affine2 {
+        Affine2::from_translation(self.viewport_size * 0.5)
+            * Affine2::from_angle(-self.rotation)
+            * Affine2::from_scale(Vec2::splat(self.zoom))
+            * Affine2::from_translation(-self.position)
+    }
+
+    /// Gets the level of detail appropriate for the current zoom, clamped to `max_level`.
+    ///
+    /// Level 0 is full detail; each subsequent level is half the resolution of the previous one. A level is only selected once the camera is zoomed out far enough that content at that level is drawn at no more than its native size.
+    ///
+    /// Tilemaps with [`crate::tilemap::LodOptions`] pick their level the same way, from the scale they are actually drawn at, which also includes the renderer's scale factor.
+    pub fn lod(&self, max_level: u32) -> u32 {
+        lod_level(self.zoom, max_level)
+    }
+
+    /// Converts a point in world coordinates to target coordinates.
+    pub fn world_to_screen(&self, point: Vec2) -> Vec2 {
+        self.view().transform_point2(point)
+    }
+
+    /// Converts a point in target coordinates to world coordinates.
+    pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
+        self.view().inverse().transform_point2(point)
+    }
+}
+
+/// Gets the level of detail for content drawn at `scale`, clamped to `max_level`, see [`Camera::lod`].
+pub(crate) fn lod_level(scale: f32, max_level: u32) -> u32 {
+    if scale.is_nan() || scale <= 0.0 {
+        return max_level;
+    }
+    ((-scale.log2()).floor().max(0.0) as u32).min(max_level)
+}
+
+fn dead_zone_excess(delta: f32, half_extent: f32) -> f32 {
+    if delta > half_extent {
+        delta - half_extent
+    } else if delta < -half_extent {
+        delta + half_extent
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lod_halves_with_zoom() {
+        let mut camera = Camera::new(Vec2::new(320.0, 240.0));
+        for (zoom, level) in [
+            (1.0, 0),
+            (0.75, 0),
+            (0.5, 1),
+            (0.3, 1),
+            (0.25, 2),
+            (0.01, 3),
+        ] {
+            camera.zoom = zoom;
+            assert_eq!(camera.lod(3), level, "zoom {zoom}");
+        }
+        camera.zoom = 4.0;
+        assert_eq!(camera.lod(3), 0);
+        camera.zoom = f32::NAN;
+        assert_eq!(camera.lod(3), 3);
+    }
+}
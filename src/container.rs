@@ -0,0 +1,217 @@
+//! Loading KTX2 and DDS textures with their pre-generated mip chains and array layers.
+//!
+//! Cube maps are loaded as 2D arrays of their faces. Volume textures and supercompressed KTX2 files are not supported: see [`crate::transcode`] for the latter.
+
+use crate::Image;
+
+/// Errors that can occur while loading a texture container.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    /// The KTX2 file could not be parsed.
+    #[cfg(feature = "ktx2")]
+    #[error("ktx2: {0}")]
+    Ktx2(#[from] ktx2::ParseError),
+
+    /// The DDS file could not be parsed.
+    #[cfg(feature = "dds")]
+    #[error("dds: {0}")]
+    Dds(#[from] ddsfile::Error),
+
+    /// The texture's format has no wgpu equivalent.
+    #[error("unsupported format")]
+    UnsupportedFormat,
+
+    /// The KTX2 file is supercompressed.
+    #[error("supercompressed textures are not supported")]
+    Supercompressed,
+
+    /// The texture is a volume texture.
+    #[error("volume textures are not supported")]
+    Volume,
+
+    /// The texture holds less data than its mip levels and array layers need.
+    #[error("expected {expected} bytes of texture data, got {actual}")]
+    Truncated {
+        /// Length of the mip levels and array layers.
+        expected: usize,
+        /// Length of the data in the file.
+        actual: usize,
+    },
+}
+
+#[cfg(feature = "ktx2")]
+impl Image {
+    /// Loads an image from a KTX2 file, with all of its mip levels and array layers.
+    pub fn from_ktx2(data: &[u8]) -> Result<Self, Error> {
+        let reader = ktx2::Reader::new(data)?;
+        let header = reader.header();
+        if header.supercompression_scheme.is_some() {
+            return Err(Error::Supercompressed);
+        }
+        if header.pixel_depth > 1 {
+            return Err(Error::Volume);
+        }
+        let format = header
+            .format
+            .and_then(ktx2_format)
+            .ok_or(Error::UnsupportedFormat)?;
+
+        // Levels hold every layer and face of one mip level, which is the order wgpu calls mip-major.
+        let pixels = reader.levels().flat_map(|level| level.data).copied();
+        load(
+            pixels.collect(),
+            format,
+            header.pixel_width,
+            header.pixel_height.max(1),
+            header.layer_count.max(1) * header.face_count.max(1),
+            header.level_count.max(1),
+            wgpu::util::TextureDataOrder::MipMajor,
+        )
+    }
+}
+
+#[cfg(feature = "dds")]
+impl Image {
+    /// Loads an image from a DDS file, with all of its mip levels and array layers.
+    pub fn from_dds(data: &[u8]) -> Result<Self, Error> {
+        let dds = ddsfile::Dds::read(data)?;
+        if dds.get_depth() > 1 {
+            return Err(Error::Volume);
+        }
+        let format = match dds.get_dxgi_format() {
+            Some(format) => dxgi_format(format),
+            None => dds.get_d3d_format().and_then(d3d_format),
+        }
+        .ok_or(Error::UnsupportedFormat)?;
+
+        // Arrays of cube maps count cubes rather than faces.
+        let layers = match &dds.header10 {
+            Some(header10) if header10.misc_flag.contains(ddsfile::MiscFlag::TEXTURECUBE) => {
+                header10.array_size * 6
+            }
+            _ => dds.get_num_array_layers(),
+        };
+        let width = dds.get_width();
+        let height = dds.get_height();
+        let levels = dds.get_num_mipmap_levels().max(1);
+
+        // Layers hold their whole mip chain one after the other, which is the order wgpu calls layer-major.
+        load(
+            dds.data,
+            format,
+            width,
+            height,
+            layers.max(1),
+            levels,
+            wgpu::util::TextureDataOrder::LayerMajor,
+        )
+    }
+}
+
+/// Creates an image from the pixels of every mip level and array layer, checking there are enough of them.
+fn load(
+    pixels: Vec<u8>,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    layers: u32,
+    levels: u32,
+    order: wgpu::util::TextureDataOrder,
+) -> Result<Image, Error> {
+    let desc = wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: layers,
+        },
+        mip_level_count: levels,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    };
+    let expected = crate::upload::mip_chain_len(&desc);
+    if pixels.len() < expected {
+        return Err(Error::Truncated {
+            expected,
+            actual: pixels.len(),
+        });
+    }
+    Ok(Image::new(pixels, desc).with_data_order(order))
+}
+
+/// Gets the wgpu equivalent of a KTX2 format.
+#[cfg(feature = "ktx2")]
+fn ktx2_format(format: ktx2::Format) -> Option<wgpu::TextureFormat> {
+    use ktx2::Format;
+
+    Some(match format {
+        Format::R8_UNORM => wgpu::TextureFormat::R8Unorm,
+        Format::R8G8B8A8_UNORM => wgpu::TextureFormat::Rgba8Unorm,
+        Format::R8G8B8A8_SRGB => wgpu::TextureFormat::Rgba8UnormSrgb,
+        Format::B8G8R8A8_UNORM => wgpu::TextureFormat::Bgra8Unorm,
+        Format::B8G8R8A8_SRGB => wgpu::TextureFormat::Bgra8UnormSrgb,
+        Format::R16G16B16A16_SFLOAT => wgpu::TextureFormat::Rgba16Float,
+        Format::R32G32B32A32_SFLOAT => wgpu::TextureFormat::Rgba32Float,
+        Format::BC1_RGBA_UNORM_BLOCK => wgpu::TextureFormat::Bc1RgbaUnorm,
+        Format::BC1_RGBA_SRGB_BLOCK => wgpu::TextureFormat::Bc1RgbaUnormSrgb,
+        Format::BC3_UNORM_BLOCK => wgpu::TextureFormat::Bc3RgbaUnorm,
+        Format::BC3_SRGB_BLOCK => wgpu::TextureFormat::Bc3RgbaUnormSrgb,
+        Format::BC4_UNORM_BLOCK => wgpu::TextureFormat::Bc4RUnorm,
+        Format::BC5_UNORM_BLOCK => wgpu::TextureFormat::Bc5RgUnorm,
+        Format::BC7_UNORM_BLOCK => wgpu::TextureFormat::Bc7RgbaUnorm,
+        Format::BC7_SRGB_BLOCK => wgpu::TextureFormat::Bc7RgbaUnormSrgb,
+        Format::ETC2_R8G8B8A8_UNORM_BLOCK => wgpu::TextureFormat::Etc2Rgba8Unorm,
+        Format::ETC2_R8G8B8A8_SRGB_BLOCK => wgpu::TextureFormat::Etc2Rgba8UnormSrgb,
+        Format::ASTC_4x4_UNORM_BLOCK => wgpu::TextureFormat::Astc {
+            block: wgpu::AstcBlock::B4x4,
+            channel: wgpu::AstcChannel::Unorm,
+        },
+        Format::ASTC_4x4_SRGB_BLOCK => wgpu::TextureFormat::Astc {
+            block: wgpu::AstcBlock::B4x4,
+            channel: wgpu::AstcChannel::UnormSrgb,
+        },
+        _ => return None,
+    })
+}
+
+/// Gets the wgpu equivalent of a DXGI format.
+#[cfg(feature = "dds")]
+fn dxgi_format(format: ddsfile::DxgiFormat) -> Option<wgpu::TextureFormat> {
+    use ddsfile::DxgiFormat;
+
+    Some(match format {
+        DxgiFormat::R8_UNorm => wgpu::TextureFormat::R8Unorm,
+        DxgiFormat::R8G8B8A8_UNorm => wgpu::TextureFormat::Rgba8Unorm,
+        DxgiFormat::R8G8B8A8_UNorm_sRGB => wgpu::TextureFormat::Rgba8UnormSrgb,
+        DxgiFormat::B8G8R8A8_UNorm => wgpu::TextureFormat::Bgra8Unorm,
+        DxgiFormat::B8G8R8A8_UNorm_sRGB => wgpu::TextureFormat::Bgra8UnormSrgb,
+        DxgiFormat::R16G16B16A16_Float => wgpu::TextureFormat::Rgba16Float,
+        DxgiFormat::R32G32B32A32_Float => wgpu::TextureFormat::Rgba32Float,
+        DxgiFormat::BC1_UNorm => wgpu::TextureFormat::Bc1RgbaUnorm,
+        DxgiFormat::BC1_UNorm_sRGB => wgpu::TextureFormat::Bc1RgbaUnormSrgb,
+        DxgiFormat::BC3_UNorm => wgpu::TextureFormat::Bc3RgbaUnorm,
+        DxgiFormat::BC3_UNorm_sRGB => wgpu::TextureFormat::Bc3RgbaUnormSrgb,
+        DxgiFormat::BC4_UNorm => wgpu::TextureFormat::Bc4RUnorm,
+        DxgiFormat::BC5_UNorm => wgpu::TextureFormat::Bc5RgUnorm,
+        DxgiFormat::BC7_UNorm => wgpu::TextureFormat::Bc7RgbaUnorm,
+        DxgiFormat::BC7_UNorm_sRGB => wgpu::TextureFormat::Bc7RgbaUnormSrgb,
+        _ => return None,
+    })
+}
+
+/// Gets the wgpu equivalent of a legacy Direct3D format.
+#[cfg(feature = "dds")]
+fn d3d_format(format: ddsfile::D3DFormat) -> Option<wgpu::TextureFormat> {
+    use ddsfile::D3DFormat;
+
+    Some(match format {
+        D3DFormat::A8B8G8R8 => wgpu::TextureFormat::Rgba8Unorm,
+        D3DFormat::A8R8G8B8 => wgpu::TextureFormat::Bgra8Unorm,
+        D3DFormat::DXT1 => wgpu::TextureFormat::Bc1RgbaUnorm,
+        D3DFormat::DXT5 => wgpu::TextureFormat::Bc3RgbaUnorm,
+        _ => return None,
+    })
+}
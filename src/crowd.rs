@@ -0,0 +1,101 @@
+//! Large numbers of animated sprites sharing a frame table.
+//!
+//! A [`Crowd`] holds a table of frames (texture slices) and a list of instances, each of which picks a frame by index. If all frames come from the same texture, the whole crowd is drawn in a single draw call.
+
+use glam::*;
+
+use crate::{multiply_tint, palette, Canvas, Color, Drawable, Texture, TextureSlice};
+
+/// A single member of a [`Crowd`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Instance {
+    /// Index into the crowd's frame table.
+    pub frame: u32,
+    /// Transform of the instance, relative to the transform the crowd is drawn with.
+    pub transform: Affine2,
+    /// Tint of the instance, multiplied with the tint the crowd is drawn with.
+    pub tint: Color,
+}
+
+impl Instance {
+    /// Creates a new untinted instance.
+    pub fn new(frame: u32, transform: Affine2) -> Self {
+        Self {
+            frame,
+            transform,
+            tint: palette::WHITE,
+        }
+    }
+}
+
+/// Many sprites drawn from a shared frame table.
+pub struct Crowd<'a, T> {
+    frames: Vec<TextureSlice<'a, T>>,
+    instances: Vec<Instance>,
+}
+
+impl<'a, T> Clone for Crowd<'a, T> {
+    fn clone(&self) -> Self {
+        Self {
+            frames: self.frames.clone(),
+            instances: self.instances.clone(),
+        }
+    }
+}
+
+impl<'a, T> Crowd<'a, T>
+where
+    T: Texture,
+{
+    /// Creates a new crowd with the given frame table and no instances.
+    pub fn new(frames: Vec<TextureSlice<'a, T>>) -> Self {
+        Self {
+            frames,
+            instances: vec![],
+        }
+    }
+
+    /// Gets the frame table.
+    pub fn frames(&self) -> &[TextureSlice<'a, T>] {
+        &self.frames
+    }
+
+    /// Gets the instances.
+    pub fn instances(&self) -> &[Instance] {
+        &self.instances
+    }
+
+    /// Gets the instances mutably, e.g. to advance their frames.
+    pub fn instances_mut(&mut self) -> &mut Vec<Instance> {
+        &mut self.instances
+    }
+
+    /// Adds an instance.
+    pub fn push(&mut self, instance: Instance) {
+        self.instances.push(instance);
+    }
+
+    /// Removes all instances.
+    pub fn clear(&mut self) {
+        self.instances.clear();
+    }
+}
+
+impl<'a, T> Drawable<'a> for Crowd<'a, T>
+where
+    T: Texture,
+{
+    /// Draws every instance. Instances with an out-of-range frame index are skipped.
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: Affine2) {
+        for instance in self.instances.iter() {
+            let Some(frame) = self.frames.get(instance.frame as usize) else {
+                continue;
+            };
+            frame.draw(
+                canvas,
+                multiply_tint(tint, instance.tint),
+                transform * instance.transform,
+            );
+        }
+    }
+}
@@ -0,0 +1,399 @@
+//! Descriptions of prepared frames, for debugging and bug reports.
+//!
+//! [`crate::Renderer::describe_frame`] captures what the last prepared frame will draw: the passes it is split into, the batches in each pass, the textures they sample and the sizes of the buffers backing them. The description prints as an indented text dump with [`std::fmt::Display`], or as JSON with [`FrameDescription::to_json`].
+//!
+//! [`crate::Renderer::describe_atlases`] reports how full each atlas page is, and an [`AtlasView`] draws a page on screen.
+
+use std::fmt::Write as _;
+
+use glam::*;
+
+use crate::{BlendMode, Canvas, Color, Command, Drawable};
+
+/// A texture sampled by a batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureDescription {
+    /// Width in texels.
+    pub width: u32,
+    /// Height in texels.
+    pub height: u32,
+    /// Number of array layers.
+    pub layers: u32,
+    /// Texel format.
+    pub format: wgpu::TextureFormat,
+}
+
+impl TextureDescription {
+    pub(crate) fn new(texture: &wgpu::Texture) -> Self {
+        Self {
+            width: texture.width(),
+            height: texture.height(),
+            layers: texture.depth_or_array_layers(),
+            format: texture.format(),
+        }
+    }
+}
+
+/// A single draw call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchDescription {
+    /// Texture the batch samples.
+    pub texture: TextureDescription,
+    /// Blend mode the batch is drawn with.
+    pub blend_mode: BlendMode,
+    /// Special treatment of the batch, e.g. a distortion or material, in debug notation.
+    pub effect: String,
+    /// Whether the batch samples the backdrop, and is therefore skipped by [`crate::Renderer::render`].
+    pub needs_backdrop: bool,
+    /// Scissor rectangle as `[x, y, width, height]`, if clipped.
+    pub scissor: Option<[u32; 4]>,
+    /// Range of indices drawn.
+    pub indices: std::ops::Range<u32>,
+}
+
+/// A render pass, as recorded by [`crate::Renderer::render_to_target`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassDescription {
+    /// Whether the target is copied into the backdrop before the pass begins.
+    pub copies_backdrop: bool,
+    /// Batches drawn in the pass, in order.
+    pub batches: Vec<BatchDescription>,
+}
+
+/// A GPU buffer backing a frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BufferDescription {
+    /// Label of the buffer.
+    pub label: &'static str,
+    /// Allocated size in bytes.
+    pub size: u64,
+    /// Bytes written by the last prepare.
+    pub used: u64,
+}
+
+/// Counts of the work a prepared frame does, cheap enough to read every frame, unlike a [`FrameDescription`].
+///
+/// Opacity groups are prepared into frames of their own, whose counts are included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FrameStats {
+    /// Number of draws on the canvas, counting each opacity group as well as the draws inside it.
+    pub draws: u32,
+    /// Number of batches, i.e. draw calls.
+    pub batches: u32,
+    /// Number of batches sampling a different texture from the batch before them.
+    pub texture_switches: u32,
+    /// Number of glyphs rasterized into the glyph atlases.
+    pub glyphs_rasterized: u32,
+    /// Number of images and dynamic textures uploaded, whether into the image atlas or into textures of their own.
+    pub texture_uploads: u32,
+    /// Number of draws skipped because they would draw nothing, see [`FrameDescription::skipped_draws`].
+    pub skipped_draws: u32,
+    /// Number of draws dropped because the renderer's draw budget was exceeded.
+    pub dropped_draws: u32,
+    /// Number of draws culled because they lay entirely outside the target or their clip rectangle.
+    pub culled_draws: u32,
+    /// What kept each batch from being merged into the batch before it.
+    pub batch_breaks: BatchBreaks,
+}
+
+impl std::ops::AddAssign for FrameStats {
+    fn add_assign(&mut self, other: Self) {
+        self.draws += other.draws;
+        self.batches += other.batches;
+        self.texture_switches += other.texture_switches;
+        self.glyphs_rasterized += other.glyphs_rasterized;
+        self.texture_uploads += other.texture_uploads;
+        self.skipped_draws += other.skipped_draws;
+        self.dropped_draws += other.dropped_draws;
+        self.culled_draws += other.culled_draws;
+        self.batch_breaks += other.batch_breaks;
+    }
+}
+
+/// Counts of what differed between consecutive batches of a frame, and so split them into separate draw calls.
+///
+/// A batch that differs from the one before it in several ways counts towards each of them, so the counts may add up to more than the number of breaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BatchBreaks {
+    /// Batches sampling a different texture, the same as [`FrameStats::texture_switches`]. Packing images into the image atlas avoids these.
+    pub texture: u32,
+    /// Batches drawn with a different [`BlendMode`].
+    pub blend_mode: u32,
+    /// Batches clipped to a different rectangle, or clipped where the batch before was not.
+    pub scissor: u32,
+    /// Batches drawn with a different custom material, or with one where the batch before was not.
+    pub material: u32,
+    /// Batches with a different built-in effect, e.g. a distortion, reflection fade, palette row or mask, or a different palette or mask texture.
+    pub effect: u32,
+}
+
+impl std::ops::AddAssign for BatchBreaks {
+    fn add_assign(&mut self, other: Self) {
+        self.texture += other.texture;
+        self.blend_mode += other.blend_mode;
+        self.scissor += other.scissor;
+        self.material += other.material;
+        self.effect += other.effect;
+    }
+}
+
+/// Everything a prepared frame will draw.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameDescription {
+    /// Size of the target the frame was prepared for.
+    pub target_size: wgpu::Extent3d,
+    /// Format of the target.
+    pub target_format: wgpu::TextureFormat,
+    /// Number of vertices.
+    pub vertices: u32,
+    /// Number of indices.
+    pub indices: u32,
+    /// Buffers backing the frame.
+    pub buffers: Vec<BufferDescription>,
+    /// Passes the frame is split into.
+    pub passes: Vec<PassDescription>,
+    /// Number of draws skipped because they would draw nothing, e.g. zero-sized slices, zero-scale transforms and empty labels.
+    pub skipped_draws: u32,
+    /// Number of draws dropped because the renderer's draw budget was exceeded.
+    pub dropped_draws: u32,
+    /// Number of draws culled because they lay entirely outside the target or their clip rectangle.
+    pub culled_draws: u32,
+}
+
+impl FrameDescription {
+    /// Total number of batches, i.e. draw calls, across all passes.
+    pub fn batch_count(&self) -> usize {
+        self.passes.iter().map(|pass| pass.batches.len()).sum()
+    }
+
+    /// Serializes the description as JSON.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        write!(
+            out,
+            r#"{{"target_size":[{},{}],"target_format":{},"vertices":{},"indices":{},"skipped_draws":{},"dropped_draws":{},"culled_draws":{},"buffers":["#,
+            self.target_size.width,
+            self.target_size.height,
+            json_string(&format!("{:?}", self.target_format)),
+            self.vertices,
+            self.indices,
+            self.skipped_draws,
+            self.dropped_draws,
+            self.culled_draws,
+        )
+        .unwrap();
+        for (i, buffer) in self.buffers.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            write!(
+                out,
+                r#"{{"label":{},"size":{},"used":{}}}"#,
+                json_string(buffer.label),
+                buffer.size,
+                buffer.used,
+            )
+            .unwrap();
+        }
+        out.push_str(r#"],"passes":["#);
+        for (i, pass) in self.passes.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            write!(
+                out,
+                r#"{{"copies_backdrop":{},"batches":["#,
+                pass.copies_backdrop
+            )
+            .unwrap();
+            for (j, batch) in pass.batches.iter().enumerate() {
+                if j > 0 {
+                    out.push(',');
+                }
+                write!(
+                    out,
+                    r#"{{"texture":{{"width":{},"height":{},"layers":{},"format":{}}},"blend_mode":{},"effect":{},"needs_backdrop":{},"scissor":{},"indices":[{},{}]}}"#,
+                    batch.texture.width,
+                    batch.texture.height,
+                    batch.texture.layers,
+                    json_string(&format!("{:?}", batch.texture.format)),
+                    json_string(&format!("{:?}", batch.blend_mode)),
+                    json_string(&batch.effect),
+                    batch.needs_backdrop,
+                    match batch.scissor {
+                        Some([x, y, w, h]) => format!("[{x},{y},{w},{h}]"),
+                        None => "null".to_string(),
+                    },
+                    batch.indices.start,
+                    batch.indices.end,
+                )
+                .unwrap();
+            }
+            out.push_str("]}");
+        }
+        out.push_str("]}");
+        out
+    }
+}
+
+impl std::fmt::Display for FrameDescription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "frame {}x{} {:?}: {} vertices, {} indices, {} passes, {} batches, {} skipped draws, {} dropped draws, {} culled draws",
+            self.target_size.width,
+            self.target_size.height,
+            self.target_format,
+            self.vertices,
+            self.indices,
+            self.passes.len(),
+            self.batch_count(),
+            self.skipped_draws,
+            self.dropped_draws,
+            self.culled_draws,
+        )?;
+        for buffer in self.buffers.iter() {
+            writeln!(
+                f,
+                "  buffer {}: {}/{} bytes",
+                buffer.label, buffer.used, buffer.size
+            )?;
+        }
+        for (i, pass) in self.passes.iter().enumerate() {
+            writeln!(
+                f,
+                "  pass {i}{}",
+                if pass.copies_backdrop {
+                    " (copies backdrop)"
+                } else {
+                    ""
+                }
+            )?;
+            for batch in pass.batches.iter() {
+                write!(
+                    f,
+                    "    batch indices {}..{}: texture {}x{}x{} {:?}, {:?}, {}",
+                    batch.indices.start,
+                    batch.indices.end,
+                    batch.texture.width,
+                    batch.texture.height,
+                    batch.texture.layers,
+                    batch.texture.format,
+                    batch.blend_mode,
+                    batch.effect,
+                )?;
+                if let Some([x, y, w, h]) = batch.scissor {
+                    write!(f, ", scissor {x},{y} {w}x{h}")?;
+                }
+                if batch.needs_backdrop {
+                    write!(f, ", needs backdrop")?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Encodes a string as a JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Which atlas a page belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AtlasKind {
+    /// Glyphs drawn as masks and tinted, i.e. most text.
+    GlyphMask,
+    /// Glyphs with their own colors, such as emoji.
+    GlyphColor,
+    /// Small images packed together, see [`crate::ImageAtlasOptions`].
+    Image,
+}
+
+/// Occupancy of a single atlas page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtlasPageDescription {
+    /// Atlas the page belongs to.
+    pub kind: AtlasKind,
+    /// Size class of the page, see [`crate::GlyphAtlasOptions::size_classes`]. Always 0 for the image atlas.
+    pub size_class: usize,
+    /// Index of the page within its size class. Always 0 for the image atlas.
+    pub page: usize,
+    /// Texture backing the page.
+    pub texture: TextureDescription,
+    /// Number of glyphs or images in the page.
+    pub allocations: usize,
+    /// Area taken by allocations, in texels, including padding for alignment.
+    pub used_area: u64,
+    /// Area not taken by allocations, in texels. It may be too fragmented to fit a large glyph.
+    pub free_area: u64,
+}
+
+impl AtlasPageDescription {
+    /// Fraction of the page's area taken by allocations.
+    pub fn occupancy(&self) -> f32 {
+        let total = self.used_area + self.free_area;
+        if total == 0 {
+            return 0.0;
+        }
+        self.used_area as f32 / total as f32
+    }
+}
+
+/// Draws a whole atlas page stretched over a rectangle, e.g. to see how fragmented it is.
+///
+/// The page is looked up when the canvas is prepared, so it shows the page as it is then. Glyph mask pages are drawn as the tint's color where glyphs are. Pages that do not exist draw nothing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtlasView {
+    kind: AtlasKind,
+    size_class: usize,
+    page: usize,
+    size: Vec2,
+}
+
+impl AtlasView {
+    /// Creates a view of an atlas page, drawn at `size` with its top-left corner at the origin.
+    pub fn new(kind: AtlasKind, size_class: usize, page: usize, size: Vec2) -> Self {
+        Self {
+            kind,
+            size_class,
+            page,
+            size,
+        }
+    }
+}
+
+/// An atlas view staged for drawing.
+pub(crate) struct AtlasViewCommand {
+    pub kind: AtlasKind,
+    pub size_class: usize,
+    pub page: usize,
+    pub size: Vec2,
+    pub transform: Affine2,
+    pub tint: Color,
+}
+
+impl<'a> Drawable<'a> for AtlasView {
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: Affine2) {
+        canvas.push(Command::AtlasView(AtlasViewCommand {
+            kind: self.kind,
+            size_class: self.size_class,
+            page: self.page,
+            size: self.size,
+            transform,
+            tint,
+        }));
+    }
+}
@@ -0,0 +1,215 @@
+//! Decals: marks such as scorch marks or splats left on the world.
+//!
+//! [`Decals`] is a capped ring buffer of decals. Once it is full, adding a decal evicts the oldest one; the oldest decals fade out gradually as they approach eviction so they don't pop. Decals are always drawn oldest first, so newer decals appear on top of older ones, and can be clipped to the bounds of the surface they are projected onto.
+
+use std::collections::VecDeque;
+
+use glam::*;
+
+use crate::{
+    camera::Bounds,
+    mesh::{MeshCommand, MeshVertex},
+    multiply_tint, palette, Canvas, Color, Command, Drawable, Texture, TextureSlice,
+};
+
+/// A single decal.
+pub struct Decal<'a, T> {
+    /// The image of the decal.
+    pub slice: TextureSlice<'a, T>,
+    /// Transform of the decal, in the same space as the bounds of the [`Decals`] it belongs to.
+    pub transform: Affine2,
+    /// Tint of the decal.
+    pub tint: Color,
+}
+
+impl<'a, T> Clone for Decal<'a, T> {
+    fn clone(&self) -> Self {
+        Self {
+            slice: self.slice,
+            transform: self.transform,
+            tint: self.tint,
+        }
+    }
+}
+
+/// A capped ring buffer of decals.
+pub struct Decals<'a, T> {
+    decals: VecDeque<Decal<'a, T>>,
+    capacity: usize,
+    fade: usize,
+    bounds: Option<Bounds>,
+}
+
+impl<'a, T> Clone for Decals<'a, T> {
+    fn clone(&self) -> Self {
+        Self {
+            decals: self.decals.clone(),
+            capacity: self.capacity,
+            fade: self.fade,
+            bounds: self.bounds,
+        }
+    }
+}
+
+impl<'a, T> Decals<'a, T>
+where
+    T: Texture,
+{
+    /// Creates a new ring buffer holding up to `capacity` decals.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            decals: VecDeque::with_capacity(capacity),
+            capacity,
+            fade: 0,
+            bounds: None,
+        }
+    }
+
+    /// Sets how many additions before eviction a decal starts fading out.
+    ///
+    /// A decal that will be evicted after `n` more additions, where `n <= fade`, is drawn with its alpha scaled by `n / (fade + 1)`. The default of 0 disables fading.
+    pub fn set_fade(&mut self, fade: usize) {
+        self.fade = fade;
+    }
+
+    /// Sets the bounds decals are clipped to, e.g. the extent of the surface they are projected onto.
+    pub fn set_bounds(&mut self, bounds: Option<Bounds>) {
+        self.bounds = bounds;
+    }
+
+    /// Adds a decal, evicting the oldest one if the buffer is full.
+    pub fn push(&mut self, decal: Decal<'a, T>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.decals.len() == self.capacity {
+            self.decals.pop_front();
+        }
+        self.decals.push_back(decal);
+    }
+
+    /// Removes all decals.
+    pub fn clear(&mut self) {
+        self.decals.clear();
+    }
+
+    /// Number of decals currently held.
+    pub fn len(&self) -> usize {
+        self.decals.len()
+    }
+
+    /// Whether there are no decals.
+    pub fn is_empty(&self) -> bool {
+        self.decals.is_empty()
+    }
+
+    /// Iterates over decals from oldest to newest.
+    pub fn iter(&self) -> impl Iterator<Item = &Decal<'a, T>> {
+        self.decals.iter()
+    }
+
+    fn fade_alpha(&self, index: usize) -> f32 {
+        let remaining = self.capacity - self.decals.len() + index + 1;
+        if remaining > self.fade {
+            1.0
+        } else {
+            remaining as f32 / (self.fade + 1) as f32
+        }
+    }
+}
+
+/// Clips a convex polygon against an axis-aligned rectangle.
+fn clip(polygon: Vec<Vec2>, bounds: &Bounds) -> Vec<Vec2> {
+    let planes: [(Vec2, f32); 4] = [
+        (Vec2::X, bounds.min.x),
+        (Vec2::NEG_X, -bounds.max.x),
+        (Vec2::Y, bounds.min.y),
+        (Vec2::NEG_Y, -bounds.max.y),
+    ];
+    planes
+        .into_iter()
+        .fold(polygon, |polygon, (normal, offset)| {
+            let mut out = Vec::with_capacity(polygon.len() + 1);
+            for (i, a) in polygon.iter().enumerate() {
+                let b = polygon[(i + 1) % polygon.len()];
+                let da = a.dot(normal) - offset;
+                let db = b.dot(normal) - offset;
+                if da >= 0.0 {
+                    out.push(*a);
+                }
+                if (da >= 0.0) != (db >= 0.0) {
+                    out.push(a.lerp(b, da / (da - db)));
+                }
+            }
+            out
+        })
+}
+
+impl<'a, T> Drawable<'a> for Decals<'a, T>
+where
+    T: Texture,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: Affine2) {
+        for (i, decal) in self.decals.iter().enumerate() {
+            let alpha = self.fade_alpha(i);
+            let tint = multiply_tint(
+                multiply_tint(tint, decal.tint),
+                palette::with_alpha(palette::WHITE, (alpha * 255.0).round() as u8),
+            );
+
+            let Some(bounds) = &self.bounds else {
+                decal.slice.draw(canvas, tint, transform * decal.transform);
+                continue;
+            };
+
+            let size = decal.slice.size().as_vec2();
+            let corners = vec![
+                Vec2::ZERO,
+                Vec2::new(size.x, 0.0),
+                size,
+                Vec2::new(0.0, size.y),
+            ]
+            .into_iter()
+            .map(|p| decal.transform.transform_point2(p))
+            .collect::<Vec<_>>();
+
+            if corners.iter().all(|p| {
+                p.x >= bounds.min.x
+                    && p.x <= bounds.max.x
+                    && p.y >= bounds.min.y
+                    && p.y <= bounds.max.y
+            }) {
+                decal.slice.draw(canvas, tint, transform * decal.transform);
+                continue;
+            }
+
+            if decal.transform.matrix2.determinant() == 0.0 {
+                continue;
+            }
+
+            let polygon = clip(corners, bounds);
+            if polygon.len() < 3 {
+                continue;
+            }
+
+            let inverse = decal.transform.inverse();
+            canvas.push(Command::Mesh(MeshCommand {
+                texture: Some(decal.slice.texture),
+                src_layer: decal.slice.layer,
+                vertices: polygon
+                    .iter()
+                    .map(|p| MeshVertex {
+                        position: *p,
+                        tex_coords: decal.slice.tex_coords(inverse.transform_point2(*p)),
+                        tint: palette::WHITE,
+                    })
+                    .collect(),
+                indices: (1..polygon.len() as u32 - 1)
+                    .flat_map(|i| [0, i, i + 1])
+                    .collect(),
+                transform,
+                tint,
+            }));
+        }
+    }
+}
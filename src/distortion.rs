@@ -0,0 +1,73 @@
+//! Distortion regions: heat haze, water refraction and similar effects.
+//!
+//! A [`Distortion`] redraws the part of the scene rendered so far that lies behind it, with each pixel displaced according to an offset map. Sampling what has already been rendered means the target has to be copied mid-frame, so distortions are only drawn by [`crate::Renderer::render_to_target`]; [`crate::Renderer::render`] skips them.
+
+use glam::*;
+
+use crate::{Canvas, Color, Command, Drawable, Texture, TextureSlice};
+
+/// A region that displaces the scene behind it by an offset map.
+///
+/// The red and green channels of the map give the horizontal and vertical offset, with 0.5 meaning no offset, and 0 and 1 meaning `-strength` and `strength` target pixels. The map should use a non-sRGB format such as [`wgpu::TextureFormat::Rgba8Unorm`] so its values are read unchanged. The map is stretched to cover the region, and the tint is applied to the displaced scene.
+pub struct Distortion<'a, T> {
+    map: TextureSlice<'a, T>,
+    size: Vec2,
+    strength: f32,
+}
+
+impl<'a, T> Clone for Distortion<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for Distortion<'a, T> {}
+
+impl<'a, T> Distortion<'a, T>
+where
+    T: Texture,
+{
+    /// Creates a new distortion region of the given size, with its top-left corner at the origin.
+    pub fn new(map: TextureSlice<'a, T>, size: Vec2, strength: f32) -> Self {
+        Self {
+            map,
+            size,
+            strength,
+        }
+    }
+
+    /// Returns a copy with a different strength.
+    pub fn with_strength(&self, strength: f32) -> Self {
+        Self { strength, ..*self }
+    }
+}
+
+/// A distortion region staged for drawing.
+pub(crate) struct DistortionCommand<'a> {
+    pub texture: &'a dyn Texture,
+    pub src_offset: IVec2,
+    pub src_size: UVec2,
+    pub src_layer: u32,
+    pub size: Vec2,
+    pub strength: f32,
+    pub transform: Affine2,
+    pub tint: Color,
+}
+
+impl<'a, T> Drawable<'a> for Distortion<'a, T>
+where
+    T: Texture,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: Affine2) {
+        canvas.push(Command::Distortion(DistortionCommand {
+            texture: self.map.texture,
+            src_offset: self.map.rect.offset,
+            src_size: self.map.rect.size,
+            src_layer: self.map.layer,
+            size: self.size,
+            strength: self.strength,
+            transform,
+            tint,
+        }));
+    }
+}
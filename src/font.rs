@@ -1,6 +1,29 @@
 //! Various types for fonts.
 
-pub use cosmic_text::{FamilyOwned as Family, Metrics, Stretch, Style, Weight};
+pub use cosmic_text::{Align, FamilyOwned as Family, Metrics, Stretch, Style, Weight, Wrap};
+
+use crate::{Canvas, Color, Drawable, Shape};
+
+/// Creates a font system for the user's locale, with no fonts loaded.
+///
+/// If `system_fonts` is set, the platform's installed fonts are loaded too, so text falls back to them for emoji, CJK and other scripts the app's own fonts do not cover.
+pub fn new_font_system(system_fonts: bool) -> cosmic_text::FontSystem {
+    let mut font_system = cosmic_text::FontSystem::new_with_locale_and_db(
+        sys_locale::get_locale().unwrap_or_else(|| "en-US".to_string()),
+        cosmic_text::fontdb::Database::new(),
+    );
+    if system_fonts {
+        load_system_fonts(&mut font_system);
+    }
+    font_system
+}
+
+/// Loads the platform's installed fonts into a font system, to fall back to for glyphs its other fonts lack.
+///
+/// This scans the system font directories, so it can take a while on the first call.
+pub fn load_system_fonts(font_system: &mut cosmic_text::FontSystem) {
+    font_system.db_mut().load_system_fonts();
+}
 
 /// Font attributes.
 #[derive(Debug, Clone)]
@@ -13,6 +36,22 @@ pub struct Attrs {
     pub style: Style,
     /// Font weight.
     pub weight: Weight,
+    /// Metrics overriding the label's, e.g. for a larger word in rich text.
+    pub metrics: Option<Metrics>,
+}
+
+impl Attrs {
+    pub(crate) fn to_cosmic(&self) -> cosmic_text::Attrs<'_> {
+        let attrs = cosmic_text::Attrs::new()
+            .family(self.family.as_family())
+            .stretch(self.stretch)
+            .style(self.style)
+            .weight(self.weight);
+        match self.metrics {
+            Some(metrics) => attrs.metrics(metrics),
+            None => attrs,
+        }
+    }
 }
 
 impl Default for Attrs {
@@ -22,6 +61,123 @@ impl Default for Attrs {
             stretch: Default::default(),
             style: Default::default(),
             weight: Default::default(),
+            metrics: None,
         }
     }
 }
+
+/// Metrics of a laid out line of text, in pixels from the top-left of the label.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineMetrics {
+    /// Left of the line's glyphs, which is past 0 for aligned lines.
+    pub left: f32,
+    /// Top of the line.
+    pub top: f32,
+    /// Baseline glyphs sit on.
+    pub baseline: f32,
+    /// Height of the line, as set by its metrics.
+    pub height: f32,
+    /// Width of the line's glyphs.
+    pub width: f32,
+    /// Largest distance the line's glyphs reach above the baseline.
+    pub ascent: f32,
+    /// Largest distance the line's glyphs reach below the baseline.
+    pub descent: f32,
+}
+
+impl LineMetrics {
+    /// Gets the top-left corner of the line.
+    pub fn offset(&self) -> glam::Vec2 {
+        glam::Vec2::new(self.left, self.top)
+    }
+
+    /// Gets the size of the line.
+    pub fn size(&self) -> glam::Vec2 {
+        glam::Vec2::new(self.width, self.height)
+    }
+}
+
+/// A laid out glyph cluster, i.e. the glyphs drawn for a grapheme or ligature, in pixels from the top-left of the label.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cluster {
+    /// Byte offset of the start of the cluster in the label's text.
+    pub start: usize,
+    /// Byte offset of the end of the cluster in the label's text.
+    pub end: usize,
+    /// Index of the visual line the cluster is on, as yielded by [`crate::Label::lines`].
+    pub line: usize,
+    /// Top-left corner of the cluster. Its top is the top of its line.
+    pub offset: glam::Vec2,
+    /// Size of the cluster. Its height is the height of its line.
+    pub size: glam::Vec2,
+    /// Whether the cluster is laid out right to left.
+    pub rtl: bool,
+}
+
+impl Cluster {
+    /// Whether a point is within the cluster.
+    pub fn contains(&self, point: glam::Vec2) -> bool {
+        let point = point - self.offset;
+        point.x >= 0.0 && point.y >= 0.0 && point.x < self.size.x && point.y < self.size.y
+    }
+}
+
+/// Paragraph layout options.
+#[derive(Debug, Clone, Copy)]
+pub struct TextOptions {
+    /// Width to wrap and align lines within. [`None`] lays lines out without a width limit.
+    pub width: Option<f32>,
+    /// Height to lay lines out within; lines below it are dropped. [`None`] lays out all lines.
+    pub height: Option<f32>,
+    /// Horizontal alignment of each line. [`None`] aligns lines according to their text direction.
+    ///
+    /// Alignment other than the default needs a width to align within.
+    pub align: Option<Align>,
+    /// How lines wrap when they exceed the width.
+    pub wrap: Wrap,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self {
+            width: None,
+            height: None,
+            align: None,
+            wrap: Wrap::WordOrGlyph,
+        }
+    }
+}
+
+/// Position of a text cursor in a label, in pixels from the top-left of the label.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Caret {
+    /// Top of the cursor.
+    pub offset: glam::Vec2,
+    /// Height of the cursor, which is the height of its line.
+    pub height: f32,
+    /// Index of the visual line the cursor is on, as yielded by [`crate::Label::lines`].
+    pub line: usize,
+}
+
+/// The highlighted part of a line of selected text, in pixels from the top-left of the label.
+///
+/// Highlights are drawn as white rectangles, multiplied by the tint, so are usually drawn [tinted](Drawable::tinted) and before the label.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Highlight {
+    /// Index of the visual line the highlight is on, as yielded by [`crate::Label::lines`].
+    pub line: usize,
+    /// Top-left corner of the highlight.
+    pub offset: glam::Vec2,
+    /// Size of the highlight.
+    pub size: glam::Vec2,
+}
+
+impl<'a> Drawable<'a> for Highlight {
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
+        Shape::rect(self.size).draw(
+            canvas,
+            tint,
+            transform * glam::Affine2::from_translation(self.offset),
+        );
+    }
+}
@@ -0,0 +1,338 @@
+//! Rectangle math shared by texture slices, clip rectangles and the renderer.
+//!
+//! [`Rect`] is on the pixel grid, e.g. for texture slices and clip rectangles, and [`Rectf`] is in world units, e.g. for the bounds of drawables and layout.
+
+use glam::*;
+
+/// An axis-aligned rectangle on the pixel grid, e.g. a texture slice or a clip rectangle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rect {
+    /// Offset of the top-left corner.
+    pub offset: IVec2,
+    /// Size.
+    pub size: UVec2,
+}
+
+impl Rect {
+    /// Creates a new rectangle from its left and top edges and size.
+    pub const fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Self {
+            offset: IVec2::new(x, y),
+            size: UVec2::new(width, height),
+        }
+    }
+
+    /// Gets the left edge.
+    pub const fn left(&self) -> i32 {
+        self.offset.x
+    }
+
+    /// Gets the top edge.
+    pub const fn top(&self) -> i32 {
+        self.offset.y
+    }
+
+    /// Gets the right edge, exclusive.
+    pub const fn right(&self) -> i32 {
+        self.offset.x + self.size.x as i32
+    }
+
+    /// Gets the bottom edge, exclusive.
+    pub const fn bottom(&self) -> i32 {
+        self.offset.y + self.size.y as i32
+    }
+
+    /// Whether the rectangle covers no pixels.
+    pub fn is_empty(&self) -> bool {
+        self.size.x == 0 || self.size.y == 0
+    }
+
+    /// Whether a pixel lies within the rectangle.
+    pub fn contains(&self, point: IVec2) -> bool {
+        point.x >= self.left()
+            && point.x < self.right()
+            && point.y >= self.top()
+            && point.y < self.bottom()
+    }
+
+    /// Whether another rectangle lies entirely within this one.
+    ///
+    /// Empty rectangles are still only contained if their offset is within the edges.
+    pub fn contains_rect(&self, other: &Rect) -> bool {
+        other.left() >= self.left()
+            && other.right() <= self.right()
+            && other.top() >= self.top()
+            && other.bottom() <= self.bottom()
+    }
+
+    /// Gets the overlap of two rectangles.
+    ///
+    /// Rectangles that do not overlap give an empty rectangle, positioned at the nearest corner of the overlap.
+    pub fn intersect(&self, other: &Rect) -> Rect {
+        let left = self.left().max(other.left());
+        let top = self.top().max(other.top());
+        let right = self.right().min(other.right()).max(left);
+        let bottom = self.bottom().min(other.bottom()).max(top);
+        Rect::new(left, top, (right - left) as u32, (bottom - top) as u32)
+    }
+
+    /// Gets the smallest rectangle covering both rectangles.
+    ///
+    /// Empty rectangles still extend the union to their offset.
+    pub fn union(&self, other: &Rect) -> Rect {
+        let left = self.left().min(other.left());
+        let top = self.top().min(other.top());
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+        Rect::new(left, top, (right - left) as u32, (bottom - top) as u32)
+    }
+
+    /// Scales the rectangle, rounding outwards to whole pixels.
+    pub fn scale(&self, factor: f32) -> Rect {
+        if factor == 1.0 {
+            return *self;
+        }
+        self.as_rectf()
+            .transform(Affine2::from_scale(Vec2::splat(factor)))
+            .round_out()
+    }
+
+    /// Converts the rectangle to world units.
+    pub fn as_rectf(&self) -> Rectf {
+        Rectf::new(
+            self.offset.as_vec2(),
+            IVec2::new(self.right(), self.bottom()).as_vec2(),
+        )
+    }
+}
+
+/// An axis-aligned rectangle in world units, e.g. the bounds of a drawable.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rectf {
+    /// Top-left corner.
+    pub min: Vec2,
+    /// Bottom-right corner.
+    pub max: Vec2,
+}
+
+impl Rectf {
+    /// Creates a new rectangle from its corners.
+    pub const fn new(min: Vec2, max: Vec2) -> Self {
+        Self { min, max }
+    }
+
+    /// Creates a new rectangle from its top-left corner and size.
+    pub fn from_offset_size(offset: Vec2, size: Vec2) -> Self {
+        Self::new(offset, offset + size)
+    }
+
+    /// Gets the size.
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    /// Gets the center.
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Whether the rectangle has no area.
+    pub fn is_empty(&self) -> bool {
+        self.max.cmple(self.min).any()
+    }
+
+    /// Whether a point lies within the rectangle, including its top and left edges but not its bottom and right ones.
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.cmpge(self.min).all() && point.cmplt(self.max).all()
+    }
+
+    /// Whether another rectangle lies entirely within this one.
+    pub fn contains_rect(&self, other: &Rectf) -> bool {
+        other.min.cmpge(self.min).all() && other.max.cmple(self.max).all()
+    }
+
+    /// Gets the overlap of two rectangles.
+    ///
+    /// Rectangles that do not overlap give an empty rectangle, positioned at the nearest corner of the overlap.
+    pub fn intersect(&self, other: &Rectf) -> Rectf {
+        let min = self.min.max(other.min);
+        Rectf::new(min, self.max.min(other.max).max(min))
+    }
+
+    /// Gets the smallest rectangle covering both rectangles.
+    pub fn union(&self, other: &Rectf) -> Rectf {
+        Rectf::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    /// Gets the axis-aligned bounding box of the rectangle after a transform.
+    pub fn transform(&self, transform: Affine2) -> Rectf {
+        [
+            self.min,
+            Vec2::new(self.max.x, self.min.y),
+            Vec2::new(self.min.x, self.max.y),
+            self.max,
+        ]
+        .into_iter()
+        .map(|p| transform.transform_point2(p))
+        .fold(
+            Rectf::new(Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+            |bounds, p| Rectf::new(bounds.min.min(p), bounds.max.max(p)),
+        )
+    }
+
+    /// Gets the smallest rectangle of whole pixels covering the rectangle.
+    pub fn round_out(&self) -> Rect {
+        let min = self.min.floor().as_ivec2();
+        let max = self.max.ceil().as_ivec2().max(min);
+        Rect::new(min.x, min.y, (max.x - min.x) as u32, (max.y - min.y) as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn rectf() -> impl Strategy<Value = Rectf> {
+        (
+            -1000.0..1000.0f32,
+            -1000.0..1000.0f32,
+            0.0..1000.0f32,
+            0.0..1000.0f32,
+        )
+            .prop_map(|(x, y, width, height)| {
+                Rectf::from_offset_size(Vec2::new(x, y), Vec2::new(width, height))
+            })
+    }
+
+    fn rect() -> impl Strategy<Value = Rect> {
+        (-1000..1000i32, -1000..1000i32, 0..1000u32, 0..1000u32)
+            .prop_map(|(x, y, width, height)| Rect::new(x, y, width, height))
+    }
+
+    /// Generates rectangles within another one.
+    fn inner(outer: Rect) -> impl Strategy<Value = Rect> {
+        (0..=outer.size.x, 0..=outer.size.y).prop_flat_map(move |(x, y)| {
+            (0..=outer.size.x - x, 0..=outer.size.y - y).prop_map(move |(width, height)| {
+                Rect::new(
+                    outer.left() + x as i32,
+                    outer.top() + y as i32,
+                    width,
+                    height,
+                )
+            })
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn edges_span_size(r in rect()) {
+            prop_assert_eq!((r.right() - r.left()) as u32, r.size.x);
+            prop_assert_eq!((r.bottom() - r.top()) as u32, r.size.y);
+        }
+
+        #[test]
+        fn contains_itself(r in rect()) {
+            prop_assert!(r.contains_rect(&r));
+        }
+
+        #[test]
+        fn intersection_is_commutative(a in rect(), b in rect()) {
+            prop_assert_eq!(a.intersect(&b), b.intersect(&a));
+        }
+
+        #[test]
+        fn intersection_is_contained_in_both(a in rect(), b in rect()) {
+            let i = a.intersect(&b);
+            if !i.is_empty() {
+                prop_assert!(a.contains_rect(&i));
+                prop_assert!(b.contains_rect(&i));
+            }
+        }
+
+        #[test]
+        fn intersection_with_contained_is_identity((a, b) in rect().prop_flat_map(|a| (Just(a), inner(a)))) {
+            prop_assert!(a.contains_rect(&b));
+            prop_assert_eq!(a.intersect(&b), b);
+        }
+
+        #[test]
+        fn intersection_has_overlapping_pixels(a in rect(), b in rect(), x in -1000..2000i32, y in -1000..2000i32) {
+            let p = IVec2::new(x, y);
+            prop_assert_eq!(a.intersect(&b).contains(p), a.contains(p) && b.contains(p));
+        }
+
+        #[test]
+        fn scaling_rounds_outwards(r in rect(), factor in 0.25..4.0f32) {
+            let scaled = r.scale(factor);
+            prop_assert!(scaled.left() as f32 <= r.left() as f32 * factor);
+            prop_assert!(scaled.top() as f32 <= r.top() as f32 * factor);
+            prop_assert!(scaled.right() as f32 >= r.right() as f32 * factor);
+            prop_assert!(scaled.bottom() as f32 >= r.bottom() as f32 * factor);
+        }
+
+        #[test]
+        fn scaling_by_one_is_identity(r in rect()) {
+            prop_assert_eq!(r.scale(1.0), r);
+        }
+
+        #[test]
+        fn union_contains_both(a in rect(), b in rect()) {
+            let u = a.union(&b);
+            prop_assert!(u.contains_rect(&a) && u.contains_rect(&b));
+            prop_assert_eq!(u, b.union(&a));
+        }
+
+        #[test]
+        fn rounding_whole_pixels_is_identity(r in rect()) {
+            prop_assert_eq!(r.as_rectf().round_out(), r);
+        }
+
+        #[test]
+        fn rounding_out_covers(r in rectf()) {
+            prop_assert!(r.round_out().as_rectf().contains_rect(&r));
+        }
+
+        #[test]
+        fn float_intersection_is_contained_in_both(a in rectf(), b in rectf()) {
+            let i = a.intersect(&b);
+            prop_assert!(i.size().cmpge(Vec2::ZERO).all());
+            if !i.is_empty() {
+                prop_assert!(a.contains_rect(&i) && b.contains_rect(&i));
+            }
+        }
+
+        #[test]
+        fn float_union_contains_both(a in rectf(), b in rectf()) {
+            let u = a.union(&b);
+            prop_assert!(u.contains_rect(&a) && u.contains_rect(&b));
+        }
+
+        #[test]
+        fn transformed_bounds_contain_transformed_points(
+            r in rectf(),
+            t in (0.0..1.0f32, 0.0..1.0f32),
+            angle in -std::f32::consts::PI..std::f32::consts::PI,
+            scale in (-4.0..4.0f32, -4.0..4.0f32),
+            translation in (-1000.0..1000.0f32, -1000.0..1000.0f32),
+        ) {
+            let transform = Affine2::from_scale_angle_translation(
+                Vec2::new(scale.0, scale.1),
+                angle,
+                Vec2::new(translation.0, translation.1),
+            );
+            let bounds = r.transform(transform);
+            let p = transform.transform_point2(r.min + r.size() * Vec2::new(t.0, t.1));
+            let slack = Vec2::splat(1e-2);
+            prop_assert!(p.cmpge(bounds.min - slack).all() && p.cmple(bounds.max + slack).all());
+        }
+
+        #[test]
+        fn translating_moves_bounds(r in rectf(), offset in (-1000.0..1000.0f32, -1000.0..1000.0f32)) {
+            let offset = Vec2::new(offset.0, offset.1);
+            let moved = r.transform(Affine2::from_translation(offset));
+            prop_assert!(moved.min.abs_diff_eq(r.min + offset, 1e-3));
+            prop_assert!(moved.max.abs_diff_eq(r.max + offset, 1e-3));
+        }
+    }
+}
@@ -0,0 +1,1476 @@
+use glam::*;
+
+/// A single vertex.
+///
+/// Texture coordinates are in texels, not normalized.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Vertex {
+    pub position: [f32; 2],
+    pub tex_coords: [f32; 2],
+    pub layer: u32,
+    pub tint: [f32; 4],
+}
+
+impl Vertex {
+    const BUFFER_LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Uint32, 3 => Float32x4],
+    };
+
+    pub fn new(position: Vec2, tex_coords: Vec2, layer: u32, tint: crate::Color) -> Self {
+        Self {
+            position: position.to_array(),
+            tex_coords: tex_coords.to_array(),
+            layer,
+            tint: [
+                tint.r as f32 / 255.0,
+                tint.g as f32 / 255.0,
+                tint.b as f32 / 255.0,
+                tint.a as f32 / 255.0,
+            ],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct TextureUniforms {
+    size: [f32; 3],
+    is_mask: u32,
+    premultiplied: u32,
+    distortion_strength: f32,
+    fade_origin: [f32; 2],
+    fade_direction: [f32; 2],
+    palette_origin: [u32; 2],
+    palette_layer: u32,
+    palette_width: u32,
+    _padding: [u32; 2],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct TargetUniforms {
+    size: [f32; 3],
+    _padding: u32,
+}
+
+/// Indexed triangle geometry for a frame.
+#[derive(Default)]
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    /// Number of indices pushed so far.
+    pub fn index_count(&self) -> u32 {
+        self.indices.len() as u32
+    }
+
+    /// Pushes a textured quad covering the given source rectangle, placed by `transform`.
+    ///
+    /// `flip` mirrors the texture coordinates along each axis without moving the quad.
+    pub fn push_quad(
+        &mut self,
+        src_offset: IVec2,
+        src_size: UVec2,
+        src_layer: u32,
+        flip: BVec2,
+        transform: Affine2,
+        tint: crate::Color,
+    ) {
+        self.push_quad_with_tints(src_offset, src_size, src_layer, flip, transform, [tint; 4]);
+    }
+
+    /// Pushes a quad with a tint per corner, ordered top-left, top-right, bottom-left, bottom-right.
+    pub fn push_quad_with_tints(
+        &mut self,
+        src_offset: IVec2,
+        src_size: UVec2,
+        src_layer: u32,
+        flip: BVec2,
+        transform: Affine2,
+        tints: [crate::Color; 4],
+    ) {
+        let size = src_size.as_vec2();
+        let mut tl = src_offset.as_vec2();
+        let mut br = tl + size;
+        if flip.x {
+            std::mem::swap(&mut tl.x, &mut br.x);
+        }
+        if flip.y {
+            std::mem::swap(&mut tl.y, &mut br.y);
+        }
+        self.push(
+            [
+                Vertex::new(
+                    transform.transform_point2(Vec2::ZERO),
+                    tl,
+                    src_layer,
+                    tints[0],
+                ),
+                Vertex::new(
+                    transform.transform_point2(Vec2::new(0.0, size.y)),
+                    Vec2::new(tl.x, br.y),
+                    src_layer,
+                    tints[2],
+                ),
+                Vertex::new(
+                    transform.transform_point2(Vec2::new(size.x, 0.0)),
+                    Vec2::new(br.x, tl.y),
+                    src_layer,
+                    tints[1],
+                ),
+                Vertex::new(transform.transform_point2(size), br, src_layer, tints[3]),
+            ],
+            [0, 1, 2, 1, 2, 3],
+        );
+    }
+
+    /// Pushes arbitrary triangles. Indices are relative to the first pushed vertex.
+    pub fn push(
+        &mut self,
+        vertices: impl IntoIterator<Item = Vertex>,
+        indices: impl IntoIterator<Item = u32>,
+    ) {
+        let offset = self.vertices.len() as u32;
+        self.vertices.extend(vertices);
+        self.indices.extend(indices.into_iter().map(|i| i + offset));
+    }
+}
+
+/// Special treatment of a batch beyond drawing its texture.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Effect {
+    #[default]
+    None,
+
+    /// Draws the backdrop (the target as rendered so far) instead, with each sample offset by the texture's red and green channels mapped to `[-strength, strength]` pixels.
+    Distort { strength: f32 },
+
+    /// Draws with the material at the given index, see [`Renderer::create_material`].
+    Material(u32),
+
+    /// Fades the output out linearly away from `origin`, reaching 0 where the dot product of `direction` with the offset from `origin` (both in target pixels) reaches 1.
+    Fade { origin: Vec2, direction: Vec2 },
+
+    /// Draws the texture's red channel as indices into a row of the batch's palette texture, `width` texels long and starting at `origin` in array layer `layer`.
+    Palette {
+        origin: UVec2,
+        layer: u32,
+        width: u32,
+    },
+
+    /// Draws the batch's secondary texture, which covers the whole target, through the alpha of the texture.
+    Mask,
+}
+
+/// Pipelines for a user-supplied fragment shader.
+struct MaterialPipelines {
+    /// One pipeline per blend mode, in the order of [`crate::BlendMode::ALL`].
+    pipelines: Vec<wgpu::RenderPipeline>,
+    backdrop: bool,
+    /// Whether the material declares extra bind groups, which only a render hook can set.
+    extra_bind_groups: bool,
+}
+
+/// Called before drawing each batch, e.g. to set the extra bind groups of its material.
+pub type Hook<'a> = &'a mut dyn FnMut(&crate::BatchInfo, &mut wgpu::RenderPass<'_>);
+
+/// Entry point calling a material's `material` function.
+const MATERIAL_ENTRY_POINT: &str = r#"
+@fragment
+fn fs_material(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = material(in, unpremultiply(sample_texture(in)) * in.tint);
+    return vec4(color.rgb * color.a, color.a);
+}
+"#;
+
+/// Runs `f` within out-of-memory and validation error scopes, returning the first error it caused instead of passing it to the device's uncaptured error handler.
+///
+/// Error scopes only resolve immediately on native backends, so on the web errors are left to the handler.
+pub fn error_scope<T>(device: &wgpu::Device, f: impl FnOnce() -> T) -> Result<T, wgpu::Error> {
+    if cfg!(target_arch = "wasm32") {
+        return Ok(f());
+    }
+    device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let value = f();
+    let validation = pop_error_scope(device);
+    let out_of_memory = pop_error_scope(device);
+    match out_of_memory.or(validation) {
+        Some(error) => Err(error),
+        None => Ok(value),
+    }
+}
+
+/// Pops an error scope, which native backends resolve without waiting.
+fn pop_error_scope(device: &wgpu::Device) -> Option<wgpu::Error> {
+    let future = std::pin::pin!(device.pop_error_scope());
+    match std::future::Future::poll(
+        future,
+        &mut std::task::Context::from_waker(std::task::Waker::noop()),
+    ) {
+        std::task::Poll::Ready(error) => error,
+        std::task::Poll::Pending => None,
+    }
+}
+
+fn create_sampler(device: &wgpu::Device, options: crate::SamplerOptions) -> wgpu::Sampler {
+    device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("canvasette: sampler"),
+        address_mode_u: options.address_mode,
+        address_mode_v: options.address_mode,
+        address_mode_w: options.address_mode,
+        mag_filter: options.filter,
+        min_filter: options.filter,
+        mipmap_filter: options.filter,
+        ..Default::default()
+    })
+}
+
+/// Attachments pipelines render to.
+#[derive(Clone, Copy)]
+struct PipelineTarget {
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_render_pipeline(
+    device: &wgpu::Device,
+    cache: Option<&wgpu::PipelineCache>,
+    label: &str,
+    shader: &wgpu::ShaderModule,
+    layout: &wgpu::PipelineLayout,
+    entry_point: &str,
+    target: PipelineTarget,
+    blend: wgpu::BlendState,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        cache,
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            buffers: &[Vertex::BUFFER_LAYOUT],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some(entry_point),
+            compilation_options: Default::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: target.format,
+                blend: Some(blend),
+                write_mask: wgpu::ColorWrites::all(),
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: target.sample_count,
+            ..Default::default()
+        },
+        multiview: None,
+    })
+}
+
+/// A range of indices in a [`Mesh`] drawn with a single texture.
+pub struct Batch<'a> {
+    pub texture: &'a wgpu::Texture,
+    pub blend_mode: crate::BlendMode,
+    pub effect: Effect,
+    /// Second texture the effect samples: the palette for [`Effect::Palette`], or the masked contents for [`Effect::Mask`].
+    pub secondary: Option<&'a wgpu::Texture>,
+    /// Scissor rectangle as `[x, y, width, height]`, which must lie within the target. [`None`] draws to the whole target.
+    pub scissor: Option<[u32; 4]>,
+    pub indices: std::ops::Range<u32>,
+}
+
+struct DynamicBuffer {
+    inner: wgpu::Buffer,
+    label: &'static str,
+    /// Bytes written by the last write.
+    len: u64,
+}
+
+impl DynamicBuffer {
+    fn new(device: &wgpu::Device, label: &'static str, usage: wgpu::BufferUsages) -> Self {
+        Self {
+            inner: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: 1024,
+                usage: usage | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            label,
+            len: 0,
+        }
+    }
+
+    fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[u8]) {
+        let size = data.len() as u64;
+        if self.inner.size() < size {
+            self.inner = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(self.label),
+                size: size.next_power_of_two(),
+                usage: self.inner.usage(),
+                mapped_at_creation: false,
+            });
+        }
+        queue.write_buffer(&self.inner, 0, data);
+        self.len = size;
+    }
+
+    fn describe(&self) -> crate::debug::BufferDescription {
+        crate::debug::BufferDescription {
+            label: self.label,
+            size: self.inner.size(),
+            used: self.len,
+        }
+    }
+}
+
+struct PreparedBatch {
+    texture_bind_group: wgpu::BindGroup,
+    secondary_bind_group: Option<wgpu::BindGroup>,
+    texture: crate::debug::TextureDescription,
+    blend_mode: crate::BlendMode,
+    effect: Effect,
+    scissor: Option<[u32; 4]>,
+    indices: std::ops::Range<u32>,
+}
+
+/// Blend state for a blend mode.
+///
+/// The shader always outputs premultiplied color, so every mode uses premultiplied blend factors.
+fn blend_state(blend_mode: crate::BlendMode) -> wgpu::BlendState {
+    let keep_alpha = wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::Zero,
+        dst_factor: wgpu::BlendFactor::One,
+        operation: wgpu::BlendOperation::Add,
+    };
+    match blend_mode {
+        crate::BlendMode::Alpha | crate::BlendMode::Premultiplied => {
+            wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING
+        }
+        crate::BlendMode::Additive => wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: keep_alpha,
+        },
+        crate::BlendMode::Multiply => wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::Dst,
+                dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: keep_alpha,
+        },
+    }
+}
+
+/// Encapsulates GPU state for drawing textured triangles.
+pub struct Renderer {
+    /// One pipeline per blend mode, in the order of [`crate::BlendMode::ALL`].
+    render_pipelines: Vec<wgpu::RenderPipeline>,
+    distort_pipeline: wgpu::RenderPipeline,
+    mask_pipeline: wgpu::RenderPipeline,
+    /// One pipeline per blend mode, in the order of [`crate::BlendMode::ALL`].
+    palette_pipelines: Vec<wgpu::RenderPipeline>,
+    materials: Vec<MaterialPipelines>,
+    render_pipeline_layout: wgpu::PipelineLayout,
+    /// Layout for pipelines that additionally bind the backdrop.
+    backdrop_pipeline_layout: wgpu::PipelineLayout,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    target_uniforms_bind_group_layout: wgpu::BindGroupLayout,
+    backdrop_bind_group_layout: wgpu::BindGroupLayout,
+    palette_bind_group_layout: wgpu::BindGroupLayout,
+    target: PipelineTarget,
+    pipeline_cache: Option<wgpu::PipelineCache>,
+    sampler: wgpu::Sampler,
+    /// Whether every texture is sampled as premultiplied, see [`crate::RendererOptions::premultiplied_alpha`].
+    premultiplied_alpha: bool,
+    /// Buffers the next [`Renderer::prepare`] writes to and [`Renderer::render`] reads from.
+    pub frame: Frame,
+}
+
+/// Buffers and bind groups for a single prepared frame.
+///
+/// Each target that is rendered to within the same submission needs its own frame, as buffer writes only take effect on submission.
+pub struct Frame {
+    target_uniforms_buffer: wgpu::Buffer,
+    target_uniforms_bind_group: wgpu::BindGroup,
+    texture_uniforms_buffer: DynamicBuffer,
+    vertex_buffer: DynamicBuffer,
+    index_buffer: DynamicBuffer,
+    prepared_batches: Vec<PreparedBatch>,
+    target_size: wgpu::Extent3d,
+    backdrop: Option<Backdrop>,
+    /// Counts of the work done preparing the frame.
+    pub stats: crate::debug::FrameStats,
+}
+
+/// A copy of the target as rendered so far, for effects that sample what is behind them.
+struct Backdrop {
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+}
+
+impl Frame {
+    fn new(
+        device: &wgpu::Device,
+        target_uniforms_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let target_uniforms_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("canvasette: target_uniforms_buffer"),
+            size: std::mem::size_of::<TargetUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let target_uniforms_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("canvasette: target_uniforms_bind_group"),
+            layout: target_uniforms_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: target_uniforms_buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            target_uniforms_buffer,
+            target_uniforms_bind_group,
+            texture_uniforms_buffer: DynamicBuffer::new(
+                device,
+                "canvasette: texture_uniforms_buffer",
+                wgpu::BufferUsages::UNIFORM,
+            ),
+            vertex_buffer: DynamicBuffer::new(
+                device,
+                "canvasette: vertex_buffer",
+                wgpu::BufferUsages::VERTEX,
+            ),
+            index_buffer: DynamicBuffer::new(
+                device,
+                "canvasette: index_buffer",
+                wgpu::BufferUsages::INDEX,
+            ),
+            prepared_batches: vec![],
+            target_size: wgpu::Extent3d::default(),
+            backdrop: None,
+            stats: Default::default(),
+        }
+    }
+}
+
+impl Renderer {
+    pub fn new(
+        device: &wgpu::Device,
+        texture_format: wgpu::TextureFormat,
+        sample_count: u32,
+        sampler: crate::SamplerOptions,
+        premultiplied_alpha: bool,
+        pipeline_cache: Option<wgpu::PipelineCache>,
+    ) -> Self {
+        let target = PipelineTarget {
+            format: texture_format,
+            sample_count,
+        };
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("canvasette: shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
+        });
+
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("canvasette: texture_bind_group_layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2Array,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let target_uniforms_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("canvasette: target_uniforms_bind_group_layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("canvasette: render_pipeline.layout"),
+                bind_group_layouts: &[
+                    &texture_bind_group_layout,
+                    &target_uniforms_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+
+        let backdrop_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("canvasette: backdrop_bind_group_layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                }],
+            });
+
+        let palette_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("canvasette: palette_bind_group_layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                }],
+            });
+
+        let palette_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("canvasette: palette_pipeline.layout"),
+                bind_group_layouts: &[
+                    &texture_bind_group_layout,
+                    &target_uniforms_bind_group_layout,
+                    &palette_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+
+        let backdrop_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("canvasette: backdrop_pipeline.layout"),
+                bind_group_layouts: &[
+                    &texture_bind_group_layout,
+                    &target_uniforms_bind_group_layout,
+                    &backdrop_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+
+        let render_pipelines = crate::BlendMode::ALL
+            .iter()
+            .map(|&blend_mode| {
+                create_render_pipeline(
+                    device,
+                    pipeline_cache.as_ref(),
+                    "canvasette: render_pipeline",
+                    &shader,
+                    &render_pipeline_layout,
+                    "fs_main",
+                    target,
+                    blend_state(blend_mode),
+                )
+            })
+            .collect();
+
+        let palette_pipelines = crate::BlendMode::ALL
+            .iter()
+            .map(|&blend_mode| {
+                create_render_pipeline(
+                    device,
+                    pipeline_cache.as_ref(),
+                    "canvasette: palette_pipeline",
+                    &shader,
+                    &palette_pipeline_layout,
+                    "fs_palette",
+                    target,
+                    blend_state(blend_mode),
+                )
+            })
+            .collect();
+
+        let distort_pipeline = create_render_pipeline(
+            device,
+            pipeline_cache.as_ref(),
+            "canvasette: distort_pipeline",
+            &shader,
+            &backdrop_pipeline_layout,
+            "fs_distort",
+            target,
+            wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+        );
+
+        let mask_pipeline = create_render_pipeline(
+            device,
+            pipeline_cache.as_ref(),
+            "canvasette: mask_pipeline",
+            &shader,
+            &backdrop_pipeline_layout,
+            "fs_mask",
+            target,
+            wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+        );
+
+        Self {
+            render_pipelines,
+            distort_pipeline,
+            mask_pipeline,
+            palette_pipelines,
+            materials: vec![],
+            render_pipeline_layout,
+            backdrop_pipeline_layout,
+            texture_bind_group_layout,
+            backdrop_bind_group_layout,
+            palette_bind_group_layout,
+            frame: Frame::new(device, &target_uniforms_bind_group_layout),
+            target_uniforms_bind_group_layout,
+            target,
+            pipeline_cache,
+            sampler: create_sampler(device, sampler),
+            premultiplied_alpha,
+        }
+    }
+
+    /// Replaces the sampler used by batches prepared from now on.
+    pub fn set_sampler(&mut self, device: &wgpu::Device, sampler: crate::SamplerOptions) {
+        self.sampler = create_sampler(device, sampler);
+    }
+
+    /// Gets the format of the textures the pipelines render to.
+    pub fn texture_format(&self) -> wgpu::TextureFormat {
+        self.target.format
+    }
+
+    /// Gets the cache pipelines are compiled with, if any.
+    pub fn pipeline_cache(&self) -> Option<&wgpu::PipelineCache> {
+        self.pipeline_cache.as_ref()
+    }
+
+    /// Gets the number of samples per pixel of the textures the pipelines render to.
+    pub fn sample_count(&self) -> u32 {
+        self.target.sample_count
+    }
+
+    /// Compiles a material from WGSL source defining a `material` function, returning its index.
+    ///
+    /// The source is appended to the built-in shader, so it may use its bindings and helpers. If `backdrop` is set, the material's pipelines also bind the backdrop. Extra bind group layouts follow the built-in ones. Materials that fail to compile are not added.
+    pub fn create_material(
+        &mut self,
+        device: &wgpu::Device,
+        label: Option<&str>,
+        source: &str,
+        backdrop: bool,
+        extra_bind_group_layouts: &[&wgpu::BindGroupLayout],
+    ) -> Result<u32, wgpu::Error> {
+        let label = label.unwrap_or("canvasette: material");
+        let material = error_scope(device, || {
+            let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some(label),
+                source: wgpu::ShaderSource::Wgsl(
+                    [include_str!("shader.wgsl"), MATERIAL_ENTRY_POINT, source]
+                        .concat()
+                        .into(),
+                ),
+            });
+            let extended_layout;
+            let layout = if !extra_bind_group_layouts.is_empty() {
+                let mut bind_group_layouts = vec![
+                    &self.texture_bind_group_layout,
+                    &self.target_uniforms_bind_group_layout,
+                ];
+                if backdrop {
+                    bind_group_layouts.push(&self.backdrop_bind_group_layout);
+                }
+                bind_group_layouts.extend_from_slice(extra_bind_group_layouts);
+                extended_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("canvasette: material_pipeline.layout"),
+                    bind_group_layouts: &bind_group_layouts,
+                    push_constant_ranges: &[],
+                });
+                &extended_layout
+            } else if backdrop {
+                &self.backdrop_pipeline_layout
+            } else {
+                &self.render_pipeline_layout
+            };
+            MaterialPipelines {
+                pipelines: crate::BlendMode::ALL
+                    .iter()
+                    .map(|&blend_mode| {
+                        create_render_pipeline(
+                            device,
+                            self.pipeline_cache.as_ref(),
+                            label,
+                            &shader,
+                            layout,
+                            "fs_material",
+                            self.target,
+                            blend_state(blend_mode),
+                        )
+                    })
+                    .collect(),
+                backdrop,
+                extra_bind_groups: !extra_bind_group_layouts.is_empty(),
+            }
+        })?;
+        self.materials.push(material);
+        Ok(self.materials.len() as u32 - 1)
+    }
+
+    /// Whether batches with the given effect have extra bind groups for a render hook to set.
+    fn needs_hook(&self, effect: Effect) -> bool {
+        match effect {
+            Effect::Material(i) => self
+                .materials
+                .get(i as usize)
+                .is_some_and(|material| material.extra_bind_groups),
+            _ => false,
+        }
+    }
+
+    /// Whether batches with the given effect sample the backdrop.
+    fn needs_backdrop(&self, effect: Effect) -> bool {
+        match effect {
+            Effect::None | Effect::Fade { .. } | Effect::Palette { .. } | Effect::Mask => false,
+            Effect::Distort { .. } => true,
+            Effect::Material(i) => self
+                .materials
+                .get(i as usize)
+                .is_some_and(|material| material.backdrop),
+        }
+    }
+
+    /// Creates a new frame, e.g. for an additional render target.
+    pub fn create_frame(&self, device: &wgpu::Device) -> Frame {
+        Frame::new(device, &self.target_uniforms_bind_group_layout)
+    }
+
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        target_size: wgpu::Extent3d,
+        mesh: &Mesh,
+        batches: &[Batch<'_>],
+    ) {
+        queue.write_buffer(
+            &self.frame.target_uniforms_buffer,
+            0,
+            bytemuck::bytes_of(&TargetUniforms {
+                size: [target_size.width as f32, target_size.height as f32, 0.0],
+                _padding: 0,
+            }),
+        );
+
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as usize;
+
+        let mut texture_uniforms = vec![0u8; batches.len() * alignment];
+        for (i, batch) in batches.iter().enumerate() {
+            let uniforms = TextureUniforms {
+                size: [
+                    batch.texture.width() as f32,
+                    batch.texture.height() as f32,
+                    0.0,
+                ],
+                is_mask: (batch.texture.format() == wgpu::TextureFormat::R8Unorm) as u32,
+                premultiplied: (self.premultiplied_alpha
+                    || batch.blend_mode == crate::BlendMode::Premultiplied)
+                    as u32,
+                distortion_strength: match batch.effect {
+                    Effect::Distort { strength } => strength,
+                    _ => 0.0,
+                },
+                fade_origin: match batch.effect {
+                    Effect::Fade { origin, .. } => origin.to_array(),
+                    _ => [0.0; 2],
+                },
+                fade_direction: match batch.effect {
+                    Effect::Fade { direction, .. } => direction.to_array(),
+                    _ => [0.0; 2],
+                },
+                palette_origin: match batch.effect {
+                    Effect::Palette { origin, .. } => origin.to_array(),
+                    _ => [0; 2],
+                },
+                palette_layer: match batch.effect {
+                    Effect::Palette { layer, .. } => layer,
+                    _ => 0,
+                },
+                palette_width: match batch.effect {
+                    Effect::Palette { width, .. } => width,
+                    _ => 0,
+                },
+                _padding: [0; 2],
+            };
+            let offset = i * alignment;
+            texture_uniforms[offset..offset + std::mem::size_of::<TextureUniforms>()]
+                .copy_from_slice(bytemuck::bytes_of(&uniforms));
+        }
+        self.frame
+            .texture_uniforms_buffer
+            .write(device, queue, &texture_uniforms);
+
+        self.frame
+            .vertex_buffer
+            .write(device, queue, bytemuck::cast_slice(&mesh.vertices[..]));
+        self.frame
+            .index_buffer
+            .write(device, queue, bytemuck::cast_slice(&mesh.indices[..]));
+
+        if batches
+            .iter()
+            .any(|batch| self.needs_backdrop(batch.effect))
+            && self
+                .frame
+                .backdrop
+                .as_ref()
+                .is_none_or(|backdrop| backdrop.texture.size() != target_size)
+        {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("canvasette: backdrop"),
+                size: target_size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: self.target.format,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("canvasette: backdrop_bind_group"),
+                layout: &self.backdrop_bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(
+                        &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                    ),
+                }],
+            });
+            self.frame.backdrop = Some(Backdrop {
+                texture,
+                bind_group,
+            });
+        }
+
+        self.frame.target_size = target_size;
+        self.frame.prepared_batches.clear();
+        for (i, batch) in batches.iter().enumerate() {
+            self.frame.prepared_batches.push(PreparedBatch {
+                texture_bind_group: device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("canvasette: texture_bind_group"),
+                    layout: &self.texture_bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(
+                                &batch.texture.create_view(&wgpu::TextureViewDescriptor {
+                                    dimension: Some(wgpu::TextureViewDimension::D2Array),
+                                    ..Default::default()
+                                }),
+                            ),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(&self.sampler),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                                buffer: &self.frame.texture_uniforms_buffer.inner,
+                                offset: (i * alignment) as u64,
+                                size: wgpu::BufferSize::new(
+                                    std::mem::size_of::<TextureUniforms>() as u64
+                                ),
+                            }),
+                        },
+                    ],
+                }),
+                secondary_bind_group: batch.secondary.map(|secondary| match batch.effect {
+                    // The masked contents take the place of the backdrop.
+                    Effect::Mask => device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: Some("canvasette: mask_bind_group"),
+                        layout: &self.backdrop_bind_group_layout,
+                        entries: &[wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(
+                                &secondary.create_view(&wgpu::TextureViewDescriptor::default()),
+                            ),
+                        }],
+                    }),
+                    _ => device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: Some("canvasette: palette_bind_group"),
+                        layout: &self.palette_bind_group_layout,
+                        entries: &[wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::TextureView(&secondary.create_view(
+                                &wgpu::TextureViewDescriptor {
+                                    dimension: Some(wgpu::TextureViewDimension::D2Array),
+                                    ..Default::default()
+                                },
+                            )),
+                        }],
+                    }),
+                }),
+                texture: crate::debug::TextureDescription::new(batch.texture),
+                blend_mode: batch.blend_mode,
+                effect: batch.effect,
+                scissor: batch.scissor,
+                indices: batch.indices.clone(),
+            });
+        }
+    }
+
+    /// Describes a prepared frame, split into passes as [`Renderer::render_to_texture`] would record it.
+    pub fn describe(&self, frame: &Frame) -> crate::debug::FrameDescription {
+        let mut passes: Vec<crate::debug::PassDescription> = vec![];
+        for batch in frame.prepared_batches.iter() {
+            let needs_backdrop = self.needs_backdrop(batch.effect);
+            if needs_backdrop || passes.is_empty() {
+                passes.push(crate::debug::PassDescription {
+                    copies_backdrop: needs_backdrop,
+                    batches: vec![],
+                });
+            }
+            passes
+                .last_mut()
+                .unwrap()
+                .batches
+                .push(crate::debug::BatchDescription {
+                    texture: batch.texture.clone(),
+                    blend_mode: batch.blend_mode,
+                    effect: format!("{:?}", batch.effect),
+                    needs_backdrop,
+                    scissor: batch.scissor,
+                    indices: batch.indices.clone(),
+                });
+        }
+        crate::debug::FrameDescription {
+            target_size: frame.target_size,
+            target_format: self.target.format,
+            vertices: (frame.vertex_buffer.len / std::mem::size_of::<Vertex>() as u64) as u32,
+            indices: (frame.index_buffer.len / std::mem::size_of::<u32>() as u64) as u32,
+            buffers: vec![
+                frame.vertex_buffer.describe(),
+                frame.index_buffer.describe(),
+                frame.texture_uniforms_buffer.describe(),
+            ],
+            passes,
+            skipped_draws: frame.stats.skipped_draws,
+            dropped_draws: frame.stats.dropped_draws,
+            culled_draws: frame.stats.culled_draws,
+        }
+    }
+
+    /// Renders the prepared frame into an existing render pass.
+    ///
+    /// Batches whose [`Effect`] samples the backdrop need it to be copied mid-frame, which can't be done within a single pass, so they are skipped.
+    pub fn render(&self, rpass: &mut wgpu::RenderPass<'_>) {
+        self.render_frame(&self.frame, rpass, None);
+    }
+
+    /// Renders a frame other than the current one into an existing render pass, as [`Renderer::render`] does, calling `hook` before drawing each batch.
+    ///
+    /// Without a hook, batches with materials that declare extra bind groups are skipped, as nothing would bind them.
+    pub fn render_frame(
+        &self,
+        frame: &Frame,
+        rpass: &mut wgpu::RenderPass<'_>,
+        hook: Option<Hook<'_>>,
+    ) {
+        self.draw_batches(
+            frame,
+            rpass,
+            frame
+                .prepared_batches
+                .iter()
+                .filter(|batch| !self.needs_backdrop(batch.effect)),
+            hook,
+        );
+    }
+
+    /// Begins a render pass on `encoder` and renders the prepared frame into it, skipping batches that sample the backdrop as [`Renderer::render`] does.
+    pub fn render_to_view(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        options: crate::PassOptions,
+    ) {
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("canvasette: render_to_view"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: options.resolve_target,
+                ops: wgpu::Operations {
+                    load: options.load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: options.timestamp_writes,
+            occlusion_query_set: None,
+        });
+        self.render(&mut rpass);
+    }
+
+    /// Renders the prepared frame into a texture, splitting it into multiple passes labeled `label` where the backdrop needs to be copied.
+    ///
+    /// The texture must have been created with [`wgpu::TextureUsages::COPY_SRC`] if any batch samples the backdrop. If the pipelines are multisampled, `multisampled` must be a texture with the same size and sample count, which is drawn to and resolved into `texture` at the end of every pass. `hook` is called before drawing each batch, as with [`Renderer::render_frame`].
+    pub fn render_to_texture(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        label: &str,
+        texture: &wgpu::Texture,
+        multisampled: Option<&wgpu::Texture>,
+        mut load: wgpu::LoadOp<wgpu::Color>,
+        mut hook: Option<Hook<'_>>,
+    ) {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let multisampled_view = multisampled
+            .map(|texture| texture.create_view(&wgpu::TextureViewDescriptor::default()));
+        let mut batches = &self.frame.prepared_batches[..];
+        loop {
+            if batches
+                .first()
+                .is_some_and(|batch| self.needs_backdrop(batch.effect))
+            {
+                if let Some(backdrop) = &self.frame.backdrop {
+                    encoder.copy_texture_to_texture(
+                        texture.as_image_copy(),
+                        backdrop.texture.as_image_copy(),
+                        backdrop.texture.size(),
+                    );
+                }
+            }
+
+            let end = batches
+                .iter()
+                .skip(1)
+                .position(|batch| self.needs_backdrop(batch.effect))
+                .map_or(batches.len(), |i| i + 1);
+
+            {
+                let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some(label),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: multisampled_view.as_ref().unwrap_or(&view),
+                        resolve_target: multisampled_view.as_ref().map(|_| &view),
+                        ops: wgpu::Operations {
+                            load,
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+                self.draw_batches(
+                    &self.frame,
+                    &mut rpass,
+                    batches[..end].iter(),
+                    match &mut hook {
+                        Some(hook) => Some(*hook),
+                        None => None,
+                    },
+                );
+            }
+
+            load = wgpu::LoadOp::Load;
+            batches = &batches[end..];
+            if batches.is_empty() {
+                break;
+            }
+        }
+    }
+
+    fn draw_batches<'a>(
+        &self,
+        frame: &Frame,
+        rpass: &mut wgpu::RenderPass<'_>,
+        batches: impl Iterator<Item = &'a PreparedBatch>,
+        mut hook: Option<Hook<'_>>,
+    ) {
+        let mut batches = batches.peekable();
+        if batches.peek().is_none() {
+            return;
+        }
+        rpass.set_vertex_buffer(0, frame.vertex_buffer.inner.slice(..));
+        rpass.set_index_buffer(
+            frame.index_buffer.inner.slice(..),
+            wgpu::IndexFormat::Uint32,
+        );
+        rpass.set_bind_group(1, &frame.target_uniforms_bind_group, &[]);
+        let mut pipeline = None;
+        let mut scissor = None;
+        for batch in batches {
+            if hook.is_none() && self.needs_hook(batch.effect) {
+                continue;
+            }
+            let batch_pipeline = match batch.effect {
+                Effect::None | Effect::Fade { .. } => {
+                    &self.render_pipelines[batch.blend_mode as usize]
+                }
+                Effect::Distort { .. } => &self.distort_pipeline,
+                Effect::Mask => &self.mask_pipeline,
+                Effect::Palette { .. } => &self.palette_pipelines[batch.blend_mode as usize],
+                Effect::Material(i) => match self.materials.get(i as usize) {
+                    Some(material) => &material.pipelines[batch.blend_mode as usize],
+                    None => &self.render_pipelines[batch.blend_mode as usize],
+                },
+            };
+            if self.needs_backdrop(batch.effect) {
+                let Some(backdrop) = &frame.backdrop else {
+                    continue;
+                };
+                rpass.set_bind_group(2, &backdrop.bind_group, &[]);
+            }
+            if let Some(secondary_bind_group) = &batch.secondary_bind_group {
+                rpass.set_bind_group(2, secondary_bind_group, &[]);
+            }
+            if !pipeline.is_some_and(|pipeline| std::ptr::eq(pipeline, batch_pipeline)) {
+                rpass.set_pipeline(batch_pipeline);
+                pipeline = Some(batch_pipeline);
+            }
+            if batch.scissor != scissor {
+                let [x, y, width, height] = batch.scissor.unwrap_or([
+                    0,
+                    0,
+                    frame.target_size.width,
+                    frame.target_size.height,
+                ]);
+                rpass.set_scissor_rect(x, y, width, height);
+                scissor = batch.scissor;
+            }
+            rpass.set_bind_group(0, &batch.texture_bind_group, &[]);
+            if let Some(hook) = &mut hook {
+                hook(
+                    &crate::BatchInfo {
+                        texture: &batch.texture,
+                        blend_mode: batch.blend_mode,
+                        material: match batch.effect {
+                            Effect::Material(i) => Some(crate::material::Material(i)),
+                            _ => None,
+                        },
+                        indices: batch.indices.clone(),
+                    },
+                    rpass,
+                );
+            }
+            rpass.draw_indexed(batch.indices.clone(), 0, 0..1);
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct PostUniforms {
+    size: [f32; 2],
+    _padding: [f32; 2],
+    params: [f32; 4],
+}
+
+/// Effect that copies the source unchanged, for empty chains and unknown effects.
+const POST_PASSTHROUGH: &str = r#"
+fn post_effect(in: PostOutput, color: vec4<f32>) -> vec4<f32> {
+    return color;
+}
+"#;
+
+/// Encapsulates GPU state for full-screen post effect passes.
+pub struct PostProcessor {
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    format: wgpu::TextureFormat,
+    effects: Vec<wgpu::RenderPipeline>,
+    passthrough: Option<wgpu::RenderPipeline>,
+    /// Intermediate textures passes alternate between.
+    textures: Vec<wgpu::Texture>,
+    /// One uniform buffer per pass.
+    uniforms: Vec<wgpu::Buffer>,
+}
+
+impl PostProcessor {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        Self {
+            bind_group_layout: device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("canvasette: post_bind_group_layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            }),
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("canvasette: post_sampler"),
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }),
+            format,
+            effects: vec![],
+            passthrough: None,
+            textures: vec![],
+            uniforms: vec![],
+        }
+    }
+
+    fn create_pipeline(
+        &self,
+        device: &wgpu::Device,
+        cache: Option<&wgpu::PipelineCache>,
+        label: &str,
+        source: &str,
+        extra_bind_group_layouts: &[&wgpu::BindGroupLayout],
+    ) -> wgpu::RenderPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl([include_str!("post.wgsl"), source].concat().into()),
+        });
+        let mut bind_group_layouts = vec![&self.bind_group_layout];
+        bind_group_layouts.extend_from_slice(extra_bind_group_layouts);
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("canvasette: post_pipeline.layout"),
+            bind_group_layouts: &bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            cache,
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_post"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_post"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: self.format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::all(),
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        })
+    }
+
+    /// Compiles a post effect from WGSL source defining a `post_effect` function, returning its index. Effects that fail to compile are not added.
+    pub fn create_effect(
+        &mut self,
+        device: &wgpu::Device,
+        cache: Option<&wgpu::PipelineCache>,
+        label: Option<&str>,
+        source: &str,
+        extra_bind_group_layouts: &[&wgpu::BindGroupLayout],
+    ) -> Result<u32, wgpu::Error> {
+        let pipeline = error_scope(device, || {
+            self.create_pipeline(
+                device,
+                cache,
+                label.unwrap_or("canvasette: post_effect"),
+                source,
+                extra_bind_group_layouts,
+            )
+        })?;
+        self.effects.push(pipeline);
+        Ok(self.effects.len() as u32 - 1)
+    }
+
+    /// Records one pass per post effect, each reading the previous pass's output, with the last writing to `target`.
+    ///
+    /// An empty chain copies the source to the target.
+    #[allow(clippy::too_many_arguments)]
+    pub fn apply(
+        &mut self,
+        device: &wgpu::Device,
+        cache: Option<&wgpu::PipelineCache>,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::Texture,
+        target: &wgpu::Texture,
+        passes: &[crate::post::PostPass],
+    ) {
+        let size = target.size();
+        let intermediates = passes.len().saturating_sub(1).min(2);
+        self.textures.truncate(intermediates);
+        self.textures.retain(|texture| texture.size() == size);
+        while self.textures.len() < intermediates {
+            self.textures.push(crate::create_render_target_texture(
+                device,
+                "canvasette: post_intermediate",
+                self.format,
+                1,
+                size.width,
+                size.height,
+            ));
+        }
+        while self.uniforms.len() < passes.len().max(1) {
+            self.uniforms
+                .push(device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("canvasette: post_uniforms_buffer"),
+                    size: std::mem::size_of::<PostUniforms>() as u64,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }));
+        }
+        let needs_passthrough = passes.is_empty()
+            || passes
+                .iter()
+                .any(|pass| self.effects.get(pass.effect.0 as usize).is_none());
+        if needs_passthrough && self.passthrough.is_none() {
+            self.passthrough = Some(self.create_pipeline(
+                device,
+                cache,
+                "canvasette: post_passthrough",
+                POST_PASSTHROUGH,
+                &[],
+            ));
+        }
+
+        let passthrough = crate::post::PostPass::new(crate::post::PostEffect(u32::MAX));
+        let passes = if passes.is_empty() {
+            std::slice::from_ref(&passthrough)
+        } else {
+            passes
+        };
+        for (i, pass) in passes.iter().enumerate() {
+            let input = if i == 0 {
+                source
+            } else {
+                &self.textures[(i - 1) % 2]
+            };
+            let output = if i == passes.len() - 1 {
+                target
+            } else {
+                &self.textures[i % 2]
+            };
+
+            queue.write_buffer(
+                &self.uniforms[i],
+                0,
+                bytemuck::cast_slice(&[PostUniforms {
+                    size: [size.width as f32, size.height as f32],
+                    _padding: [0.0; 2],
+                    params: pass.params.to_array(),
+                }]),
+            );
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("canvasette: post_bind_group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(
+                            &input.create_view(&wgpu::TextureViewDescriptor::default()),
+                        ),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: self.uniforms[i].as_entire_binding(),
+                    },
+                ],
+            });
+
+            let view = output.create_view(&wgpu::TextureViewDescriptor::default());
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("canvasette: post_pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            match self.effects.get(pass.effect.0 as usize) {
+                Some(pipeline) => {
+                    rpass.set_pipeline(pipeline);
+                    for (j, bind_group) in pass.bind_groups.iter().enumerate() {
+                        rpass.set_bind_group(j as u32 + 1, *bind_group, &[]);
+                    }
+                }
+                None => rpass.set_pipeline(self.passthrough.as_ref().unwrap()),
+            }
+            rpass.set_bind_group(0, &bind_group, &[]);
+            rpass.draw(0..3, 0..1);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Uniform grids of cells, e.g. for sprite sheets.
+
+use glam::*;
+
+use crate::{Texture, TextureSlice};
+
+/// A texture slice split into equally sized cells, created with [`TextureSlice::grid`].
+///
+/// Cells are numbered left to right, then top to bottom, starting from 0, ignoring any flipping of the slice. Each cell is flipped the same way as the slice.
+pub struct Grid<'a, T> {
+    slice: TextureSlice<'a, T>,
+    dims: UVec2,
+    cell_size: UVec2,
+}
+
+impl<'a, T> Clone for Grid<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for Grid<'a, T> {}
+
+impl<'a, T> Grid<'a, T>
+where
+    T: Texture,
+{
+    pub(crate) fn new(slice: TextureSlice<'a, T>, dims: UVec2) -> Option<Self> {
+        if dims.cmpeq(UVec2::ZERO).any() || dims.cmpgt(slice.size()).any() {
+            return None;
+        }
+        Some(Self {
+            slice,
+            dims,
+            cell_size: slice.size() / dims,
+        })
+    }
+
+    /// Gets the number of columns.
+    pub fn columns(&self) -> u32 {
+        self.dims.x
+    }
+
+    /// Gets the number of rows.
+    pub fn rows(&self) -> u32 {
+        self.dims.y
+    }
+
+    /// Gets the number of cells.
+    pub fn count(&self) -> u32 {
+        self.dims.x * self.dims.y
+    }
+
+    /// Gets the size of each cell, in texels.
+    pub fn cell_size(&self) -> UVec2 {
+        self.cell_size
+    }
+
+    /// Gets a cell by index, or [`None`] if the index is out of bounds.
+    pub fn cell(&self, index: u32) -> Option<TextureSlice<'a, T>> {
+        if index >= self.count() {
+            return None;
+        }
+        self.cell_at(index % self.dims.x, index / self.dims.x)
+    }
+
+    /// Gets a cell by column and row, or [`None`] if either is out of bounds.
+    pub fn cell_at(&self, column: u32, row: u32) -> Option<TextureSlice<'a, T>> {
+        if column >= self.dims.x || row >= self.dims.y {
+            return None;
+        }
+        self.slice.slice(
+            (UVec2::new(column, row) * self.cell_size).as_ivec2(),
+            self.cell_size,
+        )
+    }
+
+    /// Iterates over the cells in index order.
+    pub fn cells(&self) -> impl Iterator<Item = TextureSlice<'a, T>> + '_ {
+        (0..self.count()).filter_map(|index| self.cell(index))
+    }
+}
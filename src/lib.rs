@@ -2,19 +2,75 @@
 
 use glam::*;
 
+use geom::{Rect, Rectf};
 use wgpu::util::DeviceExt;
 
+pub mod ambient;
+pub mod anchor;
+pub mod anim;
 mod atlas;
+pub mod camera;
+#[cfg(any(feature = "ktx2", feature = "dds"))]
+pub mod container;
+pub mod crowd;
+pub mod debug;
+pub mod decal;
+pub mod distortion;
 #[cfg(feature = "text")]
 pub mod font;
+pub mod geom;
+mod gpu;
+pub mod grid;
+pub mod material;
+mod mesh;
+pub mod nine_slice;
+pub mod palette;
+pub mod pipeline_cache;
+pub mod post;
+pub mod reflection;
+pub mod region;
+pub mod rope;
+pub mod shape;
 #[cfg(feature = "text")]
 mod text;
-
-type Cache = std::collections::HashMap<u64, wgpu::Texture>;
+pub mod tilemap;
+pub mod trail;
+pub mod transcode;
+mod upload;
+pub mod variation;
+pub mod weather;
 
 /// 8-bit RGBA color.
 pub type Color = rgb::Rgba<u8>;
 
+/// RGBA color with `f32` components, in the same sRGB-encoded space as [`Color`], where 1 is full intensity.
+pub type Colorf = rgb::Rgba<f32>;
+
+/// Colors that drawables can be tinted with.
+pub trait Tint: Copy {
+    /// Converts the color to `f32` components.
+    fn to_colorf(self) -> Colorf;
+}
+
+impl Tint for Color {
+    fn to_colorf(self) -> Colorf {
+        Colorf::new(
+            self.r as f32 / 255.0,
+            self.g as f32 / 255.0,
+            self.b as f32 / 255.0,
+            self.a as f32 / 255.0,
+        )
+    }
+}
+
+impl Tint for Colorf {
+    fn to_colorf(self) -> Colorf {
+        self
+    }
+}
+
+pub use nine_slice::NineSlice;
+pub use shape::Shape;
 #[cfg(feature = "text")]
 pub use text::Label;
 
@@ -23,380 +79,4747 @@ struct Sprite<'a> {
     src_offset: IVec2,
     src_size: UVec2,
     src_layer: u32,
+    flip: BVec2,
     transform: Affine2,
     tint: Color,
+    /// Tints of each corner, ordered top-left, top-right, bottom-left, bottom-right, multiplied with `tint`.
+    corner_tints: [Color; 4],
 }
 
 enum Command<'a> {
     Sprite(Sprite<'a>),
+    /// A sprite repeated to fill an area, given in texels.
+    Tiled(Sprite<'a>, UVec2, WrapMode),
     #[cfg(feature = "text")]
-    Text(text::Section),
+    Text(Box<text::Section>),
+    Shape(shape::ShapeCommand),
+    Mesh(mesh::MeshCommand<'a>),
+    Rope(rope::RopeCommand<'a>),
+    Distortion(distortion::DistortionCommand<'a>),
+    Tilemap(tilemap::TilemapCommand<'a>),
+    AtlasView(debug::AtlasViewCommand),
+    Group(Box<GroupCommand<'a>>),
 }
 
-/// A canvas for drawing onto.
-pub struct Canvas<'a> {
-    commands: Vec<Command<'a>>,
+/// Items drawn together into an intermediate target, which is then drawn back with a shared opacity.
+struct GroupCommand<'a> {
+    items: Vec<Item<'a>>,
+    opacity: f32,
+    /// Sprite the group is drawn back through, see [`Canvas::push_mask`].
+    mask: Option<GroupMask<'a>>,
+    /// Always the identity: the group's items are already transformed, and the intermediate target covers the whole target.
+    transform: Affine2,
 }
 
-/// Things that can be drawn.
-pub trait Drawable<'a>
-where
-    Self: Sized + Clone,
-{
-    /// Called to draw the item to the canvas.
-    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2);
+/// A texture slice whose alpha a group is clipped to.
+#[derive(Clone, Copy)]
+struct GroupMask<'a> {
+    texture: &'a dyn Texture,
+    rect: Rect,
+    layer: u32,
+    transform: Affine2,
+}
 
-    /// Adds a tint to the drawable.
-    fn tinted(&self, tint: Color) -> impl Drawable<'a> {
-        Tinted {
-            drawable: self.clone(),
-            tint,
-        }
+impl<'a> GroupCommand<'a> {
+    /// Gets the bounding box of the group's items, before layer views are applied.
+    fn bounds(&self) -> (Vec2, Vec2) {
+        let bounds = self.items.iter().map(|item| item.bounds()).fold(
+            Rectf::new(Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+            |bounds, item| bounds.union(&item),
+        );
+        (bounds.min, bounds.max)
     }
 }
 
-#[cfg(feature = "text")]
-impl<'a> Drawable<'a> for text::Label {
-    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
-        canvas.commands.push(Command::Text(text::Section {
-            label: self.clone(),
-            transform,
-            tint,
-        }));
+impl<'a> Command<'a> {
+    fn transform(&self) -> Affine2 {
+        match self {
+            Command::Sprite(sprite) | Command::Tiled(sprite, ..) => sprite.transform,
+            #[cfg(feature = "text")]
+            Command::Text(section) => section.transform,
+            Command::Shape(shape) => shape.transform,
+            Command::Mesh(mesh) => mesh.transform,
+            Command::Rope(rope) => rope.transform,
+            Command::Distortion(distortion) => distortion.transform,
+            Command::Tilemap(tilemap) => tilemap.transform,
+            Command::AtlasView(view) => view.transform,
+            Command::Group(group) => group.transform,
+        }
     }
-}
 
-#[derive(Debug, Clone, Copy)]
-struct Rect {
-    offset: IVec2,
-    size: UVec2,
-}
+    fn transform_mut(&mut self) -> &mut Affine2 {
+        match self {
+            Command::Sprite(sprite) | Command::Tiled(sprite, ..) => &mut sprite.transform,
+            #[cfg(feature = "text")]
+            Command::Text(section) => &mut section.transform,
+            Command::Shape(shape) => &mut shape.transform,
+            Command::Mesh(mesh) => &mut mesh.transform,
+            Command::Rope(rope) => &mut rope.transform,
+            Command::Distortion(distortion) => &mut distortion.transform,
+            Command::Tilemap(tilemap) => &mut tilemap.transform,
+            Command::AtlasView(view) => &mut view.transform,
+            Command::Group(group) => &mut group.transform,
+        }
+    }
 
-impl Rect {
-    fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
-        Self {
-            offset: IVec2::new(x, y),
-            size: UVec2::new(width, height),
+    fn tint(&self) -> Color {
+        match self {
+            Command::Sprite(sprite) | Command::Tiled(sprite, ..) => sprite.tint,
+            #[cfg(feature = "text")]
+            Command::Text(section) => section.tint,
+            Command::Shape(shape) => shape.tint,
+            Command::Mesh(mesh) => mesh.tint,
+            Command::Rope(rope) => rope.tint,
+            Command::Distortion(distortion) => distortion.tint,
+            Command::Tilemap(tilemap) => tilemap.tint,
+            Command::AtlasView(view) => view.tint,
+            Command::Group(group) => {
+                palette::with_alpha(palette::WHITE, (group.opacity * 255.0).round() as u8)
+            }
         }
     }
-    const fn left(&self) -> i32 {
-        self.offset.x
+
+    /// Whether the command would draw nothing, e.g. a zero-sized slice or an empty label, so it can be skipped.
+    fn is_degenerate(&self) -> bool {
+        let (min, max) = self.bounds();
+        let size = max - min;
+        !(size.x > 0.0 && size.y > 0.0)
+            || match self {
+                #[cfg(feature = "text")]
+                Command::Text(section) => section.label.is_empty(),
+                Command::Tiled(sprite, ..) => sprite.src_size.cmpeq(UVec2::ZERO).any(),
+                Command::Mesh(mesh) => mesh.indices.is_empty(),
+                Command::Rope(rope) => rope.points.len() < 2,
+                Command::Distortion(distortion) => distortion.src_size.cmpeq(UVec2::ZERO).any(),
+                _ => false,
+            }
     }
-    const fn top(&self) -> i32 {
-        self.offset.y
+
+    /// Whether the command's transform and bounds are free of NaNs and infinities.
+    ///
+    /// A group's bounds come from its draws, which are checked on their own, and are infinite when it has none.
+    fn is_finite(&self) -> bool {
+        if let Command::Group(group) = self {
+            return group.transform.is_finite();
+        }
+        let (min, max) = self.bounds();
+        self.transform().is_finite() && min.is_finite() && max.is_finite()
     }
-    const fn right(&self) -> i32 {
-        self.offset.x + self.size.x as i32
+
+    /// Gets the texture the command samples, if any besides the glyph atlases.
+    fn texture(&self) -> Option<&'a dyn Texture> {
+        match self {
+            Command::Sprite(sprite) | Command::Tiled(sprite, ..) => Some(sprite.texture),
+            Command::Mesh(mesh) => mesh.texture,
+            Command::Rope(rope) => Some(rope.texture),
+            Command::Distortion(distortion) => Some(distortion.texture),
+            Command::Tilemap(tilemap) => Some(tilemap.texture),
+            Command::Group(group) => group.mask.map(|mask| mask.texture),
+            _ => None,
+        }
     }
-    const fn bottom(&self) -> i32 {
-        self.offset.y + self.size.y as i32
+
+    /// Gets the local bounding box of the command as (min, max), before its transform is applied.
+    fn bounds(&self) -> (Vec2, Vec2) {
+        match self {
+            Command::Sprite(sprite) => (Vec2::ZERO, sprite.src_size.as_vec2()),
+            Command::Tiled(_, size, _) => (Vec2::ZERO, size.as_vec2()),
+            #[cfg(feature = "text")]
+            Command::Text(section) => (Vec2::ZERO, section.label.size()),
+            Command::Shape(shape) => shape.shape.bounds(),
+            Command::Mesh(mesh) => mesh.bounds(),
+            Command::Rope(rope) => rope.bounds(),
+            Command::Distortion(distortion) => (Vec2::ZERO, distortion.size),
+            Command::Tilemap(tilemap) => tilemap.bounds(),
+            Command::AtlasView(view) => (Vec2::ZERO, view.size),
+            Command::Group(group) => group.bounds(),
+        }
     }
 }
 
-/// Trait for textures.
+/// Texture a staged range of indices is drawn with.
 ///
-/// These textures can either be resident on the CPU, in which case they must be uploaded as needed; or on the GPU, on which case they can be used directly but you must manage the lifecycle of textures yourself.
-pub trait Texture {
-    /// The size of the texture.
-    fn size(&self) -> wgpu::Extent3d;
-
-    /// Uploads the texture to the GPU.
-    ///
-    /// If the texture is already uploaded, does nothing.
-    fn upload_to_wgpu(&self, device: &wgpu::Device, queue: &wgpu::Queue, cache: &mut Cache);
-
-    /// Gets the wgpu texture.
-    ///
-    /// If the texture is not uploaded yet, returns [`None`].
-    fn get_wgpu_texture<'a>(&'a self, cache: &'a Cache) -> Option<&'a wgpu::Texture>;
+/// Glyph atlases may be resized while text is being staged, so their textures are only resolved once staging is complete.
+#[derive(Clone, Copy)]
+enum StagedTexture<'a> {
+    Texture(&'a wgpu::Texture),
+    White,
+    /// Glyph mask atlas of a size class, and its page.
+    #[cfg(feature = "text")]
+    GlyphMask(usize, usize),
+    /// Glyph color atlas of a size class, and its page.
+    #[cfg(feature = "text")]
+    GlyphColor(usize, usize),
 }
 
-/// An image.
+/// Wgpu textures and origins of the textures drawn in a pass, looked up in the [`Cache`] once per texture rather than once per draw.
 ///
-/// This is a texture that may be reuploaded to the GPU as necessary.
-pub struct Image {
-    id: u64,
-    pixels: Vec<u8>,
-    desc: wgpu::TextureDescriptor<'static>,
+/// Textures are told apart by address, and consecutive draws usually share a texture, so the last one is checked before hashing.
+struct ResolvedTextures<'a> {
+    cache: &'a Cache,
+    last: Option<(*const (dyn Texture + 'a), &'a wgpu::Texture, wgpu::Origin3d)>,
+    resolved:
+        std::collections::HashMap<*const (dyn Texture + 'a), (&'a wgpu::Texture, wgpu::Origin3d)>,
 }
 
-impl Image {
-    /// Creates a new image.
-    pub fn new(pixels: Vec<u8>, desc: wgpu::TextureDescriptor<'static>) -> Self {
-        static IMAGE_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+impl<'a> ResolvedTextures<'a> {
+    fn new(cache: &'a Cache) -> Self {
         Self {
-            id: IMAGE_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
-            pixels,
-            desc,
+            cache,
+            last: None,
+            resolved: std::collections::HashMap::new(),
         }
     }
-}
-
-impl Texture for Image {
-    fn size(&self) -> wgpu::Extent3d {
-        self.desc.size
-    }
 
-    fn upload_to_wgpu(&self, device: &wgpu::Device, queue: &wgpu::Queue, cache: &mut Cache) {
-        cache.entry(self.id).or_insert_with(|| {
-            device.create_texture_with_data(
-                queue,
-                &self.desc,
-                wgpu::util::TextureDataOrder::default(),
-                &self.pixels,
+    /// Gets the wgpu texture and origin of a texture, which must have been uploaded.
+    fn get(&mut self, texture: &'a dyn Texture) -> (&'a wgpu::Texture, wgpu::Origin3d) {
+        let key = std::ptr::from_ref(texture);
+        if let Some((last, wgpu_texture, origin)) = self.last {
+            if std::ptr::eq(last, key) {
+                return (wgpu_texture, origin);
+            }
+        }
+        let cache = self.cache;
+        let (wgpu_texture, origin) = *self.resolved.entry(key).or_insert_with(|| {
+            (
+                texture.get_wgpu_texture(cache).unwrap(),
+                texture.get_wgpu_origin(cache),
             )
         });
+        self.last = Some((key, wgpu_texture, origin));
+        (wgpu_texture, origin)
     }
+}
 
-    fn get_wgpu_texture<'a>(&'a self, cache: &'a Cache) -> Option<&'a wgpu::Texture> {
-        cache.get(&self.id)
+impl<'a> PartialEq for StagedTexture<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (StagedTexture::Texture(a), StagedTexture::Texture(b)) => std::ptr::eq(*a, *b),
+            #[cfg(feature = "text")]
+            (StagedTexture::GlyphMask(a, a_page), StagedTexture::GlyphMask(b, b_page))
+            | (StagedTexture::GlyphColor(a, a_page), StagedTexture::GlyphColor(b, b_page)) => {
+                (a, a_page) == (b, b_page)
+            }
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
     }
 }
 
-impl Texture for wgpu::Texture {
-    fn size(&self) -> wgpu::Extent3d {
-        self.size()
-    }
+/// A range of staged indices sharing the same draw state.
+struct Run<'a> {
+    texture: StagedTexture<'a>,
+    blend_mode: BlendMode,
+    effect: gpu::Effect,
+    secondary: Option<&'a wgpu::Texture>,
+    clip: Option<Rect>,
+    indices: std::ops::Range<u32>,
+}
 
-    fn upload_to_wgpu(&self, _device: &wgpu::Device, _queue: &wgpu::Queue, _cache: &mut Cache) {}
+#[derive(Default)]
+struct Stage<'a> {
+    mesh: gpu::Mesh,
+    runs: Vec<Run<'a>>,
+    /// Blend mode of the item currently being staged.
+    blend_mode: BlendMode,
+    /// Effect of the item currently being staged.
+    effect: gpu::Effect,
+    /// Secondary texture of the item currently being staged, see [`gpu::Batch::secondary`].
+    secondary: Option<&'a wgpu::Texture>,
+    /// Clip rectangle of the item currently being staged.
+    clip: Option<Rect>,
+}
 
-    fn get_wgpu_texture<'a>(&'a self, _cache: &'a Cache) -> Option<&'a wgpu::Texture> {
-        Some(self)
+impl<'a> Stage<'a> {
+    /// Records that the indices pushed since `start` are drawn with `texture`.
+    fn end(&mut self, texture: StagedTexture<'a>, start: u32) {
+        let end = self.mesh.index_count();
+        if let Some(last) = self.runs.last_mut() {
+            if last.texture == texture
+                && last.blend_mode == self.blend_mode
+                && last.effect == self.effect
+                && last.secondary.map(std::ptr::from_ref) == self.secondary.map(std::ptr::from_ref)
+                && last.clip == self.clip
+                && last.indices.end == start
+            {
+                last.indices.end = end;
+                return;
+            }
+        }
+        self.runs.push(Run {
+            texture,
+            blend_mode: self.blend_mode,
+            effect: self.effect,
+            secondary: self.secondary,
+            clip: self.clip,
+            indices: start..end,
+        });
     }
 }
 
-/// Represents a slice of a texture to draw.
-pub struct TextureSlice<'a, T> {
-    texture: &'a T,
+struct Item<'a> {
     layer: u32,
-    rect: Rect,
+    z: i32,
+    blend_mode: BlendMode,
+    material: Option<material::Material>,
+    palette: Option<PaletteRow<'a>>,
+    clip: Option<Rect>,
+    variation: Option<variation::Variation>,
+    emissive: f32,
+    /// Tint from [`Drawable::tinted`], kept in `f32` so nested tints are only rounded once.
+    tint: Vec4,
+    /// Unclamped multiplier for the tint, for drawing brighter than white.
+    hdr_tint: Vec4,
+    /// Priority when the draw budget is exceeded, overriding the layer's.
+    priority: Option<i32>,
+    /// Whether the draw is snapped to whole target pixels, see [`Drawable::snapped`].
+    snap: bool,
+    /// The [`Canvas::draw`] call the item was drawn by.
+    draw: Option<CommandId>,
+    command: Command<'a>,
 }
 
-impl<'a, T> Clone for TextureSlice<'a, T> {
-    fn clone(&self) -> Self {
-        Self {
-            texture: self.texture,
-            layer: self.layer,
-            rect: self.rect,
-        }
+/// The palette row a draw's indices are looked up in, see [`Drawable::with_palette`].
+#[derive(Clone, Copy)]
+struct PaletteRow<'a> {
+    texture: &'a dyn Texture,
+    offset: IVec2,
+    width: u32,
+    layer: u32,
+}
+
+impl Item<'_> {
+    /// Gets the bounding box of the transformed command, before layer views are applied.
+    fn bounds(&self) -> Rectf {
+        let (min, max) = self.command.bounds();
+        Rectf::new(min, max).transform(self.command.transform())
     }
 }
 
-impl<'a, T> Copy for TextureSlice<'a, T> {}
+#[derive(Clone, Copy)]
+struct Layer<'a> {
+    view: Affine2,
+    parallax: Vec2,
+    wrap_width: Option<f32>,
+    reflection: Option<reflection::Reflection<'a>>,
+    priority: i32,
+    visible: bool,
+}
 
-impl<'a, T> TextureSlice<'a, T>
-where
-    T: Texture,
-{
-    /// Creates a new texture slice from a raw texture.
-    pub fn from_layer(texture: &'a T, layer: u32) -> Option<Self> {
-        let size = texture.size();
-        if layer >= size.depth_or_array_layers {
-            return None;
+impl<'a> Default for Layer<'a> {
+    fn default() -> Self {
+        Self {
+            view: Affine2::IDENTITY,
+            parallax: Vec2::ONE,
+            wrap_width: None,
+            reflection: None,
+            priority: 0,
+            visible: true,
         }
-        Some(Self {
-            texture,
-            layer,
-            rect: Rect::new(0, 0, size.width, size.height),
-        })
     }
+}
 
-    /// Slices the texture slice.
-    ///
-    /// Note that `offset` represents an offset into the slice and not into the overall texture -- the returned slice's offset will be the current offset + new offset.
-    ///
-    /// Returns [`None`] if the slice goes out of bounds.
-    pub fn slice(&self, offset: glam::IVec2, size: glam::UVec2) -> Option<Self> {
-        let rect = Rect {
-            offset: self.rect.offset + offset,
-            size,
+/// A layer's settings resolved against a target.
+struct ResolvedLayer {
+    view: Affine2,
+    wrap: Option<Wrap>,
+}
+
+#[derive(Clone)]
+struct Wrap {
+    width: f32,
+    visible_left: f32,
+    visible_right: f32,
+}
+
+impl ResolvedLayer {
+    const IDENTITY: Self = Self {
+        view: Affine2::IDENTITY,
+        wrap: None,
+    };
+
+    /// Gets the final transforms for an item with the given local bounds, including any wrap-around copies.
+    fn transforms(
+        &self,
+        transform: Affine2,
+        bounds: (Vec2, Vec2),
+    ) -> impl Iterator<Item = Affine2> + '_ {
+        let range = match &self.wrap {
+            Some(wrap) => {
+                let Rectf { min, max } = Rectf::new(bounds.0, bounds.1).transform(transform);
+                let first = ((wrap.visible_left - max.x) / wrap.width).ceil() as i64;
+                let last = ((wrap.visible_right - min.x) / wrap.width).floor() as i64;
+                // No more copies of an item narrower than the wrap width can overlap the visible area. Wider items overlap it in as many more copies as they are wide, drawn over each other, so only the middle ones are kept.
+                let count = (((wrap.visible_right - wrap.visible_left) / wrap.width).ceil() as i64)
+                    .saturating_add(1);
+                let first = first
+                    + (last.saturating_sub(first).saturating_add(1))
+                        .saturating_sub(count)
+                        .max(0)
+                        / 2;
+                let last = last.min(first.saturating_add(count - 1));
+                let clamp = |i: i64| i.clamp(i32::MIN.into(), i32::MAX.into()) as i32;
+                clamp(first)..=clamp(last)
+            }
+            None => 0..=0,
         };
-        if rect.left() < self.rect.left()
-            || rect.right() > self.rect.right()
-            || rect.top() < self.rect.top()
-            || rect.bottom() > self.rect.bottom()
-        {
-            return None;
-        }
-        Some(Self {
-            texture: self.texture,
-            layer: self.layer,
-            rect,
+        range.map(move |i| {
+            let offset = self.wrap.as_ref().map(|wrap| wrap.width).unwrap_or(0.0) * i as f32;
+            self.view * Affine2::from_translation(Vec2::new(offset, 0.0)) * transform
         })
     }
+}
 
-    /// Gets the size of the texture slice.
-    pub fn size(&self) -> glam::UVec2 {
-        self.rect.size
-    }
+/// A layer's reflection resolved against a target.
+struct ResolvedReflection<'a> {
+    /// The layer with its view mirrored about the axis.
+    layer: ResolvedLayer,
+    opacity: f32,
+    fade: gpu::Effect,
+    /// Distortion drawn over the reflection, already in target coordinates.
+    band: Option<Item<'a>>,
 }
 
-impl<'a, T> Drawable<'a> for TextureSlice<'a, T>
-where
-    T: Texture,
-{
-    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
-        canvas.commands.push(Command::Sprite(Sprite {
-            transform,
-            tint,
-            texture: self.texture,
-            src_offset: self.rect.offset,
-            src_size: self.rect.size,
-            src_layer: self.layer,
-        }));
+/// Rounds the translation of a transform to whole pixels, unless it rotates, skews or scales by a fraction.
+fn snap_to_pixels(transform: Affine2) -> Affine2 {
+    let m = transform.matrix2;
+    if m.x_axis.y != 0.0
+        || m.y_axis.x != 0.0
+        || m.x_axis.x.fract() != 0.0
+        || m.y_axis.y.fract() != 0.0
+    {
+        return transform;
     }
+    Affine2::from_mat2_translation(m, transform.translation.round())
 }
 
-#[derive(Clone)]
-struct Tinted<T> {
-    drawable: T,
-    tint: Color,
+/// Whether a tilemap item can be drawn as chunks when zoomed out, which holds premultiplied colors of its plain tiles.
+fn draws_tilemap_chunks(item: &Item) -> bool {
+    matches!(item.blend_mode, BlendMode::Alpha | BlendMode::Premultiplied)
+        && item.palette.is_none()
+        && item.material.is_none()
 }
 
-impl<'a, T> Drawable<'a> for Tinted<T>
-where
-    T: Drawable<'a>,
-{
-    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
-        self.drawable.draw(
-            canvas,
-            Color::new(
-                ((tint.r as u16 * self.tint.r as u16) / 0xff) as u8,
-                ((tint.g as u16 * self.tint.g as u16) / 0xff) as u8,
-                ((tint.b as u16 * self.tint.b as u16) / 0xff) as u8,
-                ((tint.a as u16 * self.tint.a as u16) / 0xff) as u8,
-            ),
-            transform,
+/// Pushes quads of tiles of a tilemap, as (cell, source offset of its tile), given the origin of its texture and its final transform.
+fn push_tiles(
+    mesh: &mut gpu::Mesh,
+    tilemap: &tilemap::TilemapCommand,
+    tiles: impl Iterator<Item = (UVec2, IVec2)>,
+    origin: wgpu::Origin3d,
+    transform: Affine2,
+    tint: Color,
+) {
+    for (cell, src_offset) in tiles {
+        mesh.push_quad(
+            src_offset + IVec2::new(origin.x as i32, origin.y as i32),
+            tilemap.tile_size,
+            tilemap.src_layer + origin.z,
+            BVec2::FALSE,
+            transform * Affine2::from_translation((cell * tilemap.tile_size).as_vec2()),
+            tint,
         );
     }
 }
 
-impl<'a> Canvas<'a> {
-    pub fn new() -> Self {
-        Self { commands: vec![] }
-    }
-
-    /// Draws an item with the given transformation matrix.
-    #[inline]
-    pub fn draw(&mut self, drawable: impl Drawable<'a>, transform: glam::Affine2) {
-        drawable.draw(self, Color::new(0xff, 0xff, 0xff, 0xff), transform);
+impl<'a> Layer<'a> {
+    /// Gets the layer's view composed with the canvas-wide view, with parallax applied to the camera position implied by both.
+    fn effective_view(&self, canvas_view: Affine2, center: Vec2) -> Affine2 {
+        let view = canvas_view * self.view;
+        if self.parallax == Vec2::ONE || view.matrix2.determinant() == 0.0 {
+            return view;
+        }
+        let m = view.matrix2;
+        let position = -(m.inverse() * (view.translation - center));
+        Affine2::from_mat2_translation(m, center - m * (position * self.parallax))
     }
 }
 
-/// Encapsulates renderer state.
-pub struct Renderer {
-    renderer: spright::Renderer,
-    cache: Cache,
-    #[cfg(feature = "text")]
-    text_sprite_maker: text::SpriteMaker,
+/// A canvas for drawing onto.
+///
+/// Draws are placed into layers. Each layer may have its own view transform (e.g. a camera), which is applied to everything drawn in that layer when the canvas is prepared.
+pub struct Canvas<'a> {
+    items: Vec<Item<'a>>,
+    layer: u32,
+    z: i32,
+    blend_mode: BlendMode,
+    material: Option<material::Material>,
+    material_stack: Vec<Option<material::Material>>,
+    palette: Option<PaletteRow<'a>>,
+    clip: Option<Rect>,
+    clip_stack: Vec<Option<Rect>>,
+    variation: Option<variation::Variation>,
+    emissive: f32,
+    tint: Vec4,
+    hdr_tint: Vec4,
+    priority: Option<i32>,
+    snap: bool,
+    /// Whether every draw is snapped, see [`Canvas::set_pixel_snapping`].
+    pixel_snapping: bool,
+    layers: std::collections::HashMap<u32, Layer<'a>>,
+    view: Affine2,
+    transform: Affine2,
+    transform_stack: Vec<Affine2>,
+    /// Opacities and masks of the pushed groups, with the items drawn before each was pushed.
+    group_stack: Vec<(f32, Option<GroupMask<'a>>, Vec<Item<'a>>)>,
+    /// Changes made to each draw since it was drawn, indexed by [`CommandId`].
+    draws: Vec<DrawChanges>,
+    /// The draw currently being made, if any.
+    current_draw: Option<CommandId>,
 }
 
-/// Errors that can occur.
-#[derive(thiserror::Error, Debug)]
-pub enum Error {
-    /// Glyph atlas has run out of space.
-    #[error("out of glylph atlas space")]
-    OutOfGlyphAtlasSpace,
+/// Identifies a draw on a canvas, returned by [`Canvas::draw`], so it can still be changed until the canvas is prepared.
+///
+/// IDs are only valid until the canvas is cleared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CommandId(u32);
+
+/// Changes made to a draw with [`Canvas::set_transform`] and [`Canvas::set_tint`].
+struct DrawChanges {
+    /// Transform composed from the pushed transforms when drawn.
+    pushed: Affine2,
+    /// Full transform the draw was made with.
+    drawn: Affine2,
+    /// Transform applied to every item of the draw, on top of the one it was drawn with.
+    transform: Affine2,
+    tint: Vec4,
 }
 
-impl Renderer {
-    /// Creates a new renderer.
-    pub fn new(device: &wgpu::Device, texture_format: wgpu::TextureFormat) -> Self {
-        Self {
-            renderer: spright::Renderer::new(device, texture_format),
-            cache: Cache::new(),
-            #[cfg(feature = "text")]
-            text_sprite_maker: text::SpriteMaker::new(device),
+/// Things that can be drawn.
+pub trait Drawable<'a>
+where
+    Self: Sized + Clone,
+{
+    /// Called to draw the item to the canvas.
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2);
+
+    /// Adds a tint to the drawable, either a [`Color`] or a [`Colorf`].
+    ///
+    /// Nested tints are multiplied in `f32` and only rounded to 8 bits once, when the canvas is prepared. Components are clamped to `[0, 1]`, see [`Drawable::hdr_tinted`] for brighter tints.
+    fn tinted(&self, tint: impl Tint) -> impl Drawable<'a> {
+        Tinted {
+            drawable: self.clone(),
+            tint: tint.to_colorf(),
         }
     }
 
-    /// Prepares a scene for rendering.
-    pub fn prepare(
-        &mut self,
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
-        font_system: &mut cosmic_text::FontSystem,
-        target_size: wgpu::Extent3d,
-        canvas: &Canvas,
-    ) -> Result<(), Error> {
-        let mut staged = vec![];
-
-        enum Staged<'a> {
-            Sprite(spright::batch::Sprite<'a>),
-            TextSprite(text::TextSprite),
+    /// Adds deterministic variation to the drawable, applied at prepare time.
+    fn varied(&self, variation: variation::Variation) -> impl Drawable<'a> {
+        Varied {
+            drawable: self.clone(),
+            variation,
         }
+    }
 
-        for cmd in canvas.commands.iter() {
-            if let Command::Sprite(sprite) = cmd {
-                sprite
-                    .texture
-                    .upload_to_wgpu(device, queue, &mut self.cache);
-            }
+    /// Draws the drawable with the given blend mode.
+    fn blended(&self, blend_mode: BlendMode) -> impl Drawable<'a> {
+        Blended {
+            drawable: self.clone(),
+            blend_mode,
         }
+    }
 
-        for cmd in canvas.commands.iter() {
-            match cmd {
-                Command::Sprite(sprite) => {
-                    staged.push(Staged::Sprite(spright::batch::Sprite {
-                        texture: sprite.texture.get_wgpu_texture(&self.cache).unwrap(),
-                        src_offset: sprite.src_offset,
-                        src_size: sprite.src_size,
-                        src_layer: sprite.src_layer,
-                        transform: sprite.transform,
-                        tint: sprite.tint,
-                    }));
+    /// Draws the drawable with a custom material.
+    fn with_material(&self, material: material::Material) -> impl Drawable<'a> {
+        WithMaterial {
+            drawable: self.clone(),
+            material,
+        }
+    }
+
+    /// Draws the drawable's textures as indices into a row of palette colors, e.g. to recolor a sprite by swapping in another row.
+    ///
+    /// Texels are read exactly rather than filtered, and their red channel scaled to `0..=255` gives the index, so indexed textures are usually [`wgpu::TextureFormat::R8Unorm`]. Index `i` is drawn with texel `i` of the top row of `palette`, or its last texel if `i` is past the end. Materials are ignored, and reflected copies are not faded.
+    fn with_palette<T: Texture>(&self, palette: TextureSlice<'a, T>) -> impl Drawable<'a> {
+        WithPalette {
+            drawable: self.clone(),
+            palette: PaletteRow {
+                texture: palette.texture,
+                offset: palette.rect.offset,
+                width: palette.rect.size.x.max(1),
+                layer: palette.layer,
+            },
+        }
+    }
+
+    /// Marks the drawable as emissive with the given intensity, so it shows up in [`Renderer::render_emissive_to_target`].
+    fn emissive(&self, intensity: f32) -> impl Drawable<'a> {
+        Emissive {
+            drawable: self.clone(),
+            intensity,
+        }
+    }
+
+    /// Multiplies the drawable's tint by an unclamped linear RGBA color, e.g. to draw brighter than white into an [`wgpu::TextureFormat::Rgba16Float`] target as part of an HDR pipeline.
+    ///
+    /// Nested HDR tints multiply together. The resulting alpha is clamped to `[0, 1]`, and on targets that clamp to `[0, 1]`, color components above 1 saturate.
+    fn hdr_tinted(&self, tint: Colorf) -> impl Drawable<'a> {
+        HdrTinted {
+            drawable: self.clone(),
+            tint,
+        }
+    }
+
+    /// Places the drawable so that a point within its bounds, rather than its top-left corner, sits at the origin of its transform.
+    ///
+    /// `anchor` is in normalized coordinates of the drawable's bounds, see [`anchor`] for named points. Rotation and scaling in the transform then happen around the anchor.
+    fn anchored(&self, anchor: glam::Vec2) -> impl Drawable<'a> {
+        Anchored {
+            drawable: self.clone(),
+            anchor,
+        }
+    }
+
+    /// Draws a drop shadow behind the drawable, offset in its local space.
+    ///
+    /// The shadow is a copy of the drawable tinted with `color`, so it is a solid silhouette for text and other white masks.
+    fn shadowed(&self, offset: glam::Vec2, color: Color) -> impl Drawable<'a> {
+        Shadowed {
+            drawable: self.clone(),
+            offset,
+            color,
+        }
+    }
+
+    /// Sets the priority of the drawable's draws when the renderer's draw budget is exceeded, overriding the priority of their layer.
+    ///
+    /// Draws with the lowest priority are dropped first, so e.g. HUD elements and text can be given [`i32::MAX`] to never be dropped before anything else. See [`Canvas::set_layer_priority`].
+    fn prioritized(&self, priority: i32) -> impl Drawable<'a> {
+        Prioritized {
+            drawable: self.clone(),
+            priority,
+        }
+    }
+
+    /// Rounds the drawable's position to whole target pixels when prepared, so pixel art stays crisp instead of blurring or shimmering as it moves.
+    ///
+    /// Only sprites, tiled sprites and tilemaps are snapped, and only if their final transform, after layer views and the renderer's scale factor, is unrotated with whole-number scales. See [`Canvas::set_pixel_snapping`] to snap everything.
+    fn snapped(&self) -> impl Drawable<'a> {
+        Snapped {
+            drawable: self.clone(),
+        }
+    }
+
+    /// Draws an outline of the given width, in local units, around the drawable.
+    ///
+    /// The outline is made of copies of the drawable tinted with `color` and offset in a ring around it, so it suits text and other white masks and thin outlines.
+    fn outlined(&self, width: f32, color: Color) -> impl Drawable<'a> {
+        Outlined {
+            drawable: self.clone(),
+            width,
+            color,
+        }
+    }
+}
+
+/// How drawn pixels are combined with the pixels already in the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BlendMode {
+    /// Standard alpha blending of straight (non-premultiplied) colors.
+    #[default]
+    Alpha,
+
+    /// Adds the color, scaled by its alpha, to the target. Useful for particles, glows and light.
+    Additive,
+
+    /// Multiplies the target by the color, scaled by its alpha. Useful for shadows and darkening.
+    Multiply,
+
+    /// Alpha blending of textures whose colors are already premultiplied by their alpha.
+    Premultiplied,
+}
+
+impl BlendMode {
+    /// All blend modes, in declaration order.
+    pub(crate) const ALL: [BlendMode; 4] = [
+        BlendMode::Alpha,
+        BlendMode::Additive,
+        BlendMode::Multiply,
+        BlendMode::Premultiplied,
+    ];
+}
+
+#[cfg(feature = "text")]
+impl<'a> Drawable<'a> for text::Label {
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
+        canvas.push(Command::Text(Box::new(text::Section {
+            label: self.clone(),
+            transform,
+            tint,
+        })));
+    }
+}
+
+/// Trait for textures.
+///
+/// These textures can either be resident on the CPU, in which case they must be uploaded as needed; or on the GPU, on which case they can be used directly but you must manage the lifecycle of textures yourself.
+pub trait Texture {
+    /// The size of the texture.
+    fn size(&self) -> wgpu::Extent3d;
+
+    /// Uploads the texture to the GPU.
+    ///
+    /// If the texture is already uploaded, does nothing.
+    fn upload_to_wgpu(&self, device: &wgpu::Device, queue: &wgpu::Queue, cache: &mut Cache);
+
+    /// Gets the wgpu texture.
+    ///
+    /// If the texture is not uploaded yet, returns [`None`].
+    fn get_wgpu_texture<'a>(&'a self, cache: &'a Cache) -> Option<&'a wgpu::Texture>;
+
+    /// Gets the offset in texels and array layer of the texture within its wgpu texture.
+    ///
+    /// This is only non-zero for textures packed into an atlas.
+    fn get_wgpu_origin(&self, _cache: &Cache) -> wgpu::Origin3d {
+        wgpu::Origin3d::ZERO
+    }
+
+    /// Gets the named regions of the texture, if it carries any.
+    fn regions(&self) -> Option<&region::Regions> {
+        None
+    }
+
+    /// Gets a named region of the texture as a slice.
+    ///
+    /// Returns [`None`] if there is no region with that name or it does not fit within the texture.
+    fn region(&self, name: &str) -> Option<TextureSlice<'_, Self>>
+    where
+        Self: Sized,
+    {
+        let region = self.regions()?.get(name)?;
+        TextureSlice::from_layer(self, region.layer)?.slice(region.offset, region.size)
+    }
+}
+
+/// Textures uploaded to the GPU by a [`Renderer`].
+pub struct Cache {
+    textures: std::collections::HashMap<u64, wgpu::Texture>,
+    /// Version of the pixels last uploaded for each dynamic texture.
+    versions: std::collections::HashMap<u64, u64>,
+    /// Liveness of the texture each entry was uploaded from, so entries can be freed once it is dropped.
+    owners: std::collections::HashMap<u64, std::sync::Weak<()>>,
+    atlas: Option<atlas::Atlas<u64, Color>>,
+    atlas_options: ImageAtlasOptions,
+    /// ID of the block of white texels reserved in the atlas.
+    white_id: u64,
+    /// Whether pixels are premultiplied by their alpha when uploaded.
+    premultiply: bool,
+    /// Number of textures uploaded since the last prepare began, see [`debug::FrameStats::texture_uploads`].
+    uploads: u32,
+}
+
+impl Cache {
+    fn new(atlas_options: ImageAtlasOptions, premultiply: bool) -> Self {
+        Self {
+            textures: std::collections::HashMap::new(),
+            versions: std::collections::HashMap::new(),
+            owners: std::collections::HashMap::new(),
+            atlas: None,
+            atlas_options,
+            white_id: next_texture_id(),
+            premultiply,
+            uploads: 0,
+        }
+    }
+
+    /// Gets the texture untextured draws sample and the texel they sample, which is the middle of the white texels in the image atlas once it has been created, so they batch with images in it.
+    fn white_texel(&self) -> (StagedTexture<'_>, Vec2) {
+        let Some((atlas, allocation)) = self
+            .atlas
+            .as_ref()
+            .and_then(|atlas| Some((atlas, atlas.get(self.white_id)?)))
+        else {
+            return (StagedTexture::White, Vec2::splat(0.5));
+        };
+        (
+            StagedTexture::Texture(atlas.texture()),
+            Vec2::new(
+                allocation.rectangle.min.x as f32,
+                allocation.rectangle.min.y as f32,
+            ) + 1.5,
+        )
+    }
+
+    /// Gets whether a texture has been uploaded.
+    ///
+    /// A [`DynamicTexture`] whose pixels changed since it was uploaded still counts, as it is only reuploaded when next drawn.
+    pub fn contains(&self, texture: &(impl Texture + ?Sized)) -> bool {
+        texture.get_wgpu_texture(self).is_some()
+    }
+
+    /// Gets pixels as they should be uploaded, color keyed and premultiplied if needed.
+    fn pixels_for_upload<'b>(
+        &self,
+        format: wgpu::TextureFormat,
+        pixels: &'b [u8],
+        color_key: Option<Color>,
+    ) -> std::borrow::Cow<'b, [u8]> {
+        let pixels = match color_key.and_then(|key| upload::color_key(format, pixels, key)) {
+            Some(keyed) => std::borrow::Cow::Owned(keyed),
+            None => std::borrow::Cow::Borrowed(pixels),
+        };
+        match self
+            .premultiply
+            .then(|| upload::premultiply(format, &pixels))
+            .flatten()
+        {
+            Some(premultiplied) => std::borrow::Cow::Owned(premultiplied),
+            None => pixels,
+        }
+    }
+
+    /// Frees the GPU copies of textures that have been dropped.
+    fn remove_dropped(&mut self, queue: &wgpu::Queue) {
+        let dropped = self
+            .owners
+            .iter()
+            .filter(|(_, owner)| owner.strong_count() == 0)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for id in dropped {
+            self.owners.remove(&id);
+            self.textures.remove(&id);
+            self.versions.remove(&id);
+            if let Some(atlas) = &mut self.atlas {
+                atlas.remove(queue, &id);
+            }
+        }
+    }
+
+    /// Uploads an image, packing it into the shared atlas if it is small enough.
+    fn upload_image(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, image: &Image) {
+        if self.textures.contains_key(&image.id)
+            || self
+                .atlas
+                .as_ref()
+                .is_some_and(|atlas| atlas.get(image.id).is_some())
+        {
+            return;
+        }
+        self.owners
+            .insert(image.id, std::sync::Arc::downgrade(&image.alive));
+
+        let desc = &image.desc;
+        let pixels = self.pixels_for_upload(desc.format, &image.pixels, image.color_key);
+        let (width, height) = (desc.size.width as usize, desc.size.height as usize);
+        if !image.mipmaps
+            && desc.format == self.atlas_options.format
+            && matches!(
+                desc.format,
+                wgpu::TextureFormat::Rgba8Unorm
+                    | wgpu::TextureFormat::Rgba8UnormSrgb
+                    | wgpu::TextureFormat::Bgra8Unorm
+                    | wgpu::TextureFormat::Bgra8UnormSrgb
+            )
+            && desc.dimension == wgpu::TextureDimension::D2
+            && desc.size.depth_or_array_layers == 1
+            && desc.mip_level_count == 1
+            && desc.sample_count == 1
+            && width > 0
+            && height > 0
+            && desc.size.width.max(desc.size.height) <= self.atlas_options.max_image_size
+        {
+            let size = self.atlas_options.initial_size;
+            let atlas = self.atlas.get_or_insert_with(|| {
+                let mut atlas = atlas::Atlas::with_format(
+                    device,
+                    "canvasette: image_atlas",
+                    wgpu::Extent3d {
+                        width: size,
+                        height: size,
+                        depth_or_array_layers: 1,
+                    },
+                    u32::MAX,
+                    self.atlas_options.format,
+                    Default::default(),
+                );
+                // Reserved for untextured draws, see `Cache::white_texel`.
+                atlas.add(
+                    device,
+                    queue,
+                    self.white_id,
+                    imgref::ImgRef::new(&[palette::WHITE; 9], 3, 3),
+                );
+                atlas
+            });
+            // Images are padded by a copy of their edge pixels, so filtering at the edges does not bleed in neighboring images.
+            let pixels: &[Color] = bytemuck::cast_slice(&pixels);
+            let padded = (0..height + 2)
+                .flat_map(|y| {
+                    let y = y.saturating_sub(1).min(height - 1);
+                    (0..width + 2)
+                        .map(move |x| pixels[y * width + x.saturating_sub(1).min(width - 1)])
+                })
+                .collect::<Vec<_>>();
+            if atlas
+                .add(
+                    device,
+                    queue,
+                    image.id,
+                    imgref::ImgRef::new(&padded, width + 2, height + 2),
+                )
+                .is_some()
+            {
+                self.uploads += 1;
+                return;
+            }
+        }
+
+        self.uploads += 1;
+        let mut desc = wgpu::TextureDescriptor {
+            label: image
+                .name
+                .as_deref()
+                .or(desc.label)
+                .or(Some("canvasette: image")),
+            ..*desc
+        };
+        let mut order = image.order;
+        let mipmaps = (image.mipmaps
+            && desc.mip_level_count == 1
+            && desc.dimension == wgpu::TextureDimension::D2)
+            .then(|| upload::mipmaps(desc.format, desc.size, &pixels))
+            .flatten();
+        let pixels = match mipmaps {
+            Some((pixels, levels)) => {
+                desc.mip_level_count = levels;
+                order = wgpu::util::TextureDataOrder::LayerMajor;
+                std::borrow::Cow::Owned(pixels)
+            }
+            None => pixels,
+        };
+        self.textures.insert(
+            image.id,
+            upload::create_texture_with_data(device, queue, &desc, order, &pixels),
+        );
+    }
+}
+
+/// An image.
+///
+/// This is a texture that may be reuploaded to the GPU as necessary, in the format given by its descriptor. Small single-layer images in the image atlas's format, [`wgpu::TextureFormat::Rgba8UnormSrgb`] unless changed with [`ImageAtlasOptions::format`], are packed into an atlas shared with other images, so sprites drawn from different images can still be batched together.
+///
+/// Once an image is dropped, renderers free its GPU copy the next time they prepare a scene.
+pub struct Image {
+    id: u64,
+    /// Only held to be observed by the caches the image is uploaded to.
+    alive: std::sync::Arc<()>,
+    pixels: Vec<u8>,
+    desc: wgpu::TextureDescriptor<'static>,
+    order: wgpu::util::TextureDataOrder,
+    regions: region::Regions,
+    /// Name labeling the GPU copy, see [`Image::new_named`].
+    name: Option<String>,
+    /// Color made transparent when uploaded, see [`Image::with_color_key`].
+    color_key: Option<Color>,
+    /// Whether mipmaps are generated when uploaded, see [`Image::with_mipmaps`].
+    mipmaps: bool,
+}
+
+impl Image {
+    /// Creates a new image.
+    ///
+    /// Images with more than one mip level hold the pixels of every level, ordered layer by layer unless changed with [`Image::with_data_order`].
+    pub fn new(pixels: Vec<u8>, desc: wgpu::TextureDescriptor<'static>) -> Self {
+        Self {
+            id: next_texture_id(),
+            alive: std::sync::Arc::new(()),
+            pixels,
+            desc,
+            order: wgpu::util::TextureDataOrder::LayerMajor,
+            regions: region::Regions::new(),
+            name: None,
+            color_key: None,
+            mipmaps: false,
+        }
+    }
+
+    /// Creates a new image with a name, e.g. its asset path, which labels its GPU copy in GPU captures instead of the descriptor's label.
+    ///
+    /// Images packed into the image atlas have no GPU texture of their own, so they show up as part of the atlas.
+    pub fn new_named(
+        name: impl Into<String>,
+        pixels: Vec<u8>,
+        desc: wgpu::TextureDescriptor<'static>,
+    ) -> Self {
+        Self {
+            name: Some(name.into()),
+            ..Self::new(pixels, desc)
+        }
+    }
+
+    /// Gets the name given to [`Image::new_named`], if any.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Sets the order of the mip levels and array layers in the pixels, as given to [`wgpu::util::DeviceExt::create_texture_with_data`].
+    pub fn with_data_order(self, order: wgpu::util::TextureDataOrder) -> Self {
+        Self { order, ..self }
+    }
+
+    /// Makes pixels of a color transparent when the image is uploaded, e.g. the magenta background of an old sprite sheet. The key's alpha is ignored.
+    ///
+    /// Keyed pixels become transparent black. Only 8-bit RGBA and BGRA images are keyed, and the pixels held by the image are left as they are.
+    pub fn with_color_key(self, key: Color) -> Self {
+        Self {
+            color_key: Some(key),
+            ..self
+        }
+    }
+
+    /// Generates mipmaps when the image is uploaded, so it does not alias and shimmer when drawn much smaller than its size, e.g. a tileset on a zoomed-out map.
+    ///
+    /// Images with mipmaps get a GPU texture of their own instead of being packed into the image atlas. Only 8-bit RGBA and BGRA images with a single mip level get mipmaps. Mip levels are blended between when the renderer's sampler filters linearly.
+    pub fn with_mipmaps(self) -> Self {
+        Self {
+            mipmaps: true,
+            ..self
+        }
+    }
+
+    /// Creates a new [`wgpu::TextureFormat::Rgba8UnormSrgb`] image from a baked atlas, with its named regions.
+    pub fn from_baked(atlas: &region::BakedAtlas) -> Self {
+        let mut image = Self::new(
+            atlas.pixels.to_vec(),
+            wgpu::TextureDescriptor {
+                label: None,
+                size: wgpu::Extent3d {
+                    width: atlas.width,
+                    height: atlas.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+        );
+        image
+            .regions
+            .extend(atlas.regions.iter().map(|(name, region)| (*name, *region)));
+        image
+    }
+
+    /// Replaces the pixels, e.g. with ones of a different size. The GPU copy of the old pixels is freed and the new ones uploaded the next time a scene is prepared.
+    ///
+    /// The new pixels are a single mip level, so the image is left with one, though [`Image::with_mipmaps`] still generates the rest when it is uploaded. Slices of the image keep their old rectangles, so they should be recreated if the size changed.
+    ///
+    /// Panics if the pixels are not the right length for the new size.
+    pub fn replace(&mut self, pixels: Vec<u8>, size: wgpu::Extent3d) {
+        assert_eq!(pixels.len(), upload::data_len(self.desc.format, size));
+        // Images are never reuploaded in place, so a new ID is needed, and the cache entry under the old one is freed as if the image had been dropped.
+        self.id = next_texture_id();
+        self.alive = std::sync::Arc::new(());
+        self.pixels = pixels;
+        self.desc.size = size;
+        self.desc.mip_level_count = 1;
+    }
+
+    /// Gets the named regions of the image for modification.
+    pub fn regions_mut(&mut self) -> &mut region::Regions {
+        &mut self.regions
+    }
+}
+
+/// Allocates an ID for a texture kept in the [`Cache`].
+fn next_texture_id() -> u64 {
+    static TEXTURE_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    TEXTURE_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+}
+
+impl Texture for Image {
+    fn size(&self) -> wgpu::Extent3d {
+        self.desc.size
+    }
+
+    fn upload_to_wgpu(&self, device: &wgpu::Device, queue: &wgpu::Queue, cache: &mut Cache) {
+        cache.upload_image(device, queue, self);
+    }
+
+    fn get_wgpu_texture<'a>(&'a self, cache: &'a Cache) -> Option<&'a wgpu::Texture> {
+        if let Some(texture) = cache.textures.get(&self.id) {
+            return Some(texture);
+        }
+        let atlas = cache.atlas.as_ref()?;
+        atlas.get(self.id)?;
+        Some(atlas.texture())
+    }
+
+    fn get_wgpu_origin(&self, cache: &Cache) -> wgpu::Origin3d {
+        match cache.atlas.as_ref().and_then(|atlas| atlas.get(self.id)) {
+            Some(allocation) => wgpu::Origin3d {
+                x: allocation.rectangle.min.x as u32 + 1,
+                y: allocation.rectangle.min.y as u32 + 1,
+                z: 0,
+            },
+            None => wgpu::Origin3d::ZERO,
+        }
+    }
+
+    fn regions(&self) -> Option<&region::Regions> {
+        Some(&self.regions)
+    }
+}
+
+/// A texture whose pixels can be changed every frame, e.g. for video or software-rendered effects.
+///
+/// Changed pixels are written to the GPU once, the next time a scene drawing the texture is prepared. Dynamic textures are never packed into the image atlas, and must have a single mip level. As with [`Image`], the GPU copy is freed after the texture is dropped.
+pub struct DynamicTexture {
+    id: u64,
+    alive: std::sync::Arc<()>,
+    version: u64,
+    pixels: Vec<u8>,
+    desc: wgpu::TextureDescriptor<'static>,
+    regions: region::Regions,
+    /// Name labeling the GPU copy, see [`DynamicTexture::new_named`].
+    name: Option<String>,
+}
+
+impl DynamicTexture {
+    /// Creates a new dynamic texture.
+    ///
+    /// The descriptor's usage must include [`wgpu::TextureUsages::COPY_DST`].
+    pub fn new(pixels: Vec<u8>, desc: wgpu::TextureDescriptor<'static>) -> Self {
+        Self {
+            id: next_texture_id(),
+            alive: std::sync::Arc::new(()),
+            version: 0,
+            pixels,
+            desc,
+            regions: region::Regions::new(),
+            name: None,
+        }
+    }
+
+    /// Creates a new dynamic texture with a name, which labels its GPU copy in GPU captures instead of the descriptor's label.
+    pub fn new_named(
+        name: impl Into<String>,
+        pixels: Vec<u8>,
+        desc: wgpu::TextureDescriptor<'static>,
+    ) -> Self {
+        Self {
+            name: Some(name.into()),
+            ..Self::new(pixels, desc)
+        }
+    }
+
+    /// Gets the name given to [`DynamicTexture::new_named`], if any.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Gets the descriptor of the GPU copy, labeled with the texture's name.
+    fn labeled_desc(&self) -> wgpu::TextureDescriptor<'_> {
+        wgpu::TextureDescriptor {
+            label: self
+                .name
+                .as_deref()
+                .or(self.desc.label)
+                .or(Some("canvasette: dynamic_texture")),
+            ..self.desc
+        }
+    }
+
+    /// Gets the named regions of the texture for modification.
+    pub fn regions_mut(&mut self) -> &mut region::Regions {
+        &mut self.regions
+    }
+
+    /// Gets the pixels.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Gets the pixels for modification, marking the texture as needing to be reuploaded.
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        self.version += 1;
+        &mut self.pixels
+    }
+
+    /// Replaces the pixels, which must be the same length as before.
+    pub fn set_pixels(&mut self, pixels: Vec<u8>) {
+        assert_eq!(pixels.len(), self.pixels.len());
+        self.version += 1;
+        self.pixels = pixels;
+    }
+
+    /// Replaces the pixels with ones of a different size. The GPU texture is recreated at the new size the next time a scene drawing the texture is prepared.
+    ///
+    /// Slices of the texture keep their old rectangles, so they should be recreated too.
+    ///
+    /// Panics if the pixels are not the right length for the new size.
+    pub fn replace(&mut self, pixels: Vec<u8>, size: wgpu::Extent3d) {
+        assert_eq!(pixels.len(), upload::data_len(self.desc.format, size));
+        self.version += 1;
+        self.pixels = pixels;
+        self.desc.size = size;
+    }
+}
+
+impl Texture for DynamicTexture {
+    fn size(&self) -> wgpu::Extent3d {
+        self.desc.size
+    }
+
+    fn upload_to_wgpu(&self, device: &wgpu::Device, queue: &wgpu::Queue, cache: &mut Cache) {
+        let Some(texture) = cache.textures.get(&self.id) else {
+            cache.uploads += 1;
+            let pixels = cache.pixels_for_upload(self.desc.format, &self.pixels, None);
+            let texture = upload::create_texture_with_data(
+                device,
+                queue,
+                &self.labeled_desc(),
+                Default::default(),
+                &pixels,
+            );
+            cache.textures.insert(self.id, texture);
+            cache.versions.insert(self.id, self.version);
+            cache
+                .owners
+                .insert(self.id, std::sync::Arc::downgrade(&self.alive));
+            return;
+        };
+        if cache.versions.get(&self.id) == Some(&self.version) {
+            return;
+        }
+        cache.uploads += 1;
+        let pixels = cache.pixels_for_upload(self.desc.format, &self.pixels, None);
+        if texture.size() != self.desc.size {
+            let texture = upload::create_texture_with_data(
+                device,
+                queue,
+                &self.labeled_desc(),
+                Default::default(),
+                &pixels,
+            );
+            cache.textures.insert(self.id, texture);
+            cache.versions.insert(self.id, self.version);
+            return;
+        }
+        upload::write_texture(device, queue, texture, &pixels);
+        cache.versions.insert(self.id, self.version);
+    }
+
+    fn get_wgpu_texture<'a>(&'a self, cache: &'a Cache) -> Option<&'a wgpu::Texture> {
+        cache.textures.get(&self.id)
+    }
+
+    fn regions(&self) -> Option<&region::Regions> {
+        Some(&self.regions)
+    }
+}
+
+impl Texture for wgpu::Texture {
+    fn size(&self) -> wgpu::Extent3d {
+        self.size()
+    }
+
+    fn upload_to_wgpu(&self, _device: &wgpu::Device, _queue: &wgpu::Queue, _cache: &mut Cache) {}
+
+    fn get_wgpu_texture<'a>(&'a self, _cache: &'a Cache) -> Option<&'a wgpu::Texture> {
+        Some(self)
+    }
+}
+
+/// A scene prepared into its own buffers by [`Renderer::batch`].
+pub struct Prepared {
+    frame: gpu::Frame,
+}
+
+/// An offscreen texture that can be rendered to with [`Renderer::render_to_target`] and then drawn like any other texture.
+pub struct RenderTarget {
+    /// Label of the texture and of the passes rendering to it.
+    label: String,
+    texture: wgpu::Texture,
+    /// Multisampled texture drawn to and resolved into `texture`, if the renderer uses MSAA.
+    multisampled: Option<wgpu::Texture>,
+    frame: gpu::Frame,
+}
+
+impl RenderTarget {
+    /// Gets the underlying wgpu texture.
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    /// Resizes the target.
+    ///
+    /// If the size changes, the texture is recreated and its contents are lost.
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        if self.texture.width() == width && self.texture.height() == height {
+            return;
+        }
+        self.texture = create_render_target_texture(
+            device,
+            &self.label,
+            self.texture.format(),
+            1,
+            width,
+            height,
+        );
+        self.multisampled = self.multisampled.as_ref().map(|multisampled| {
+            create_render_target_texture(
+                device,
+                &self.label,
+                multisampled.format(),
+                multisampled.sample_count(),
+                width,
+                height,
+            )
+        });
+    }
+}
+
+fn create_render_target_texture(
+    device: &wgpu::Device,
+    label: &str,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    width: u32,
+    height: u32,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(&if sample_count > 1 {
+            format!("{label} (multisampled)")
+        } else {
+            label.to_string()
+        }),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: if sample_count > 1 {
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+        } else {
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC
+        },
+        view_formats: &[],
+    })
+}
+
+impl Texture for RenderTarget {
+    fn size(&self) -> wgpu::Extent3d {
+        self.texture.size()
+    }
+
+    fn upload_to_wgpu(&self, _device: &wgpu::Device, _queue: &wgpu::Queue, _cache: &mut Cache) {}
+
+    fn get_wgpu_texture<'a>(&'a self, _cache: &'a Cache) -> Option<&'a wgpu::Texture> {
+        Some(&self.texture)
+    }
+}
+
+/// Represents a slice of a texture to draw.
+pub struct TextureSlice<'a, T> {
+    texture: &'a T,
+    layer: u32,
+    rect: Rect,
+    flip: BVec2,
+}
+
+impl<'a, T> Clone for TextureSlice<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for TextureSlice<'a, T> {}
+
+impl<'a, T> TextureSlice<'a, T>
+where
+    T: Texture,
+{
+    /// Creates a new texture slice from a raw texture.
+    pub fn from_layer(texture: &'a T, layer: u32) -> Option<Self> {
+        let size = texture.size();
+        if layer >= size.depth_or_array_layers {
+            return None;
+        }
+        Some(Self {
+            texture,
+            layer,
+            rect: Rect::new(0, 0, size.width, size.height),
+            flip: BVec2::FALSE,
+        })
+    }
+
+    /// Slices the texture slice.
+    ///
+    /// Note that `offset` represents an offset into the slice and not into the overall texture -- the returned slice's offset will be the current offset + new offset. The offset ignores any flipping, and the returned slice is flipped the same way as this one.
+    ///
+    /// Returns [`None`] if the slice goes out of bounds.
+    pub fn slice(&self, offset: glam::IVec2, size: glam::UVec2) -> Option<Self> {
+        let rect = Rect {
+            offset: self.rect.offset + offset,
+            size,
+        };
+        if !self.rect.contains_rect(&rect) {
+            return None;
+        }
+        Some(Self {
+            texture: self.texture,
+            layer: self.layer,
+            rect,
+            flip: self.flip,
+        })
+    }
+
+    /// Gets the size of the texture slice.
+    pub fn size(&self) -> glam::UVec2 {
+        self.rect.size
+    }
+
+    /// Gets the area of the texture covered by the slice, in texels.
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    /// Returns a copy mirrored horizontally.
+    ///
+    /// Only the image is mirrored: the slice still covers the same area when drawn, so its origin stays at the top-left corner. Flipping twice restores the original.
+    pub fn flip_x(&self) -> Self {
+        Self {
+            flip: BVec2::new(!self.flip.x, self.flip.y),
+            ..*self
+        }
+    }
+
+    /// Returns a copy mirrored vertically.
+    ///
+    /// Only the image is mirrored: the slice still covers the same area when drawn, so its origin stays at the top-left corner. Flipping twice restores the original.
+    pub fn flip_y(&self) -> Self {
+        Self {
+            flip: BVec2::new(self.flip.x, !self.flip.y),
+            ..*self
+        }
+    }
+
+    /// Gets whether the slice is mirrored along each axis.
+    pub fn flipped(&self) -> BVec2 {
+        self.flip
+    }
+
+    /// Draws the slice with a tint per corner, interpolated across it, e.g. for gradients or fake lighting.
+    ///
+    /// Corners are ordered top-left, top-right, bottom-left, bottom-right, and are multiplied with the tint the slice is drawn with.
+    pub fn corner_tinted(&self, tints: [Color; 4]) -> impl Drawable<'a> {
+        CornerTinted {
+            slice: *self,
+            tints,
+        }
+    }
+
+    /// Draws the slice with a vertical gradient from `top` to `bottom`.
+    pub fn vertical_gradient(&self, top: Color, bottom: Color) -> impl Drawable<'a> {
+        self.corner_tinted([top, top, bottom, bottom])
+    }
+
+    /// Draws the slice with a horizontal gradient from `left` to `right`.
+    pub fn horizontal_gradient(&self, left: Color, right: Color) -> impl Drawable<'a> {
+        self.corner_tinted([left, right, left, right])
+    }
+
+    /// Draws the slice repeated to fill an area of the given size, starting from the top-left corner.
+    ///
+    /// Tiles along the right and bottom edges are cropped to the area. The whole area is a single draw, so filling a background with it is much cheaper than drawing each tile.
+    pub fn tiled(&self, size: glam::UVec2) -> impl Drawable<'a> {
+        self.tiled_with(size, WrapMode::Repeat)
+    }
+
+    /// Draws the slice filling an area of the given size, starting from the top-left corner, wrapped past its edges as given by `mode`.
+    pub fn tiled_with(&self, size: glam::UVec2, mode: WrapMode) -> impl Drawable<'a> {
+        Tiled {
+            slice: *self,
+            size,
+            mode,
+        }
+    }
+
+    /// Splits the slice into a grid of `columns` by `rows` equally sized cells, e.g. the frames of a uniform sprite sheet.
+    ///
+    /// If the slice does not divide evenly, the leftover texels along the right and bottom edges are not part of any cell. Returns [`None`] if either count is zero or larger than the slice's size along that axis.
+    pub fn grid(&self, columns: u32, rows: u32) -> Option<grid::Grid<'a, T>> {
+        grid::Grid::new(*self, glam::UVec2::new(columns, rows))
+    }
+
+    /// Maps a point in the slice's local space, from the origin to [`TextureSlice::size`], to texture coordinates in texels, accounting for flipping.
+    pub(crate) fn tex_coords(&self, p: Vec2) -> Vec2 {
+        let size = self.rect.size.as_vec2();
+        self.rect.offset.as_vec2() + Vec2::select(self.flip, size - p, p)
+    }
+}
+
+impl<'a, T> Drawable<'a> for TextureSlice<'a, T>
+where
+    T: Texture,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
+        self.corner_tinted([palette::WHITE; 4])
+            .draw(canvas, tint, transform);
+    }
+}
+
+/// A texture slice drawn with a tint per corner.
+struct CornerTinted<'a, T> {
+    slice: TextureSlice<'a, T>,
+    tints: [Color; 4],
+}
+
+impl<'a, T> Clone for CornerTinted<'a, T> {
+    fn clone(&self) -> Self {
+        Self {
+            slice: self.slice,
+            tints: self.tints,
+        }
+    }
+}
+
+impl<'a, T> Drawable<'a> for CornerTinted<'a, T>
+where
+    T: Texture,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
+        canvas.push(Command::Sprite(Sprite {
+            transform,
+            tint,
+            texture: self.slice.texture,
+            src_offset: self.slice.rect.offset,
+            src_size: self.slice.rect.size,
+            src_layer: self.slice.layer,
+            flip: self.slice.flip,
+            corner_tints: self.tints,
+        }));
+    }
+}
+
+/// How a slice drawn with [`TextureSlice::tiled_with`] fills the area past its edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum WrapMode {
+    /// Repeats the slice.
+    #[default]
+    Repeat,
+    /// Repeats the slice, mirrored every other time, so tiles meet seamlessly at their edges.
+    MirrorRepeat,
+    /// Draws the slice once and stretches its edge texels over the rest of the area.
+    ClampToEdge,
+}
+
+/// A texture slice repeated to fill an area.
+struct Tiled<'a, T> {
+    slice: TextureSlice<'a, T>,
+    size: UVec2,
+    mode: WrapMode,
+}
+
+impl<'a, T> Clone for Tiled<'a, T> {
+    fn clone(&self) -> Self {
+        Self {
+            slice: self.slice,
+            size: self.size,
+            mode: self.mode,
+        }
+    }
+}
+
+impl<'a, T> Drawable<'a> for Tiled<'a, T>
+where
+    T: Texture,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
+        canvas.push(Command::Tiled(
+            Sprite {
+                transform,
+                tint,
+                texture: self.slice.texture,
+                src_offset: self.slice.rect.offset,
+                src_size: self.slice.rect.size,
+                src_layer: self.slice.layer,
+                flip: self.slice.flip,
+                corner_tints: [palette::WHITE; 4],
+            },
+            self.size,
+            self.mode,
+        ));
+    }
+}
+
+/// Multiplies two tints together.
+fn multiply_tint(a: Color, b: Color) -> Color {
+    Color::new(
+        ((a.r as u16 * b.r as u16) / 0xff) as u8,
+        ((a.g as u16 * b.g as u16) / 0xff) as u8,
+        ((a.b as u16 * b.b as u16) / 0xff) as u8,
+        ((a.a as u16 * b.a as u16) / 0xff) as u8,
+    )
+}
+
+/// Multiplies a tint by an `f32` tint in `[0, 1]`.
+fn scale_tint(a: Color, b: Vec4) -> Color {
+    if b == Vec4::ONE {
+        return a;
+    }
+    let c = Vec4::new(a.r as f32, a.g as f32, a.b as f32, a.a as f32) * b;
+    Color::new(
+        c.x.round() as u8,
+        c.y.round() as u8,
+        c.z.round() as u8,
+        c.w.round() as u8,
+    )
+}
+
+#[derive(Clone)]
+struct Tinted<T> {
+    drawable: T,
+    tint: Colorf,
+}
+
+impl<'a, T> Drawable<'a> for Tinted<T>
+where
+    T: Drawable<'a>,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
+        let prev = canvas.tint;
+        canvas.tint *= Vec4::from(<[f32; 4]>::from(self.tint)).clamp(Vec4::ZERO, Vec4::ONE);
+        self.drawable.draw(canvas, tint, transform);
+        canvas.tint = prev;
+    }
+}
+
+#[derive(Clone)]
+struct Varied<T> {
+    drawable: T,
+    variation: variation::Variation,
+}
+
+impl<'a, T> Drawable<'a> for Varied<T>
+where
+    T: Drawable<'a>,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
+        let prev = canvas.variation.replace(self.variation);
+        self.drawable.draw(canvas, tint, transform);
+        canvas.variation = prev;
+    }
+}
+
+#[derive(Clone)]
+struct Blended<T> {
+    drawable: T,
+    blend_mode: BlendMode,
+}
+
+impl<'a, T> Drawable<'a> for Blended<T>
+where
+    T: Drawable<'a>,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
+        let prev = std::mem::replace(&mut canvas.blend_mode, self.blend_mode);
+        self.drawable.draw(canvas, tint, transform);
+        canvas.blend_mode = prev;
+    }
+}
+
+#[derive(Clone)]
+struct Prioritized<T> {
+    drawable: T,
+    priority: i32,
+}
+
+impl<'a, T> Drawable<'a> for Prioritized<T>
+where
+    T: Drawable<'a>,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
+        let prev = canvas.priority.replace(self.priority);
+        self.drawable.draw(canvas, tint, transform);
+        canvas.priority = prev;
+    }
+}
+
+#[derive(Clone)]
+struct Snapped<T> {
+    drawable: T,
+}
+
+impl<'a, T> Drawable<'a> for Snapped<T>
+where
+    T: Drawable<'a>,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
+        let prev = std::mem::replace(&mut canvas.snap, true);
+        self.drawable.draw(canvas, tint, transform);
+        canvas.snap = prev;
+    }
+}
+
+#[derive(Clone)]
+struct WithMaterial<T> {
+    drawable: T,
+    material: material::Material,
+}
+
+impl<'a, T> Drawable<'a> for WithMaterial<T>
+where
+    T: Drawable<'a>,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
+        let prev = canvas.material.replace(self.material);
+        self.drawable.draw(canvas, tint, transform);
+        canvas.material = prev;
+    }
+}
+
+#[derive(Clone)]
+struct WithPalette<'a, T> {
+    drawable: T,
+    palette: PaletteRow<'a>,
+}
+
+impl<'a, T> Drawable<'a> for WithPalette<'a, T>
+where
+    T: Drawable<'a>,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
+        let prev = canvas.palette.replace(self.palette);
+        self.drawable.draw(canvas, tint, transform);
+        canvas.palette = prev;
+    }
+}
+
+#[derive(Clone)]
+struct HdrTinted<T> {
+    drawable: T,
+    tint: Colorf,
+}
+
+impl<'a, T> Drawable<'a> for HdrTinted<T>
+where
+    T: Drawable<'a>,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
+        let prev = canvas.hdr_tint;
+        canvas.hdr_tint *= Vec4::from(<[f32; 4]>::from(self.tint));
+        self.drawable.draw(canvas, tint, transform);
+        canvas.hdr_tint = prev;
+    }
+}
+
+#[derive(Clone)]
+struct Emissive<T> {
+    drawable: T,
+    intensity: f32,
+}
+
+impl<'a, T> Drawable<'a> for Emissive<T>
+where
+    T: Drawable<'a>,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
+        let prev = std::mem::replace(&mut canvas.emissive, self.intensity);
+        self.drawable.draw(canvas, tint, transform);
+        canvas.emissive = prev;
+    }
+}
+
+#[derive(Clone)]
+struct Shadowed<T> {
+    drawable: T,
+    offset: Vec2,
+    color: Color,
+}
+
+impl<'a, T> Drawable<'a> for Shadowed<T>
+where
+    T: Drawable<'a>,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
+        // The shadow's color replaces any tint, except for its alpha.
+        let prev = canvas.tint;
+        canvas.tint = Vec4::ONE.with_w(prev.w);
+        self.drawable.draw(
+            canvas,
+            palette::with_alpha(
+                self.color,
+                (self.color.a as u16 * tint.a as u16 / 255) as u8,
+            ),
+            transform * Affine2::from_translation(self.offset),
+        );
+        canvas.tint = prev;
+        self.drawable.draw(canvas, tint, transform);
+    }
+}
+
+#[derive(Clone)]
+struct Outlined<T> {
+    drawable: T,
+    width: f32,
+    color: Color,
+}
+
+impl<'a, T> Drawable<'a> for Outlined<T>
+where
+    T: Drawable<'a>,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
+        let color = palette::with_alpha(
+            self.color,
+            (self.color.a as u16 * tint.a as u16 / 255) as u8,
+        );
+        // Space copies at most about a unit apart along the ring, so it has no gaps.
+        let count = (self.width * std::f32::consts::TAU).ceil().max(8.0) as u32;
+        let prev = canvas.tint;
+        canvas.tint = Vec4::ONE.with_w(prev.w);
+        for i in 0..count {
+            let angle = i as f32 / count as f32 * std::f32::consts::TAU;
+            self.drawable.draw(
+                canvas,
+                color,
+                transform * Affine2::from_translation(Vec2::from_angle(angle) * self.width),
+            );
+        }
+        canvas.tint = prev;
+        self.drawable.draw(canvas, tint, transform);
+    }
+}
+
+#[derive(Clone)]
+struct Anchored<T> {
+    drawable: T,
+    anchor: Vec2,
+}
+
+impl<'a, T> Drawable<'a> for Anchored<T>
+where
+    T: Drawable<'a>,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: glam::Affine2) {
+        // Draw untransformed first, so the bounds of everything drawn are known before placing it.
+        let start = canvas.items.len();
+        self.drawable.draw(canvas, tint, Affine2::IDENTITY);
+        let items = &mut canvas.items[start..];
+        let Some(bounds) = items
+            .iter()
+            .map(|item| item.bounds())
+            .reduce(|bounds, item| bounds.union(&item))
+        else {
+            return;
+        };
+        let offset =
+            transform * Affine2::from_translation(-(bounds.min + bounds.size() * self.anchor));
+        for item in items {
+            let transform = item.command.transform_mut();
+            *transform = offset * *transform;
+        }
+    }
+}
+
+impl<'a> Default for Canvas<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Canvas<'a> {
+    pub fn new() -> Self {
+        Self {
+            items: vec![],
+            layer: 0,
+            z: 0,
+            blend_mode: BlendMode::Alpha,
+            material: None,
+            material_stack: vec![],
+            palette: None,
+            clip: None,
+            clip_stack: vec![],
+            variation: None,
+            emissive: 0.0,
+            tint: Vec4::ONE,
+            hdr_tint: Vec4::ONE,
+            priority: None,
+            snap: false,
+            pixel_snapping: false,
+            layers: std::collections::HashMap::new(),
+            view: Affine2::IDENTITY,
+            transform: Affine2::IDENTITY,
+            transform_stack: vec![],
+            group_stack: vec![],
+            draws: vec![],
+            current_draw: None,
+        }
+    }
+
+    /// Creates a new canvas with room for `capacity` draws before it reallocates.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            items: Vec::with_capacity(capacity),
+            ..Self::new()
+        }
+    }
+
+    /// Removes all draws and resets the draw state, keeping the allocated memory so the canvas can be reused for the next frame.
+    ///
+    /// Pushed clips, materials, transforms, opacity groups and masks are discarded, and the current layer, z-order, blend mode and other state set for subsequent draws return to their defaults. Layer settings, the canvas-wide view and pixel snapping are kept, as they usually persist across frames.
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.layer = 0;
+        self.z = 0;
+        self.blend_mode = BlendMode::Alpha;
+        self.material = None;
+        self.material_stack.clear();
+        self.palette = None;
+        self.clip = None;
+        self.clip_stack.clear();
+        self.variation = None;
+        self.emissive = 0.0;
+        self.tint = Vec4::ONE;
+        self.hdr_tint = Vec4::ONE;
+        self.priority = None;
+        self.snap = false;
+        self.transform = Affine2::IDENTITY;
+        self.transform_stack.clear();
+        self.group_stack.clear();
+        self.draws.clear();
+    }
+
+    fn push(&mut self, command: Command<'a>) {
+        self.items.push(Item {
+            layer: self.layer,
+            z: self.z,
+            blend_mode: self.blend_mode,
+            material: self.material,
+            palette: self.palette,
+            clip: self.clip,
+            variation: self.variation,
+            emissive: self.emissive,
+            tint: self.tint,
+            hdr_tint: self.hdr_tint,
+            priority: self.priority,
+            snap: self.snap,
+            draw: self.current_draw,
+            command,
+        });
+    }
+
+    /// Draws a drawable as a new draw, or as part of the current one if called from within another drawable.
+    fn record_draw(&mut self, drawable: impl Drawable<'a>, transform: glam::Affine2) -> CommandId {
+        let transform = self.transform * transform;
+        if let Some(id) = self.current_draw {
+            drawable.draw(self, palette::WHITE, transform);
+            return id;
+        }
+        let id = CommandId(self.draws.len() as u32);
+        self.draws.push(DrawChanges {
+            pushed: self.transform,
+            drawn: transform,
+            transform: Affine2::IDENTITY,
+            tint: Vec4::ONE,
+        });
+        self.current_draw = Some(id);
+        drawable.draw(self, palette::WHITE, transform);
+        self.current_draw = None;
+        id
+    }
+
+    /// Gets the transform an item is drawn with before its layer's view, including its variation and changes to its draw.
+    fn item_transform(&self, item: &Item) -> Affine2 {
+        let (min, max) = item.command.bounds();
+        let transform = match &item.variation {
+            Some(v) => v.apply_transform(item.command.transform(), (min + max) * 0.5),
+            None => item.command.transform(),
+        };
+        self.draw_changes(item).0 * transform
+    }
+
+    /// Gets the transform and tint applied to an item by changes to its draw.
+    fn draw_changes(&self, item: &Item) -> (Affine2, Vec4) {
+        item.draw
+            .and_then(|id| self.draws.get(id.0 as usize))
+            .map_or((Affine2::IDENTITY, Vec4::ONE), |changes| {
+                (changes.transform, changes.tint)
+            })
+    }
+
+    fn resolve_layers(
+        &self,
+        target_size: wgpu::Extent3d,
+        scale_factor: f32,
+    ) -> std::collections::HashMap<u32, ResolvedLayer> {
+        let target_size = Vec2::new(target_size.width as f32, target_size.height as f32);
+        let root = Affine2::from_scale(Vec2::splat(scale_factor));
+        self.layers
+            .iter()
+            .map(|(id, layer)| {
+                // Parallax is centered on the middle of the target in logical pixels.
+                let view = root * layer.effective_view(self.view, target_size / scale_factor * 0.5);
+                let wrap = layer
+                    .wrap_width
+                    .filter(|width| *width > 0.0 && view.matrix2.determinant() != 0.0)
+                    .map(|width| {
+                        let Rectf { min, max } =
+                            Rectf::new(Vec2::ZERO, target_size).transform(view.inverse());
+                        Wrap {
+                            width,
+                            visible_left: min.x,
+                            visible_right: max.x,
+                        }
+                    });
+                (*id, ResolvedLayer { view, wrap })
+            })
+            .collect()
+    }
+
+    fn resolve_reflections(
+        &self,
+        layers: &std::collections::HashMap<u32, ResolvedLayer>,
+        target_size: wgpu::Extent3d,
+    ) -> std::collections::HashMap<u32, ResolvedReflection<'a>> {
+        let target_size = Vec2::new(target_size.width as f32, target_size.height as f32);
+        self.layers
+            .iter()
+            .filter_map(|(id, layer)| {
+                let reflection = layer.reflection?;
+                let resolved = layers.get(id)?;
+                let view = resolved.view;
+                if view.matrix2.determinant() == 0.0 {
+                    return None;
+                }
+
+                // The fade is measured along the world y axis, so in target pixels its gradient is the second row of the inverse view.
+                let fade = if reflection.fade > 0.0 {
+                    gpu::Effect::Fade {
+                        origin: view.transform_point2(Vec2::new(0.0, reflection.axis)),
+                        direction: view.matrix2.inverse().row(1) / reflection.fade,
+                    }
+                } else {
+                    gpu::Effect::None
+                };
+
+                let band = reflection.distortion.and_then(|distortion| {
+                    let Rectf { min, max } =
+                        Rectf::new(Vec2::ZERO, target_size).transform(view.inverse());
+                    let bottom = if reflection.fade > 0.0 {
+                        reflection.axis + reflection.fade
+                    } else {
+                        max.y
+                    };
+                    if bottom <= reflection.axis || max.x <= min.x {
+                        return None;
+                    }
+                    Some(Item {
+                        layer: *id,
+                        z: 0,
+                        blend_mode: BlendMode::Alpha,
+                        material: None,
+                        palette: None,
+                        clip: None,
+                        variation: None,
+                        emissive: 0.0,
+                        tint: Vec4::ONE,
+                        hdr_tint: Vec4::ONE,
+                        priority: None,
+                        snap: false,
+                        draw: None,
+                        command: Command::Distortion(distortion::DistortionCommand {
+                            texture: distortion.texture,
+                            src_offset: distortion.src_offset,
+                            src_size: distortion.src_size,
+                            src_layer: distortion.src_layer,
+                            size: Vec2::new(max.x - min.x, bottom - reflection.axis),
+                            strength: distortion.strength,
+                            transform: view
+                                * Affine2::from_translation(Vec2::new(min.x, reflection.axis)),
+                            tint: palette::WHITE,
+                        }),
+                    })
+                });
+
+                Some((
+                    *id,
+                    ResolvedReflection {
+                        layer: ResolvedLayer {
+                            view: view * reflection.mirror(),
+                            wrap: resolved.wrap.clone(),
+                        },
+                        opacity: reflection.opacity.clamp(0.0, 1.0),
+                        fade,
+                        band,
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    /// Clips subsequent draws to a rectangle in logical pixels, see [`RendererOptions::scale_factor`], until the matching [`Canvas::pop_clip`].
+    ///
+    /// Clips nest: the effective clip is the intersection of all pushed rectangles. Clipping is done with a scissor rectangle, so it is not affected by layer views.
+    pub fn push_clip(&mut self, offset: glam::IVec2, size: glam::UVec2) {
+        self.clip_stack.push(self.clip);
+        let rect = Rect { offset, size };
+        self.clip = Some(match &self.clip {
+            Some(clip) => clip.intersect(&rect),
+            None => rect,
+        });
+    }
+
+    /// Removes the most recently pushed clip rectangle.
+    ///
+    /// Does nothing if there is no clip rectangle.
+    pub fn pop_clip(&mut self) {
+        if let Some(clip) = self.clip_stack.pop() {
+            self.clip = clip;
+        }
+    }
+
+    /// Draws subsequent draws with a custom material, until the matching [`Canvas::pop_material`], e.g. to gray out a whole menu.
+    ///
+    /// Materials do not nest: the most recently pushed one replaces the others, as does [`Drawable::with_material`] for the draw it wraps.
+    pub fn push_material(&mut self, material: material::Material) {
+        self.material_stack.push(self.material.replace(material));
+    }
+
+    /// Removes the most recently pushed material.
+    ///
+    /// Does nothing if there is no pushed material.
+    pub fn pop_material(&mut self) {
+        if let Some(material) = self.material_stack.pop() {
+            self.material = material;
+        }
+    }
+
+    /// Sets the layer subsequent draws are placed into.
+    ///
+    /// The default layer is 0.
+    pub fn set_layer(&mut self, layer: u32) {
+        self.layer = layer;
+    }
+
+    /// Sets the view transform applied to every layer, e.g. a camera shared by the whole scene.
+    ///
+    /// The view is applied at prepare time after each layer's own view, so it affects items drawn before this call too, and layer parallax scales the camera position implied by both views. Clip rectangles are not affected. The default view is the identity transform.
+    pub fn set_view(&mut self, view: glam::Affine2) {
+        self.view = view;
+    }
+
+    /// Gets the view transform applied to every layer.
+    pub fn view(&self) -> glam::Affine2 {
+        self.view
+    }
+
+    /// Sets whether every draw is snapped to whole target pixels, as if drawn with [`Drawable::snapped`].
+    ///
+    /// Like the view, this applies to items drawn before this call too.
+    pub fn set_pixel_snapping(&mut self, pixel_snapping: bool) {
+        self.pixel_snapping = pixel_snapping;
+    }
+
+    /// Sets the view transform for a layer.
+    ///
+    /// The view is applied to every item in the layer at prepare time, regardless of whether it was drawn before or after this call. Layers without a view use the identity transform.
+    pub fn set_layer_view(&mut self, layer: u32, view: glam::Affine2) {
+        self.layers.entry(layer).or_default().view = view;
+    }
+
+    /// Sets the parallax factor for a layer.
+    ///
+    /// The camera position implied by the layer's view combined with [`Canvas::set_view`] (the world point at the center of the target) is scaled by this factor on each axis before the view is applied, so a factor of 0.5 scrolls at half speed and a factor of 0 stays fixed. The default factor is 1.
+    pub fn set_layer_parallax(&mut self, layer: u32, factor: glam::Vec2) {
+        self.layers.entry(layer).or_default().parallax = factor;
+    }
+
+    /// Makes a layer wrap around horizontally with the given world width.
+    ///
+    /// Items in the layer are drawn at every multiple of `width` along the x axis that overlaps the visible area, so items near the seam appear on both sides of it. Items that do not overlap the visible area at any offset are not drawn. Pass [`None`] to disable wrapping.
+    pub fn set_layer_wrap(&mut self, layer: u32, width: Option<f32>) {
+        self.layers.entry(layer).or_default().wrap_width = width;
+    }
+
+    /// Sets a reflection for a layer.
+    ///
+    /// Everything in the layer except distortions is drawn again mirrored about the reflection's axis, just before the layer's first item in z-order. Reflected copies are drawn without their materials. Pass [`None`] to remove the reflection.
+    pub fn set_layer_reflection(
+        &mut self,
+        layer: u32,
+        reflection: Option<reflection::Reflection<'a>>,
+    ) {
+        self.layers.entry(layer).or_default().reflection = reflection;
+    }
+
+    /// Sets the priority of a layer's draws when the renderer's draw budget is exceeded.
+    ///
+    /// Draws in the layers with the lowest priority are dropped first, most recently drawn first. Individual draws can override their layer's priority with [`Drawable::prioritized`]. The default priority is 0.
+    pub fn set_layer_priority(&mut self, layer: u32, priority: i32) {
+        self.layers.entry(layer).or_default().priority = priority;
+    }
+
+    /// Sets whether a layer's draws are prepared, e.g. to toggle a debug overlay without redrawing the canvas.
+    ///
+    /// Hidden layers keep their draws, and are drawn again once shown. Layers are visible by default.
+    pub fn set_layer_visible(&mut self, layer: u32, visible: bool) {
+        self.layers.entry(layer).or_default().visible = visible;
+    }
+
+    /// Gets whether a layer's draws are prepared.
+    pub fn is_layer_visible(&self, layer: u32) -> bool {
+        self.layers.get(&layer).is_none_or(|layer| layer.visible)
+    }
+
+    /// Composes a transform with the current one for subsequent draws, until the matching [`Canvas::pop_transform`].
+    ///
+    /// Transforms nest like a scene graph: a draw's transform is applied first, then each pushed transform from the innermost outwards.
+    pub fn push_transform(&mut self, transform: glam::Affine2) {
+        self.transform_stack.push(self.transform);
+        self.transform *= transform;
+    }
+
+    /// Removes the most recently pushed transform.
+    ///
+    /// Does nothing if there is no pushed transform.
+    pub fn pop_transform(&mut self) {
+        if let Some(transform) = self.transform_stack.pop() {
+            self.transform = transform;
+        }
+    }
+
+    /// Starts a group of draws that is composited with a shared opacity, until the matching [`Canvas::pop_opacity`].
+    ///
+    /// The group is rendered into an intermediate target and drawn back as a whole, so overlapping draws within it do not show through each other. Groups nest, and each open group needs an intermediate target the size of the final one. Every pushed group must be popped before the canvas is prepared.
+    pub fn push_opacity(&mut self, opacity: f32) {
+        self.group_stack
+            .push((opacity, None, std::mem::take(&mut self.items)));
+    }
+
+    /// Ends the most recently pushed opacity group, drawing it into the canvas.
+    ///
+    /// The group is drawn with the layer, z-order, clip rectangle and blend state current at this call. Does nothing if there is no pushed group.
+    pub fn pop_opacity(&mut self) {
+        if let Some((opacity, mask, items)) = self.group_stack.pop() {
+            let group_items = std::mem::replace(&mut self.items, items);
+            self.push(Command::Group(Box::new(GroupCommand {
+                items: group_items,
+                opacity: opacity.clamp(0.0, 1.0),
+                mask,
+                transform: Affine2::IDENTITY,
+            })));
+        }
+    }
+
+    /// Starts a group of draws that is clipped by the alpha of a sprite, until the matching [`Canvas::pop_mask`].
+    ///
+    /// The mask slice is placed with `transform` under the current pushed transforms, as if it were drawn, and with the view of the layer current when the group is popped. Like [`Canvas::push_opacity`], the group is rendered into an intermediate target, and groups of either kind nest.
+    pub fn push_mask<T: Texture>(&mut self, mask: TextureSlice<'a, T>, transform: glam::Affine2) {
+        let mask = GroupMask {
+            texture: mask.texture,
+            rect: mask.rect,
+            layer: mask.layer,
+            transform: self.transform * transform,
+        };
+        self.group_stack
+            .push((1.0, Some(mask), std::mem::take(&mut self.items)));
+    }
+
+    /// Ends the most recently pushed group, drawing it into the canvas through its mask.
+    ///
+    /// Opacity groups and masks share a stack, so this is the same as [`Canvas::pop_opacity`].
+    pub fn pop_mask(&mut self) {
+        self.pop_opacity();
+    }
+
+    /// Gets the transform composed from all pushed transforms.
+    pub fn transform(&self) -> glam::Affine2 {
+        self.transform
+    }
+
+    /// Draws an item with the given transformation matrix.
+    ///
+    /// Returns an ID for changing the draw's transform or tint later, e.g. once layout has run over everything drawn. Draws made by a drawable from within its own [`Drawable::draw`] are part of the outer draw, and return its ID.
+    #[inline]
+    pub fn draw(&mut self, drawable: impl Drawable<'a>, transform: glam::Affine2) -> CommandId {
+        self.record_draw(drawable, transform)
+    }
+
+    /// Draws an item with the given transformation matrix and z-order.
+    ///
+    /// Items are drawn in ascending z-order, and in submission order within the same z. Items drawn with [`Canvas::draw`] have a z of 0.
+    pub fn draw_with_z(
+        &mut self,
+        drawable: impl Drawable<'a>,
+        transform: glam::Affine2,
+        z: i32,
+    ) -> CommandId {
+        let prev = std::mem::replace(&mut self.z, z);
+        let id = self.record_draw(drawable, transform);
+        self.z = prev;
+        id
+    }
+
+    /// Replaces the transform a draw was made with, as if it had been given to [`Canvas::draw`] instead.
+    ///
+    /// Transforms pushed when the draw was made still apply. Draws made with a transform that cannot be inverted, e.g. a zero scale, keep it.
+    pub fn set_transform(&mut self, id: CommandId, transform: glam::Affine2) {
+        let Some(changes) = self.draws.get_mut(id.0 as usize) else {
+            return;
+        };
+        if changes.drawn.matrix2.determinant() == 0.0 {
+            return;
+        }
+        changes.transform = changes.pushed * transform * changes.drawn.inverse();
+    }
+
+    /// Replaces the tint of a draw, which is multiplied with any tints the drawable already has, see [`Drawable::tinted`].
+    pub fn set_tint(&mut self, id: CommandId, tint: impl Tint) {
+        let Some(changes) = self.draws.get_mut(id.0 as usize) else {
+            return;
+        };
+        changes.tint = Vec4::from(<[f32; 4]>::from(tint.to_colorf())).clamp(Vec4::ZERO, Vec4::ONE);
+    }
+}
+
+/// Encapsulates renderer state.
+pub struct Renderer {
+    renderer: gpu::Renderer,
+    cache: Cache,
+    white_texture: wgpu::Texture,
+    /// Glyph atlases, if text is enabled.
+    #[cfg(feature = "text")]
+    text_sprite_maker: Option<text::SpriteMaker>,
+    non_finite: NonFiniteDraws,
+    draw_budget: Option<usize>,
+    culling: bool,
+    scale_factor: f32,
+    /// Intermediate targets of opacity groups, one per nesting depth.
+    group_targets: Vec<Option<RenderTarget>>,
+    /// Downsampled chunks of tilemaps drawn zoomed out, by map ID, chunk size and chunk.
+    tilemap_chunks: std::collections::HashMap<(u64, u32, UVec2), tilemap::Chunk>,
+    /// Number of scenes prepared, to tell how long ago tilemap chunks were last drawn.
+    prepare_count: u64,
+    post: gpu::PostProcessor,
+    /// Target the canvas is rendered to before post effects, created on first use.
+    post_source: Option<RenderTarget>,
+}
+
+/// A batch about to be drawn, as passed to the hook given to [`Renderer::render_with`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchInfo<'a> {
+    /// Texture the batch samples.
+    pub texture: &'a debug::TextureDescription,
+    /// Blend mode the batch is drawn with.
+    pub blend_mode: BlendMode,
+    /// Material the batch is drawn with, if any.
+    pub material: Option<material::Material>,
+    /// Range of indices drawn.
+    pub indices: std::ops::Range<u32>,
+}
+
+/// Options for the render pass recorded by [`Renderer::render_to_view`].
+#[derive(Debug, Clone)]
+pub struct PassOptions<'a> {
+    /// What happens to the view's existing contents. The default keeps them.
+    pub load: wgpu::LoadOp<wgpu::Color>,
+
+    /// Texture the multisampled view is resolved into, if the renderer uses MSAA.
+    pub resolve_target: Option<&'a wgpu::TextureView>,
+
+    /// Timestamp queries written at the beginning and end of the pass, e.g. for GPU profiling.
+    pub timestamp_writes: Option<wgpu::RenderPassTimestampWrites<'a>>,
+}
+
+impl<'a> Default for PassOptions<'a> {
+    fn default() -> Self {
+        Self {
+            load: wgpu::LoadOp::Load,
+            resolve_target: None,
+            timestamp_writes: None,
+        }
+    }
+}
+
+/// Options for creating a [`Renderer`].
+#[derive(Debug, Clone, Copy)]
+pub struct RendererOptions {
+    /// Whether text can be drawn.
+    ///
+    /// Glyph atlases are only allocated when glyphs are first drawn, and the color atlas only when a color glyph such as an emoji is. Disabling text makes drawing a label fail with [`Error::TextDisabled`] instead. Has no effect without the `text` feature.
+    pub text: bool,
+
+    /// Number of samples per pixel of the targets the renderer draws to, for multisample antialiasing. Must be supported by the device for the texture format, e.g. 1 or 4.
+    ///
+    /// With more than one sample, the pass given to [`Renderer::render`] must draw to a multisampled texture with the same sample count, and set the final target (e.g. the surface texture) as its `resolve_target`. Render targets handle this themselves.
+    pub sample_count: u32,
+
+    /// How textures are sampled.
+    pub sampler: SamplerOptions,
+
+    /// How glyphs are packed into the glyph atlases.
+    pub glyph_atlas: GlyphAtlasOptions,
+
+    /// Which images are packed into the shared image atlas.
+    pub image_atlas: ImageAtlasOptions,
+
+    /// What happens to draws with NaN or infinite transforms.
+    pub non_finite: NonFiniteDraws,
+
+    /// Maximum number of draws prepared per frame, e.g. to contain a runaway particle system. [`None`] draws everything.
+    ///
+    /// Draws over the budget are dropped by priority, see [`Canvas::set_layer_priority`] and [`Drawable::prioritized`], and counted in [`debug::FrameDescription::dropped_draws`].
+    pub draw_budget: Option<usize>,
+
+    /// Whether draws lying entirely outside the target, or outside their clip rectangle, are skipped when preparing.
+    ///
+    /// Draws are tested by the bounding box of their transformed bounds, after layer views are applied. Culled draws are counted in [`debug::FrameDescription::culled_draws`].
+    pub culling: bool,
+
+    /// Whether textures are sampled as premultiplied alpha, which avoids dark fringes where filtering blends opaque texels with transparent ones, e.g. around the edges of scaled sprites.
+    ///
+    /// [`Image`] and [`DynamicTexture`] pixels in 8-bit RGBA or BGRA formats, and color glyphs, are premultiplied when uploaded, and glyph masks are sampled as premultiplied coverage. Other textures, e.g. [`wgpu::Texture`]s drawn directly, must already be premultiplied. Tints are still straight alpha, and are premultiplied in the shader. [`material::Material`]s still receive straight colors.
+    pub premultiplied_alpha: bool,
+
+    /// Whether glyphs are rasterized at quarter-pixel horizontal offsets, so text drawn at fractional positions, e.g. while scrolling sideways, moves smoothly instead of jumping a pixel at a time.
+    ///
+    /// Each glyph may be cached at up to 4 offsets. Only text drawn without rotation or scaling is offset, and glyphs stay snapped to whole pixels vertically, as hinting aligns them to. Has no effect without the `text` feature.
+    pub subpixel_text: bool,
+
+    /// Number of target pixels per logical pixel, e.g. 2 on a HiDPI display.
+    ///
+    /// Everything drawn, including clip rectangles, is given in logical pixels and scaled to the target when preparing, and text is rasterized at its scaled size so it stays crisp. Must be positive.
+    pub scale_factor: f32,
+}
+
+/// A span of a tiled draw along one axis, drawn from a span of its slice.
+struct TileSpan {
+    /// Start of the span in the area.
+    position: u32,
+    /// Start of the span in the slice, in texels.
+    src: u32,
+    /// Length of the span in the slice, in texels.
+    src_len: u32,
+    /// How much the span is stretched to fill the area.
+    scale: f32,
+    /// Whether the span is flipped.
+    flip: bool,
+}
+
+/// Splits an axis of a tiled draw of a slice `len` texels long over an area `area` texels long into spans.
+fn tile_spans(len: u32, area: u32, flip: bool, mode: WrapMode) -> Vec<TileSpan> {
+    // Spans cropped to the area keep the part of the slice nearest the origin, which is at the far edge of the texture when flipped.
+    let cropped = |position: u32, flip: bool| {
+        let src_len = len.min(area - position);
+        TileSpan {
+            position,
+            src: if flip { len - src_len } else { 0 },
+            src_len,
+            scale: 1.0,
+            flip,
+        }
+    };
+    match mode {
+        WrapMode::Repeat | WrapMode::MirrorRepeat => (0..area)
+            .step_by(len as usize)
+            .enumerate()
+            .map(|(i, position)| {
+                cropped(
+                    position,
+                    flip != (mode == WrapMode::MirrorRepeat && i % 2 == 1),
+                )
+            })
+            .collect(),
+        WrapMode::ClampToEdge if area == 0 => vec![],
+        WrapMode::ClampToEdge => {
+            let mut spans = vec![cropped(0, flip)];
+            if area > len {
+                spans.push(TileSpan {
+                    position: len,
+                    src: if flip { 0 } else { len - 1 },
+                    src_len: 1,
+                    scale: (area - len) as f32,
+                    flip,
+                });
+            }
+            spans
+        }
+    }
+}
+
+/// What [`Renderer::prepare`] does with draws whose transform, bounds or tint are NaN or infinite.
+///
+/// Transforms are checked as drawn, including the canvas and layer views, and draws inside groups are checked too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NonFiniteDraws {
+    /// Draws them anyway, leaving the result to the GPU. This costs nothing, but a single bad transform can make a draw vanish or cover the whole target.
+    #[default]
+    Draw,
+
+    /// Skips them, counting them in [`debug::FrameDescription::skipped_draws`].
+    Skip,
+
+    /// Fails with [`Error::NonFiniteDraw`], naming the first offending draw.
+    Error,
+}
+
+impl Default for RendererOptions {
+    fn default() -> Self {
+        Self {
+            text: true,
+            sample_count: 1,
+            sampler: SamplerOptions::default(),
+            glyph_atlas: GlyphAtlasOptions::default(),
+            image_atlas: ImageAtlasOptions::default(),
+            non_finite: NonFiniteDraws::default(),
+            draw_budget: None,
+            culling: true,
+            premultiplied_alpha: false,
+            subpixel_text: false,
+            scale_factor: 1.0,
+        }
+    }
+}
+
+/// Which [`Image`]s are packed into the shared image atlas.
+///
+/// Images in the atlas are sampled with their texture coordinates offset into it, so meshes drawn with them must not rely on coordinates outside of the image wrapping or clamping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageAtlasOptions {
+    /// Largest width or height of an image packed into the atlas. Zero disables the atlas.
+    pub max_image_size: u32,
+
+    /// Width and height of the atlas when first created. The atlas doubles in size when full, up to the device's limit, after which images get their own textures.
+    pub initial_size: u32,
+
+    /// Format of the atlas. Only images in this format are packed into it.
+    ///
+    /// Use [`wgpu::TextureFormat::Rgba8Unorm`] to pack images whose pixels should be passed through to a non-sRGB target unconverted. Formats other than [`wgpu::TextureFormat::Rgba8Unorm`], [`wgpu::TextureFormat::Rgba8UnormSrgb`], [`wgpu::TextureFormat::Bgra8Unorm`] and [`wgpu::TextureFormat::Bgra8UnormSrgb`] disable the atlas.
+    pub format: wgpu::TextureFormat,
+}
+
+impl Default for ImageAtlasOptions {
+    fn default() -> Self {
+        Self {
+            max_image_size: 256,
+            initial_size: 1024,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        }
+    }
+}
+
+/// How glyphs are packed into the glyph atlases. Has no effect without the `text` feature.
+///
+/// Glyphs are packed into shelves, rows as tall as the first glyph placed in them. Rounding glyph sizes up to an alignment lets glyphs of similar heights, e.g. from several font sizes, share shelves instead of each opening a new one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlyphAtlasOptions {
+    /// Width and height of each atlas page when first created. Pages double in size when full, up to `max_size`.
+    pub initial_size: u32,
+
+    /// Width and height pages may grow to, which is further limited by the device's maximum texture size. Once every page of an atlas is full at this size, another page is added.
+    pub max_size: u32,
+
+    /// Granularity glyph sizes are rounded up to. Must be at least 1 in each direction.
+    pub alignment: glam::UVec2,
+
+    /// Number of columns the atlas is split into. More columns give more, shorter shelves, which suits many small glyphs.
+    pub columns: u32,
+
+    /// Ascending maximum glyph heights, in pixels, of each size class but the last. Every size class gets its own atlases, so a few huge glyphs do not grow the atlas shared with small text.
+    pub size_classes: &'static [u32],
+}
+
+impl Default for GlyphAtlasOptions {
+    fn default() -> Self {
+        Self {
+            initial_size: 1024,
+            max_size: 4096,
+            alignment: glam::UVec2::new(1, 4),
+            columns: 2,
+            size_classes: &[64],
+        }
+    }
+}
+
+/// How textures are filtered and addressed when sampled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SamplerOptions {
+    /// Filter used when textures are magnified or minified, and between the mip levels of images with [`Image::with_mipmaps`].
+    pub filter: wgpu::FilterMode,
+
+    /// How texture coordinates outside of a texture are handled.
+    pub address_mode: wgpu::AddressMode,
+}
+
+impl SamplerOptions {
+    /// Nearest-neighbor filtering, which keeps pixel art crisp.
+    pub const NEAREST: Self = Self {
+        filter: wgpu::FilterMode::Nearest,
+        address_mode: wgpu::AddressMode::ClampToEdge,
+    };
+
+    /// Linear filtering, which smooths scaled and rotated textures.
+    pub const LINEAR: Self = Self {
+        filter: wgpu::FilterMode::Linear,
+        address_mode: wgpu::AddressMode::ClampToEdge,
+    };
+
+    /// Sets how texture coordinates outside of a texture are handled.
+    pub fn with_address_mode(self, address_mode: wgpu::AddressMode) -> Self {
+        Self {
+            address_mode,
+            ..self
+        }
+    }
+}
+
+impl Default for SamplerOptions {
+    fn default() -> Self {
+        Self::NEAREST
+    }
+}
+
+/// Errors that can occur.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    /// Glyph atlas has run out of space, because it cannot grow past the device's maximum texture size.
+    ///
+    /// The frame is not prepared, but the renderer remains usable, e.g. to prepare the frame again with smaller text.
+    #[error("out of glylph atlas space")]
+    OutOfGlyphAtlasSpace,
+
+    /// Text was drawn with a renderer created without text support.
+    #[error("text is disabled for this renderer")]
+    TextDisabled,
+
+    /// A draw has a NaN or infinite transform, bounds or tint, and the renderer was created with [`NonFiniteDraws::Error`].
+    #[error("draw {index} has a non-finite transform, bounds or tint: {transform:?}")]
+    NonFiniteDraw {
+        /// Index of the draw on the canvas, or within its group for draws inside a group, in the order it was drawn.
+        index: usize,
+        /// The draw's transform, including the canvas transform stack and its layer's view.
+        transform: Affine2,
+    },
+
+    /// Creating a GPU object failed, e.g. the device ran out of memory growing an atlas, or a material failed validation.
+    ///
+    /// Only reported on native backends. On the web, errors still go to the device's uncaptured error handler.
+    #[error("gpu: {0}")]
+    Gpu(#[from] wgpu::Error),
+}
+
+impl Renderer {
+    /// Creates a new renderer.
+    ///
+    /// `texture_format` is the format of the targets it renders to. Float formats such as [`wgpu::TextureFormat::Rgba16Float`] keep tints brighter than white from [`Drawable::hdr_tinted`] unclamped, so the output can be composited over an HDR scene.
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture_format: wgpu::TextureFormat,
+    ) -> Self {
+        Self::with_options(device, queue, texture_format, RendererOptions::default())
+    }
+
+    /// Creates a new renderer with the given options.
+    pub fn with_options(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture_format: wgpu::TextureFormat,
+        options: RendererOptions,
+    ) -> Self {
+        Self::with_pipeline_cache(device, queue, texture_format, options, None)
+    }
+
+    /// Creates a new renderer that compiles its pipelines, including materials and post effects created later, with a pipeline cache.
+    ///
+    /// See [`pipeline_cache`] for loading a cache saved by a previous run. Passing [`None`] is the same as [`Renderer::with_options`].
+    pub fn with_pipeline_cache(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture_format: wgpu::TextureFormat,
+        options: RendererOptions,
+        pipeline_cache: Option<wgpu::PipelineCache>,
+    ) -> Self {
+        Self {
+            renderer: gpu::Renderer::new(
+                device,
+                texture_format,
+                options.sample_count,
+                options.sampler,
+                options.premultiplied_alpha,
+                pipeline_cache,
+            ),
+            cache: Cache::new(options.image_atlas, options.premultiplied_alpha),
+            white_texture: device.create_texture_with_data(
+                queue,
+                &wgpu::TextureDescriptor {
+                    label: Some("canvasette: white_texture"),
+                    size: wgpu::Extent3d {
+                        width: 1,
+                        height: 1,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                },
+                wgpu::util::TextureDataOrder::default(),
+                &[0xff; 4],
+            ),
+            #[cfg(feature = "text")]
+            text_sprite_maker: options.text.then(|| {
+                text::SpriteMaker::new(
+                    options.glyph_atlas,
+                    options.premultiplied_alpha,
+                    options.subpixel_text,
+                )
+            }),
+            non_finite: options.non_finite,
+            draw_budget: options.draw_budget,
+            culling: options.culling,
+            scale_factor: options.scale_factor,
+            group_targets: vec![],
+            tilemap_chunks: std::collections::HashMap::new(),
+            prepare_count: 0,
+            post: gpu::PostProcessor::new(device, texture_format),
+            post_source: None,
+        }
+    }
+
+    /// Changes the maximum number of draws prepared per frame. See [`RendererOptions::draw_budget`].
+    pub fn set_draw_budget(&mut self, draw_budget: Option<usize>) {
+        self.draw_budget = draw_budget;
+    }
+
+    /// Changes whether draws outside the target are culled. See [`RendererOptions::culling`].
+    pub fn set_culling(&mut self, culling: bool) {
+        self.culling = culling;
+    }
+
+    /// Changes the number of target pixels per logical pixel, e.g. when a window moves to a display with a different DPI. See [`RendererOptions::scale_factor`].
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.scale_factor = scale_factor;
+    }
+
+    /// Gets the number of target pixels per logical pixel.
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    /// Gets the cache the renderer compiles pipelines with, e.g. to save it with [`pipeline_cache::save`] once the pipelines have been created.
+    pub fn pipeline_cache(&self) -> Option<&wgpu::PipelineCache> {
+        self.renderer.pipeline_cache()
+    }
+
+    /// Changes how textures are sampled, taking effect from the next prepared frame.
+    pub fn set_sampler(&mut self, device: &wgpu::Device, sampler: SamplerOptions) {
+        self.renderer.set_sampler(device, sampler);
+    }
+
+    /// Rasterizes and uploads the glyphs of a label ahead of time, so drawing it later does not hitch.
+    ///
+    /// Glyphs are still evicted if they are not drawn for a while; use [`Renderer::pin_text`] to keep them.
+    #[cfg(feature = "text")]
+    pub fn prewarm_text(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        font_system: &mut cosmic_text::FontSystem,
+        label: &Label,
+    ) -> Result<(), Error> {
+        let text_sprite_maker = self.text_sprite_maker.as_mut().ok_or(Error::TextDisabled)?;
+        let raster = text_sprite_maker.raster(Affine2::from_scale(Vec2::splat(self.scale_factor)));
+        gpu::error_scope(device, || {
+            text_sprite_maker.make(device, queue, font_system, label, palette::WHITE, raster)
+        })?
+        .ok_or(Error::OutOfGlyphAtlasSpace)?;
+        Ok(())
+    }
+
+    /// Prewarms the glyphs of a label and pins them, so they are never evicted until [`Renderer::unpin_text`] is called with the same label.
+    ///
+    /// Pins are counted, so glyphs shared by several pinned labels stay until every label is unpinned.
+    #[cfg(feature = "text")]
+    pub fn pin_text(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        font_system: &mut cosmic_text::FontSystem,
+        label: &Label,
+    ) -> Result<(), Error> {
+        self.prewarm_text(device, queue, font_system, label)?;
+        let text_sprite_maker = self.text_sprite_maker.as_mut().unwrap();
+        let raster = text_sprite_maker.raster(Affine2::from_scale(Vec2::splat(self.scale_factor)));
+        text_sprite_maker.pin(label, raster);
+        Ok(())
+    }
+
+    /// Releases the latest pin taken on a label's glyphs by [`Renderer::pin_text`], with the same label or a clone of it.
+    #[cfg(feature = "text")]
+    pub fn unpin_text(&mut self, label: &Label) {
+        if let Some(text_sprite_maker) = &mut self.text_sprite_maker {
+            text_sprite_maker.unpin(label);
+        }
+    }
+
+    /// Gets whether a texture has been uploaded, so drawing it does not upload it first. See [`Cache::contains`].
+    pub fn is_resident(&self, texture: &(impl Texture + ?Sized)) -> bool {
+        self.cache.contains(texture)
+    }
+
+    /// Uploads a texture ahead of its first draw, e.g. from a loading screen. Does nothing if it is already uploaded.
+    pub fn upload_texture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture: &(impl Texture + ?Sized),
+    ) -> Result<(), Error> {
+        gpu::error_scope(device, || {
+            texture.upload_to_wgpu(device, queue, &mut self.cache)
+        })?;
+        Ok(())
+    }
+
+    /// Uploads the textures and glyphs a scene draws, without preparing it.
+    ///
+    /// This lets uploads be scheduled early in the frame, leaving less work for a later [`Renderer::prepare`] or [`Renderer::batch`] of the same scene.
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        #[cfg(feature = "text")] font_system: &mut cosmic_text::FontSystem,
+        canvas: &Canvas,
+    ) -> Result<(), Error> {
+        self.cache.remove_dropped(queue);
+
+        gpu::error_scope(device, || {
+            let mut items = canvas.items.iter().collect::<Vec<_>>();
+            while let Some(item) = items.pop() {
+                if let Some(texture) = item.command.texture() {
+                    texture.upload_to_wgpu(device, queue, &mut self.cache);
+                }
+                if let Some(palette) = item.palette {
+                    palette
+                        .texture
+                        .upload_to_wgpu(device, queue, &mut self.cache);
+                }
+                match &item.command {
+                    #[cfg(feature = "text")]
+                    Command::Text(section) => {
+                        self.prewarm_text(device, queue, font_system, &section.label)?
+                    }
+                    Command::Group(group) => items.extend(group.items.iter()),
+                    _ => {}
+                }
+            }
+            for reflection in canvas.layers.values().filter_map(|layer| layer.reflection) {
+                if let Some(distortion) = reflection.distortion {
+                    distortion
+                        .texture
+                        .upload_to_wgpu(device, queue, &mut self.cache);
+                }
+            }
+            Ok(())
+        })?
+    }
+
+    /// Creates buffers to prepare a scene into with [`Renderer::batch`].
+    pub fn create_prepared(&self, device: &wgpu::Device) -> Prepared {
+        Prepared {
+            frame: self.renderer.create_frame(device),
+        }
+    }
+
+    /// Prepares a scene into its own buffers, to be drawn later with [`Renderer::render_prepared`].
+    ///
+    /// Unlike [`Renderer::prepare`], several scenes can be kept prepared at once. Prepared scenes share the renderer's atlases, so glyphs that are not drawn for a while may be evicted from under a scene that is kept for a long time without being prepared again.
+    pub fn batch(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        #[cfg(feature = "text")] font_system: &mut cosmic_text::FontSystem,
+        target_size: wgpu::Extent3d,
+        canvas: &Canvas,
+        prepared: &mut Prepared,
+    ) -> Result<(), Error> {
+        std::mem::swap(&mut self.renderer.frame, &mut prepared.frame);
+        let result = self.prepare(
+            device,
+            queue,
+            #[cfg(feature = "text")]
+            font_system,
+            target_size,
+            canvas,
+        );
+        std::mem::swap(&mut self.renderer.frame, &mut prepared.frame);
+        result
+    }
+
+    /// Prepares a scene for rendering.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        #[cfg(feature = "text")] font_system: &mut cosmic_text::FontSystem,
+        target_size: wgpu::Extent3d,
+        canvas: &Canvas,
+    ) -> Result<(), Error> {
+        self.prepare_pass(
+            device,
+            queue,
+            #[cfg(feature = "text")]
+            font_system,
+            target_size,
+            canvas,
+            false,
+        )
+    }
+
+    /// Prepares a scene, either as normal or as its emissive buffer.
+    fn prepare_pass(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        #[cfg(feature = "text")] font_system: &mut cosmic_text::FontSystem,
+        target_size: wgpu::Extent3d,
+        canvas: &Canvas,
+        emissive: bool,
+    ) -> Result<(), Error> {
+        const MAX_CHUNK_AGE: u64 = 100;
+
+        self.cache.remove_dropped(queue);
+        self.cache.uploads = 0;
+        self.prepare_count += 1;
+        let prepare_count = self.prepare_count;
+        self.tilemap_chunks
+            .retain(|_, chunk| prepare_count - chunk.last_used < MAX_CHUNK_AGE);
+        #[cfg(feature = "text")]
+        if let Some(text_sprite_maker) = &mut self.text_sprite_maker {
+            text_sprite_maker.rasterized = 0;
+        }
+
+        gpu::error_scope(device, || {
+            self.prepare_items(
+                device,
+                queue,
+                #[cfg(feature = "text")]
+                font_system,
+                target_size,
+                canvas,
+                &canvas.items,
+                emissive,
+                0,
+            )
+        })??;
+
+        self.renderer.frame.stats.texture_uploads = self.cache.uploads;
+        #[cfg(feature = "text")]
+        if let Some(text_sprite_maker) = &mut self.text_sprite_maker {
+            self.renderer.frame.stats.glyphs_rasterized = text_sprite_maker.rasterized;
+            text_sprite_maker.flush(queue);
+        }
+
+        Ok(())
+    }
+
+    /// Prepares items of a canvas into the current frame.
+    ///
+    /// Opacity groups among the items are rendered first, into group targets numbered from `first_group`. Returns the number of the first group target left unused.
+    #[allow(clippy::too_many_arguments)]
+    fn prepare_items(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        #[cfg(feature = "text")] font_system: &mut cosmic_text::FontSystem,
+        target_size: wgpu::Extent3d,
+        canvas: &Canvas,
+        items: &[Item],
+        emissive: bool,
+        first_group: usize,
+    ) -> Result<usize, Error> {
+        let mut next_group = first_group;
+        let mut group_targets = std::collections::HashMap::new();
+        for item in items
+            .iter()
+            .filter(|item| canvas.is_layer_visible(item.layer))
+        {
+            if let Command::Group(group) = &item.command {
+                group_targets.insert(std::ptr::from_ref(item), next_group);
+                next_group = self.render_group(
+                    device,
+                    queue,
+                    #[cfg(feature = "text")]
+                    font_system,
+                    target_size,
+                    canvas,
+                    &group.items,
+                    emissive,
+                    next_group,
+                )?;
+            }
+        }
+
+        let layers = canvas.resolve_layers(target_size, self.scale_factor);
+        let reflections = canvas.resolve_reflections(&layers, target_size);
+        let default_layer = ResolvedLayer {
+            view: Affine2::from_scale(Vec2::splat(self.scale_factor)) * canvas.view,
+            wrap: None,
+        };
+        let target_rect = Rect::new(0, 0, target_size.width, target_size.height);
+
+        // Like groups, chunks of tilemaps drawn zoomed out are rendered first, at the finest level any copy of the map needs.
+        let mut chunks = std::collections::HashMap::new();
+        for item in items
+            .iter()
+            .filter(|item| canvas.is_layer_visible(item.layer))
+        {
+            let Command::Tilemap(tilemap) = &item.command else {
+                continue;
+            };
+            let Some(lod) = tilemap.lod.filter(|_| draws_tilemap_chunks(item)) else {
+                continue;
+            };
+            let layer = layers.get(&item.layer).unwrap_or(&default_layer);
+            let viewport = match item.clip.map(|clip| clip.scale(self.scale_factor)) {
+                Some(clip) => clip.intersect(&target_rect),
+                None => target_rect,
+            };
+            let (min, max) = (
+                viewport.offset.as_vec2(),
+                (viewport.offset + viewport.size.as_ivec2()).as_vec2(),
+            );
+            for transform in layer.transforms(canvas.item_transform(item), tilemap.bounds()) {
+                if !transform.is_finite() || transform.matrix2.determinant() == 0.0 {
+                    continue;
+                }
+                let level = tilemap.lod_level(transform);
+                if level == 0 {
+                    continue;
+                }
+                for chunk in tilemap.visible_chunks(transform, min, max, lod.chunk_size) {
+                    let needed = chunks
+                        .entry((tilemap.id, lod.chunk_size, chunk))
+                        .or_insert((tilemap, item.blend_mode, level));
+                    needed.2 = needed.2.max(level);
+                }
+            }
+        }
+        if !chunks.is_empty() {
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("canvasette: render_tilemap_chunks"),
+            });
+            let mut result = Ok(());
+            for ((_, _, chunk), (tilemap, blend_mode, level)) in chunks {
+                result = self.render_tilemap_chunk(
+                    device,
+                    queue,
+                    &mut encoder,
+                    #[cfg(feature = "text")]
+                    font_system,
+                    tilemap,
+                    blend_mode,
+                    chunk,
+                    level,
+                    next_group,
+                );
+                if result.is_err() {
+                    break;
+                }
+            }
+            // Levels recorded before an error are already marked valid.
+            queue.submit(Some(encoder.finish()));
+            result?;
+        }
+
+        // Consecutive items usually share a texture, which only needs offering to the cache once.
+        let mut last_uploaded = None;
+        for item in items
+            .iter()
+            .chain(reflections.values().filter_map(|r| r.band.as_ref()))
+        {
+            let textures = item
+                .command
+                .texture()
+                .into_iter()
+                .chain(item.palette.map(|palette| palette.texture));
+            for texture in textures {
+                let key = std::ptr::from_ref(texture);
+                if last_uploaded.is_some_and(|last| std::ptr::eq(last, key)) {
+                    continue;
+                }
+                last_uploaded = Some(key);
+                texture.upload_to_wgpu(device, queue, &mut self.cache);
+            }
+        }
+
+        let mut stage = Stage::default();
+
+        let mut stats = debug::FrameStats {
+            draws: items.len() as u32,
+            ..Default::default()
+        };
+        for group in group_targets.values() {
+            stats += self.group_targets[*group].as_ref().unwrap().frame.stats;
+        }
+
+        let drawn = items;
+        let mut items = items
+            .iter()
+            .filter(|item| canvas.is_layer_visible(item.layer))
+            .collect::<Vec<_>>();
+        let mut dropped_draws = 0;
+        if let Some(budget) = self.draw_budget.filter(|budget| items.len() > *budget) {
+            let priority = |item: &Item| {
+                item.priority.unwrap_or_else(|| {
+                    canvas
+                        .layers
+                        .get(&item.layer)
+                        .map(|layer| layer.priority)
+                        .unwrap_or(0)
+                })
+            };
+            let mut order = (0..items.len()).collect::<Vec<_>>();
+            order.sort_by_key(|i| (priority(items[*i]), std::cmp::Reverse(*i)));
+            let mut dropped = vec![false; items.len()];
+            for i in &order[..items.len() - budget] {
+                dropped[*i] = true;
+            }
+            dropped_draws = items.len() - budget;
+            items = items
+                .into_iter()
+                .zip(dropped)
+                .filter_map(|(item, dropped)| (!dropped).then_some(item))
+                .collect();
+        }
+        items.sort_by_key(|item| item.z);
+
+        // Reflected copies of a layer, and any distortion over them, are drawn just before the layer's first item.
+        let mut passes = Vec::with_capacity(items.len());
+        let mut reflected = std::collections::HashSet::new();
+        for item in items.iter().copied() {
+            if let Some(reflection) = reflections.get(&item.layer) {
+                if reflected.insert(item.layer) {
+                    passes.extend(
+                        items
+                            .iter()
+                            .filter(|other| {
+                                other.layer == item.layer
+                                    && !matches!(
+                                        other.command,
+                                        Command::Distortion(_) | Command::Group(_)
+                                    )
+                            })
+                            .map(|other| (*other, &reflection.layer, Some(reflection))),
+                    );
+                    if let Some(band) = &reflection.band {
+                        passes.push((band, &ResolvedLayer::IDENTITY, None));
+                    }
+                }
+            }
+            passes.push((
+                item,
+                layers.get(&item.layer).unwrap_or(&default_layer),
+                None,
+            ));
+        }
+
+        let mut skipped_draws = 0;
+        let mut culled_draws = 0;
+        let mut textures = ResolvedTextures::new(&self.cache);
+        for (item, layer, reflection) in passes {
+            if emissive && matches!(item.command, Command::Distortion(_)) {
+                continue;
+            }
+            let bounds = item.command.bounds();
+            let transform = canvas.item_transform(item);
+            let (_, draw_tint) = canvas.draw_changes(item);
+            // Checked as drawn, after the layer view and tints are applied, so the modes agree on what is non-finite.
+            if self.non_finite != NonFiniteDraws::Draw
+                && !((layer.view * transform).is_finite()
+                    && item.command.is_finite()
+                    && (item.tint * draw_tint).is_finite()
+                    && item.hdr_tint.is_finite())
+            {
+                if self.non_finite == NonFiniteDraws::Error {
+                    // A reflection's distortion band is not on the canvas, so it is blamed on the first draw of its layer.
+                    let index = drawn
+                        .iter()
+                        .position(|other| std::ptr::eq(other, item))
+                        .or_else(|| drawn.iter().position(|other| other.layer == item.layer))
+                        .unwrap_or_default();
+                    return Err(Error::NonFiniteDraw {
+                        index,
+                        transform: layer.view * transform,
+                    });
+                }
+                skipped_draws += 1;
+                continue;
+            }
+            if item.clip.is_some_and(|clip| clip.is_empty()) {
+                continue;
+            }
+            stage.blend_mode = item.blend_mode;
+            let palette = item
+                .palette
+                .filter(|_| !matches!(item.command, Command::Distortion(_) | Command::Group(_)));
+            let resolved_palette = palette.map(|palette| textures.get(palette.texture));
+            stage.secondary = resolved_palette.map(|(texture, _)| texture);
+            stage.effect = match (&item.command, reflection, palette) {
+                (_, _, Some(palette)) => {
+                    let (_, origin) = resolved_palette.unwrap();
+                    gpu::Effect::Palette {
+                        origin: UVec2::new(origin.x, origin.y) + palette.offset.as_uvec2(),
+                        layer: origin.z + palette.layer,
+                        width: palette.width,
+                    }
+                }
+                (_, Some(reflection), None) => reflection.fade,
+                (Command::Distortion(distortion), None, None) => gpu::Effect::Distort {
+                    strength: distortion.strength,
+                },
+                (_, None, None) => match item.material {
+                    Some(material) => gpu::Effect::Material(material.0),
+                    None => gpu::Effect::None,
+                },
+            };
+            let clip = item.clip.map(|clip| clip.scale(self.scale_factor));
+            stage.clip = clip;
+            // Degenerate draws would only produce zero-area triangles, which some drivers rasterize incorrectly.
+            if item.command.is_degenerate() || transform.matrix2.determinant() == 0.0 {
+                skipped_draws += 1;
+                continue;
+            }
+            // Groups cover the whole target, and their bounds are from before layer views are applied.
+            if self.culling && !matches!(item.command, Command::Group(_)) {
+                let viewport = match clip {
+                    Some(clip) => clip.intersect(&target_rect),
+                    None => target_rect,
+                };
+                let viewport = viewport.as_rectf();
+                if layer.transforms(transform, bounds).all(|transform| {
+                    Rectf::new(bounds.0, bounds.1)
+                        .transform(transform)
+                        .intersect(&viewport)
+                        .is_empty()
+                }) {
+                    culled_draws += 1;
+                    continue;
+                }
+            }
+            let tint = scale_tint(item.command.tint(), item.tint * draw_tint);
+            let tint = match &item.variation {
+                Some(v) => v.apply_tint(tint),
+                None => tint,
+            };
+            let tint = match reflection {
+                Some(reflection) => {
+                    palette::with_alpha(tint, (tint.a as f32 * reflection.opacity).round() as u8)
+                }
+                None => tint,
+            };
+            // In the emissive buffer, emissive items are brightened and everything else is drawn black so it still hides what is behind it.
+            let tint = if !emissive {
+                tint
+            } else if item.emissive > 0.0 {
+                let scale = |v: u8| (v as f32 * item.emissive).round().min(255.0) as u8;
+                Color::new(scale(tint.r), scale(tint.g), scale(tint.b), tint.a)
+            } else {
+                if let gpu::Effect::Material(_) = stage.effect {
+                    stage.effect = gpu::Effect::None;
+                }
+                palette::with_alpha(palette::BLACK, tint.a)
+            };
+            let first_vertex = stage.mesh.vertices.len();
+            let snap = |transform| {
+                if item.snap || canvas.pixel_snapping {
+                    snap_to_pixels(transform)
+                } else {
+                    transform
+                }
+            };
+            match &item.command {
+                Command::Sprite(sprite) => {
+                    let (texture, origin) = textures.get(sprite.texture);
+                    let texture = StagedTexture::Texture(texture);
+                    for transform in layer.transforms(transform, bounds).map(snap) {
+                        let start = stage.mesh.index_count();
+                        stage.mesh.push_quad_with_tints(
+                            sprite.src_offset + IVec2::new(origin.x as i32, origin.y as i32),
+                            sprite.src_size,
+                            sprite.src_layer + origin.z,
+                            sprite.flip,
+                            transform,
+                            sprite
+                                .corner_tints
+                                .map(|corner_tint| multiply_tint(tint, corner_tint)),
+                        );
+                        stage.end(texture, start);
+                    }
+                }
+                Command::Tiled(sprite, size, mode) => {
+                    let (texture, origin) = textures.get(sprite.texture);
+                    let texture = StagedTexture::Texture(texture);
+                    let src_offset =
+                        sprite.src_offset + IVec2::new(origin.x as i32, origin.y as i32);
+                    let columns = tile_spans(sprite.src_size.x, size.x, sprite.flip.x, *mode);
+                    let rows = tile_spans(sprite.src_size.y, size.y, sprite.flip.y, *mode);
+                    for transform in layer.transforms(transform, bounds).map(snap) {
+                        let start = stage.mesh.index_count();
+                        for row in rows.iter() {
+                            for column in columns.iter() {
+                                stage.mesh.push_quad(
+                                    src_offset + IVec2::new(column.src as i32, row.src as i32),
+                                    UVec2::new(column.src_len, row.src_len),
+                                    sprite.src_layer + origin.z,
+                                    BVec2::new(column.flip, row.flip),
+                                    transform
+                                        * Affine2::from_scale_angle_translation(
+                                            Vec2::new(column.scale, row.scale),
+                                            0.0,
+                                            Vec2::new(column.position as f32, row.position as f32),
+                                        ),
+                                    tint,
+                                );
+                            }
+                        }
+                        stage.end(texture, start);
+                    }
+                }
+                Command::Tilemap(tilemap) => {
+                    let (texture, origin) = textures.get(tilemap.texture);
+                    let texture = StagedTexture::Texture(texture);
+                    let viewport = match clip {
+                        Some(clip) => clip.intersect(&target_rect),
+                        None => target_rect,
+                    };
+                    let (min, max) = (
+                        viewport.offset.as_vec2(),
+                        (viewport.offset + viewport.size.as_ivec2()).as_vec2(),
+                    );
+                    let lod = tilemap.lod.filter(|_| draws_tilemap_chunks(item));
+                    for transform in layer.transforms(transform, bounds).map(snap) {
+                        let level = lod.map_or(0, |_| tilemap.lod_level(transform));
+                        let Some(lod) = lod.filter(|_| level > 0) else {
+                            let start = stage.mesh.index_count();
+                            push_tiles(
+                                &mut stage.mesh,
+                                tilemap,
+                                tilemap.visible_tiles(transform, min, max),
+                                origin,
+                                transform,
+                                tint,
+                            );
+                            stage.end(texture, start);
+                            continue;
+                        };
+                        let chunk_size = tilemap.tile_size * lod.chunk_size;
+                        for chunk in tilemap.visible_chunks(transform, min, max, lod.chunk_size) {
+                            let start = stage.mesh.index_count();
+                            let cached = self
+                                .tilemap_chunks
+                                .get(&(tilemap.id, lod.chunk_size, chunk))
+                                .filter(|cached| {
+                                    cached.last_used == self.prepare_count
+                                        && cached.valid >= level as usize
+                                });
+                            match cached {
+                                Some(cached) if cached.empty => {}
+                                Some(cached) => {
+                                    // Chunk targets hold premultiplied colors, like group targets.
+                                    let target = &cached.levels[level as usize - 1].texture;
+                                    let size = UVec2::new(target.width(), target.height());
+                                    stage.blend_mode = BlendMode::Premultiplied;
+                                    stage.mesh.push_quad(
+                                        IVec2::ZERO,
+                                        size,
+                                        0,
+                                        BVec2::FALSE,
+                                        transform
+                                            * Affine2::from_scale_angle_translation(
+                                                chunk_size.as_vec2() / size.as_vec2(),
+                                                0.0,
+                                                (chunk * chunk_size).as_vec2(),
+                                            ),
+                                        tint,
+                                    );
+                                    stage.end(StagedTexture::Texture(target), start);
+                                    stage.blend_mode = item.blend_mode;
+                                }
+                                // Chunks that were not rendered first, e.g. as they are only seen in a reflection, are drawn as tiles.
+                                None => {
+                                    let (first, last) = tilemap.chunk_cells(chunk, lod.chunk_size);
+                                    push_tiles(
+                                        &mut stage.mesh,
+                                        tilemap,
+                                        tilemap.tiles_in(first, last),
+                                        origin,
+                                        transform,
+                                        tint,
+                                    );
+                                    stage.end(texture, start);
+                                }
+                            }
+                        }
+                    }
+                }
+                Command::AtlasView(view) => {
+                    let page = match view.kind {
+                        #[cfg(feature = "text")]
+                        debug::AtlasKind::GlyphMask => {
+                            self.text_sprite_maker.as_ref().and_then(|m| {
+                                Some((
+                                    StagedTexture::GlyphMask(view.size_class, view.page),
+                                    m.mask_texture(view.size_class, view.page)?.size(),
+                                ))
+                            })
+                        }
+                        #[cfg(feature = "text")]
+                        debug::AtlasKind::GlyphColor => {
+                            self.text_sprite_maker.as_ref().and_then(|m| {
+                                Some((
+                                    StagedTexture::GlyphColor(view.size_class, view.page),
+                                    m.color_texture(view.size_class, view.page)?.size(),
+                                ))
+                            })
+                        }
+                        #[cfg(not(feature = "text"))]
+                        debug::AtlasKind::GlyphMask | debug::AtlasKind::GlyphColor => None,
+                        debug::AtlasKind::Image => self
+                            .cache
+                            .atlas
+                            .as_ref()
+                            .filter(|_| (view.size_class, view.page) == (0, 0))
+                            .map(|atlas| {
+                                (
+                                    StagedTexture::Texture(atlas.texture()),
+                                    atlas.texture().size(),
+                                )
+                            }),
+                    };
+                    let Some((texture, size)) = page else {
+                        continue;
+                    };
+                    let size = UVec2::new(size.width, size.height);
+                    for transform in layer.transforms(transform, bounds) {
+                        let start = stage.mesh.index_count();
+                        stage.mesh.push_quad(
+                            IVec2::ZERO,
+                            size,
+                            0,
+                            BVec2::FALSE,
+                            transform * Affine2::from_scale(view.size / size.as_vec2()),
+                            tint,
+                        );
+                        stage.end(texture, start);
+                    }
+                }
+                Command::Group(group) => {
+                    let target = self.group_targets[group_targets[&std::ptr::from_ref(item)]]
+                        .as_ref()
+                        .unwrap();
+                    // Group targets hold premultiplied colors, and are drawn in target pixels since their items already had their layer views applied.
+                    stage.blend_mode = BlendMode::Premultiplied;
+                    let tint =
+                        palette::with_alpha(palette::WHITE, (group.opacity * 255.0).round() as u8);
+                    if let Some(mask) = group.mask {
+                        // The mask sprite is drawn instead, sampling the group target where it covers.
+                        stage.effect = gpu::Effect::Mask;
+                        stage.secondary = Some(&target.texture);
+                        let (texture, origin) = textures.get(mask.texture);
+                        let mask_bounds = (Vec2::ZERO, mask.rect.size.as_vec2());
+                        for transform in layer
+                            .transforms(transform * mask.transform, mask_bounds)
+                            .map(snap)
+                        {
+                            let start = stage.mesh.index_count();
+                            stage.mesh.push_quad(
+                                mask.rect.offset + IVec2::new(origin.x as i32, origin.y as i32),
+                                mask.rect.size,
+                                mask.layer + origin.z,
+                                BVec2::FALSE,
+                                transform,
+                                tint,
+                            );
+                            stage.end(StagedTexture::Texture(texture), start);
+                        }
+                    } else {
+                        stage.effect = gpu::Effect::None;
+                        let start = stage.mesh.index_count();
+                        stage.mesh.push_quad(
+                            IVec2::ZERO,
+                            UVec2::new(target_size.width, target_size.height),
+                            0,
+                            BVec2::FALSE,
+                            Affine2::IDENTITY,
+                            tint,
+                        );
+                        stage.end(StagedTexture::Texture(&target.texture), start);
+                    }
                 }
+                Command::Distortion(distortion) => {
+                    let (texture, origin) = textures.get(distortion.texture);
+                    let texture = StagedTexture::Texture(texture);
+                    let scale = distortion.size / distortion.src_size.as_vec2().max(Vec2::ONE);
+                    for transform in layer.transforms(transform, bounds) {
+                        let start = stage.mesh.index_count();
+                        stage.mesh.push_quad(
+                            distortion.src_offset + IVec2::new(origin.x as i32, origin.y as i32),
+                            distortion.src_size,
+                            distortion.src_layer + origin.z,
+                            BVec2::FALSE,
+                            transform * Affine2::from_scale(scale),
+                            tint,
+                        );
+                        stage.end(texture, start);
+                    }
+                }
+                #[cfg(feature = "text")]
                 Command::Text(section) => {
-                    staged.extend(
-                        self.text_sprite_maker
-                            .make(device, queue, font_system, &section.label, section.tint)
-                            .ok_or(Error::OutOfGlyphAtlasSpace)?
-                            .into_iter()
-                            .map(|s| {
-                                Staged::TextSprite(text::TextSprite {
-                                    transform: section.transform * s.transform,
-                                    ..s
-                                })
+                    let text_sprite_maker =
+                        self.text_sprite_maker.as_mut().ok_or(Error::TextDisabled)?;
+                    // Glyphs are only made again for transforms rasterized differently.
+                    let mut text_sprites: Option<(text::Raster, Vec<text::TextSprite>)> = None;
+                    for transform in layer.transforms(transform, bounds) {
+                        let raster = text_sprite_maker.raster(transform);
+                        if text_sprites
+                            .as_ref()
+                            .is_none_or(|(made_with, _)| *made_with != raster)
+                        {
+                            text_sprites = Some((
+                                raster,
+                                text_sprite_maker
+                                    .make(device, queue, font_system, &section.label, tint, raster)
+                                    .ok_or(Error::OutOfGlyphAtlasSpace)?,
+                            ));
+                        }
+                        for text_sprite in text_sprites.iter().flat_map(|(_, sprites)| sprites) {
+                            let start = stage.mesh.index_count();
+                            stage.mesh.push_quad(
+                                text_sprite.offset,
+                                text_sprite.size,
+                                0,
+                                BVec2::FALSE,
+                                transform * text_sprite.transform,
+                                text_sprite.tint,
+                            );
+                            stage.end(
+                                if text_sprite.is_mask {
+                                    StagedTexture::GlyphMask(
+                                        text_sprite.size_class,
+                                        text_sprite.page,
+                                    )
+                                } else {
+                                    StagedTexture::GlyphColor(
+                                        text_sprite.size_class,
+                                        text_sprite.page,
+                                    )
+                                },
+                                start,
+                            );
+                        }
+                    }
+                }
+                Command::Shape(shape) => {
+                    let (texture, white) = self.cache.white_texel();
+                    for transform in layer.transforms(transform, bounds) {
+                        let outline = shape
+                            .shape
+                            .outline(transform.matrix2.determinant().abs().sqrt());
+                        let start = stage.mesh.index_count();
+                        stage.mesh.push(
+                            outline.iter().map(|p| {
+                                gpu::Vertex::new(transform.transform_point2(*p), white, 0, tint)
                             }),
-                    );
+                            (1..outline.len().saturating_sub(1) as u32).flat_map(|i| [0, i, i + 1]),
+                        );
+                        stage.end(texture, start);
+                    }
+                }
+                Command::Mesh(mesh) => {
+                    let (white_texture, white) = self.cache.white_texel();
+                    let (texture, origin) = match mesh.texture {
+                        Some(texture) => {
+                            let (texture, origin) = textures.get(texture);
+                            (StagedTexture::Texture(texture), origin)
+                        }
+                        None => (white_texture, wgpu::Origin3d::ZERO),
+                    };
+                    for transform in layer.transforms(transform, bounds) {
+                        let start = stage.mesh.index_count();
+                        stage.mesh.push(
+                            mesh.vertices.iter().map(|v| {
+                                gpu::Vertex::new(
+                                    transform.transform_point2(v.position),
+                                    if mesh.texture.is_some() {
+                                        v.tex_coords + Vec2::new(origin.x as f32, origin.y as f32)
+                                    } else {
+                                        white
+                                    },
+                                    mesh.src_layer + origin.z,
+                                    multiply_tint(tint, v.tint),
+                                )
+                            }),
+                            mesh.indices.iter().copied(),
+                        );
+                        stage.end(texture, start);
+                    }
+                }
+                Command::Rope(rope) => {
+                    let (texture, origin) = textures.get(rope.texture);
+                    let texture = StagedTexture::Texture(texture);
+                    for transform in layer.transforms(transform, bounds) {
+                        let (vertices, indices) =
+                            rope.tessellate(transform.matrix2.determinant().abs().sqrt());
+                        let start = stage.mesh.index_count();
+                        stage.mesh.push(
+                            vertices.iter().map(|v| {
+                                gpu::Vertex::new(
+                                    transform.transform_point2(v.position),
+                                    v.tex_coords + Vec2::new(origin.x as f32, origin.y as f32),
+                                    rope.src_layer + origin.z,
+                                    tint,
+                                )
+                            }),
+                            indices,
+                        );
+                        stage.end(texture, start);
+                    }
+                }
+            }
+            if item.hdr_tint != Vec4::ONE {
+                for vertex in &mut stage.mesh.vertices[first_vertex..] {
+                    let tint = Vec4::from_array(vertex.tint) * item.hdr_tint;
+                    vertex.tint = tint.with_w(tint.w.clamp(0.0, 1.0)).to_array();
+                }
+            }
+        }
+
+        // Glyphs are only staged when text is enabled.
+        #[cfg(feature = "text")]
+        let text_sprite_maker = self.text_sprite_maker.as_ref();
+        let mut batches: Vec<gpu::Batch> = vec![];
+        for Run {
+            texture,
+            blend_mode,
+            effect,
+            secondary,
+            clip,
+            indices,
+        } in stage.runs
+        {
+            let scissor = match clip {
+                Some(clip) => {
+                    let clip = clip.intersect(&target_rect);
+                    if clip.is_empty() {
+                        continue;
+                    }
+                    Some([
+                        clip.left() as u32,
+                        clip.top() as u32,
+                        clip.size.x,
+                        clip.size.y,
+                    ])
+                }
+                None => None,
+            };
+            let texture = match texture {
+                StagedTexture::Texture(texture) => texture,
+                StagedTexture::White => &self.white_texture,
+                #[cfg(feature = "text")]
+                StagedTexture::GlyphMask(size_class, page) => text_sprite_maker
+                    .and_then(|m| m.mask_texture(size_class, page))
+                    .unwrap(),
+                #[cfg(feature = "text")]
+                StagedTexture::GlyphColor(size_class, page) => text_sprite_maker
+                    .and_then(|m| m.color_texture(size_class, page))
+                    .unwrap(),
+            };
+            match batches.last_mut() {
+                Some(last)
+                    if std::ptr::eq(last.texture, texture)
+                        && last.blend_mode == blend_mode
+                        && last.effect == effect
+                        && last.secondary.map(std::ptr::from_ref)
+                            == secondary.map(std::ptr::from_ref)
+                        && last.scissor == scissor
+                        && last.indices.end == indices.start =>
+                {
+                    last.indices.end = indices.end;
+                }
+                _ => batches.push(gpu::Batch {
+                    texture,
+                    blend_mode,
+                    effect,
+                    secondary,
+                    scissor,
+                    indices,
+                }),
+            }
+        }
+
+        stats.batches += batches.len() as u32;
+        for pair in batches.windows(2) {
+            let (before, after) = (&pair[0], &pair[1]);
+            let breaks = &mut stats.batch_breaks;
+            if !std::ptr::eq(before.texture, after.texture) {
+                stats.texture_switches += 1;
+                breaks.texture += 1;
+            }
+            breaks.blend_mode += (before.blend_mode != after.blend_mode) as u32;
+            breaks.scissor += (before.scissor != after.scissor) as u32;
+            let material = |batch: &gpu::Batch| match batch.effect {
+                gpu::Effect::Material(material) => Some(material),
+                _ => None,
+            };
+            if material(before) != material(after) {
+                breaks.material += 1;
+            } else if before.effect != after.effect
+                || before.secondary.map(std::ptr::from_ref)
+                    != after.secondary.map(std::ptr::from_ref)
+            {
+                breaks.effect += 1;
+            }
+        }
+        stats.skipped_draws += skipped_draws;
+        stats.dropped_draws += dropped_draws as u32;
+        stats.culled_draws += culled_draws;
+
+        self.renderer
+            .prepare(device, queue, target_size, &stage.mesh, &batches);
+        self.renderer.frame.stats = stats;
+
+        Ok(next_group)
+    }
+
+    /// Brings the levels of a tilemap chunk up to `level`, rendering them again if its cells have changed.
+    ///
+    /// Level 1 is rendered from the tiles at half size, and every level after it from the one before. The renders are recorded into `encoder`, for the caller to submit.
+    #[allow(clippy::too_many_arguments)]
+    fn render_tilemap_chunk(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        #[cfg(feature = "text")] font_system: &mut cosmic_text::FontSystem,
+        tilemap: &tilemap::TilemapCommand,
+        blend_mode: BlendMode,
+        chunk: UVec2,
+        level: u32,
+        first_group: usize,
+    ) -> Result<(), Error> {
+        let lod = tilemap.lod.unwrap();
+        let (first, last) = tilemap.chunk_cells(chunk, lod.chunk_size);
+        let tiles = (first.y..last.y)
+            .flat_map(|y| {
+                let row = (y * tilemap.width) as usize;
+                &tilemap.tiles[row + first.x as usize..row + last.x as usize]
+            })
+            .copied()
+            .collect::<Vec<_>>();
+
+        let key = (tilemap.id, lod.chunk_size, chunk);
+        let mut cached = self
+            .tilemap_chunks
+            .remove(&key)
+            .unwrap_or_else(|| tilemap::Chunk {
+                tiles: vec![],
+                empty: true,
+                levels: vec![],
+                valid: 0,
+                last_used: 0,
+            });
+        cached.last_used = self.prepare_count;
+        if cached.tiles != tiles {
+            cached.empty = tiles.iter().all(Option::is_none);
+            cached.tiles = tiles;
+            cached.valid = 0;
+        }
+        if cached.empty {
+            cached.valid = cached.valid.max(level as usize);
+        }
+
+        // Chunks are rendered in texels of the tileset, whatever the scale factor.
+        let scale_factor = std::mem::replace(&mut self.scale_factor, 1.0);
+        let chunk_size = (tilemap.tile_size * lod.chunk_size).as_vec2();
+        let mut result = Ok(());
+        for i in cached.valid..level as usize {
+            let size = (chunk_size / 2f32.powi(i as i32 + 1))
+                .ceil()
+                .max(Vec2::ONE)
+                .as_uvec2();
+            if cached.levels.len() <= i {
+                cached.levels.push(self.create_render_target_named(
+                    device,
+                    "canvasette: tilemap_chunk",
+                    size.x,
+                    size.y,
+                ));
+            }
+            let (previous, rest) = cached.levels.split_at_mut(i);
+            let target = &mut rest[0];
+            target.resize(device, size.x, size.y);
+            let (blend_mode, command) = match previous.last() {
+                None => (
+                    blend_mode,
+                    Command::Tilemap(tilemap::TilemapCommand {
+                        lod: None,
+                        transform: Affine2::from_scale(size.as_vec2() / chunk_size)
+                            * Affine2::from_translation(-(chunk.as_vec2() * chunk_size)),
+                        tint: palette::WHITE,
+                        ..*tilemap
+                    }),
+                ),
+                Some(previous) => {
+                    let src_size = UVec2::new(previous.texture.width(), previous.texture.height());
+                    (
+                        BlendMode::Premultiplied,
+                        Command::Sprite(Sprite {
+                            texture: previous,
+                            src_offset: IVec2::ZERO,
+                            src_size,
+                            src_layer: 0,
+                            flip: BVec2::FALSE,
+                            transform: Affine2::from_scale(size.as_vec2() / src_size.as_vec2()),
+                            tint: palette::WHITE,
+                            corner_tints: [palette::WHITE; 4],
+                        }),
+                    )
                 }
+            };
+            let item = Item {
+                layer: 0,
+                z: 0,
+                blend_mode,
+                material: None,
+                palette: None,
+                clip: None,
+                variation: None,
+                emissive: 0.0,
+                tint: Vec4::ONE,
+                hdr_tint: Vec4::ONE,
+                priority: None,
+                snap: false,
+                draw: None,
+                command,
+            };
+
+            std::mem::swap(&mut self.renderer.frame, &mut target.frame);
+            result = self
+                .prepare_items(
+                    device,
+                    queue,
+                    #[cfg(feature = "text")]
+                    font_system,
+                    target.texture.size(),
+                    &Canvas::new(),
+                    std::slice::from_ref(&item),
+                    false,
+                    first_group,
+                )
+                .map(|_| ());
+            if result.is_ok() {
+                self.renderer.render_to_texture(
+                    encoder,
+                    &target.label,
+                    &target.texture,
+                    target.multisampled.as_ref(),
+                    wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    None,
+                );
             }
+            std::mem::swap(&mut self.renderer.frame, &mut target.frame);
+            if result.is_err() {
+                break;
+            }
+            cached.valid = i + 1;
+        }
+        self.scale_factor = scale_factor;
+        self.tilemap_chunks.insert(key, cached);
+        result
+    }
+
+    /// Drops the cached chunks of tilemaps drawn zoomed out, so they are rendered again, e.g. after their tileset's pixels change.
+    ///
+    /// See [`tilemap::LodOptions`].
+    pub fn clear_tilemap_chunks(&mut self) {
+        self.tilemap_chunks.clear();
+    }
+
+    /// Renders the items of an opacity group into a group target, returning the number of the first group target left unused.
+    #[allow(clippy::too_many_arguments)]
+    fn render_group(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        #[cfg(feature = "text")] font_system: &mut cosmic_text::FontSystem,
+        target_size: wgpu::Extent3d,
+        canvas: &Canvas,
+        items: &[Item],
+        emissive: bool,
+        index: usize,
+    ) -> Result<usize, Error> {
+        if self.group_targets.len() <= index {
+            self.group_targets.resize_with(index + 1, || None);
         }
+        let mut target = match self.group_targets[index].take() {
+            Some(mut target) => {
+                target.resize(device, target_size.width, target_size.height);
+                target
+            }
+            None => self.create_render_target_named(
+                device,
+                "canvasette: group_target",
+                target_size.width,
+                target_size.height,
+            ),
+        };
 
-        self.renderer.prepare(
+        // Nested groups use later group targets, so this one can be held outside the renderer meanwhile.
+        std::mem::swap(&mut self.renderer.frame, &mut target.frame);
+        let result = self.prepare_items(
             device,
             queue,
+            #[cfg(feature = "text")]
+            font_system,
             target_size,
-            &spright::batch::batch(
-                &staged
-                    .into_iter()
-                    .map(|staged| match staged {
-                        Staged::Sprite(sprite) => sprite,
-                        Staged::TextSprite(text_sprite) => spright::batch::Sprite {
-                            texture: if text_sprite.is_mask {
-                                self.text_sprite_maker.mask_texture()
-                            } else {
-                                self.text_sprite_maker.color_texture()
-                            },
-                            src_offset: text_sprite.offset,
-                            src_size: text_sprite.size,
-                            src_layer: 0,
-                            tint: text_sprite.tint,
-                            transform: text_sprite.transform,
-                        },
-                    })
-                    .collect::<Vec<_>>(),
-            ),
+            canvas,
+            items,
+            emissive,
+            index + 1,
         );
+        if result.is_ok() {
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("canvasette: render_group"),
+            });
+            self.renderer.render_to_texture(
+                &mut encoder,
+                &target.label,
+                &target.texture,
+                target.multisampled.as_ref(),
+                wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                None,
+            );
+            queue.submit(Some(encoder.finish()));
+        }
+        std::mem::swap(&mut self.renderer.frame, &mut target.frame);
+        self.group_targets[index] = Some(target);
+        result
+    }
+
+    /// Describes the scene prepared by the last call to [`Renderer::prepare`], e.g. to log with a bug report.
+    pub fn describe_frame(&self) -> debug::FrameDescription {
+        self.renderer.describe(&self.renderer.frame)
+    }
+
+    /// Describes a scene prepared with [`Renderer::batch`].
+    pub fn describe_prepared(&self, prepared: &Prepared) -> debug::FrameDescription {
+        self.renderer.describe(&prepared.frame)
+    }
+
+    /// Gets counts of the work done by the last call to [`Renderer::prepare`], e.g. to show in a profiling overlay.
+    pub fn frame_stats(&self) -> debug::FrameStats {
+        self.renderer.frame.stats
+    }
+
+    /// Gets counts of the work done preparing a scene with [`Renderer::batch`].
+    pub fn prepared_stats(&self, prepared: &Prepared) -> debug::FrameStats {
+        prepared.frame.stats
+    }
 
+    /// Describes how full every page of the glyph and image atlases is, e.g. to diagnose running out of atlas space.
+    ///
+    /// Atlases that have not been created yet, because nothing has been drawn with them, are left out.
+    pub fn describe_atlases(&self) -> Vec<debug::AtlasPageDescription> {
+        let mut pages = vec![];
         #[cfg(feature = "text")]
-        self.text_sprite_maker.flush(queue);
+        if let Some(text_sprite_maker) = &self.text_sprite_maker {
+            pages.extend(text_sprite_maker.describe_atlases());
+        }
+        if let Some(atlas) = &self.cache.atlas {
+            pages.push(atlas.describe(debug::AtlasKind::Image, 0, 0));
+        }
+        pages
+    }
 
-        Ok(())
+    /// Describes the scene last rendered into a render target.
+    pub fn describe_target_frame(&self, target: &RenderTarget) -> debug::FrameDescription {
+        self.renderer.describe(&target.frame)
+    }
+
+    /// Renders a scene prepared with [`Renderer::batch`], with the same limitations as [`Renderer::render`].
+    pub fn render_prepared(&self, prepared: &Prepared, rpass: &mut wgpu::RenderPass<'_>) {
+        self.renderer.render_frame(&prepared.frame, rpass, None);
     }
 
     /// Renders a prepared scene.
-    pub fn render<'rpass>(&'rpass self, rpass: &'rpass mut wgpu::RenderPass<'rpass>) {
+    ///
+    /// [`distortion::Distortion`] regions and materials that read the backdrop are skipped, as they need to copy the target mid-frame; use [`Renderer::render_to_target`] to draw them. Materials with extra bind groups are skipped too, as nothing binds them; use [`Renderer::render_with`] to draw them.
+    pub fn render(&self, rpass: &mut wgpu::RenderPass<'_>) {
         self.renderer.render(rpass);
     }
+
+    /// Renders a prepared scene like [`Renderer::render`], calling `hook` just before each batch is drawn.
+    ///
+    /// The batch's pipeline and built-in bind groups are already set, so the hook can set the extra bind groups of its material, see [`material::MaterialDescriptor::bind_group_layouts`]. Changing the pipeline, the built-in bind groups or the vertex and index buffers breaks the batches after it.
+    ///
+    /// Draws inside opacity and mask groups are rendered while preparing, without the hook, so those with materials that declare extra bind groups are skipped.
+    pub fn render_with(
+        &self,
+        rpass: &mut wgpu::RenderPass<'_>,
+        mut hook: impl FnMut(&BatchInfo, &mut wgpu::RenderPass<'_>),
+    ) {
+        self.renderer
+            .render_frame(&self.renderer.frame, rpass, Some(&mut hook));
+    }
+
+    /// Records a render pass drawing a prepared scene into a texture view, for callers that own the command encoder rather than a render pass.
+    ///
+    /// Like [`Renderer::render`], this skips [`distortion::Distortion`] regions and materials that read the backdrop. With multisample antialiasing, `view` must be a multisampled texture and [`PassOptions::resolve_target`] the final target.
+    pub fn render_to_view(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        options: PassOptions,
+    ) {
+        self.renderer.render_to_view(encoder, view, options);
+    }
+
+    /// Compiles a custom material.
+    ///
+    /// Returns [`Error::Gpu`] if the source fails to compile or validate.
+    pub fn create_material(
+        &mut self,
+        device: &wgpu::Device,
+        desc: material::MaterialDescriptor,
+    ) -> Result<material::Material, Error> {
+        Ok(material::Material(self.renderer.create_material(
+            device,
+            desc.label,
+            desc.source,
+            desc.backdrop,
+            desc.bind_group_layouts,
+        )?))
+    }
+
+    /// Compiles a post effect.
+    ///
+    /// Returns [`Error::Gpu`] if the source fails to compile or validate.
+    pub fn create_post_effect(
+        &mut self,
+        device: &wgpu::Device,
+        desc: post::PostEffectDescriptor,
+    ) -> Result<post::PostEffect, Error> {
+        Ok(post::PostEffect(self.post.create_effect(
+            device,
+            self.renderer.pipeline_cache(),
+            desc.label,
+            desc.source,
+            desc.bind_group_layouts,
+        )?))
+    }
+
+    /// Records passes running a chain of post effects over `source`, each reading the previous one's output, with the last writing to `target`.
+    ///
+    /// The target's contents are replaced, and an empty chain copies the source unchanged. The target must have the renderer's texture format, and the source must be a 2D texture with [`wgpu::TextureUsages::TEXTURE_BINDING`], e.g. [`RenderTarget::texture`]. Parameters are written with `queue`, so each call should be submitted before the next.
+    pub fn apply_post_effects(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::Texture,
+        target: &wgpu::Texture,
+        passes: &[post::PostPass],
+    ) {
+        self.post.apply(
+            device,
+            self.renderer.pipeline_cache(),
+            queue,
+            encoder,
+            source,
+            target,
+            passes,
+        );
+    }
+
+    /// Prepares a scene and records passes rendering it into an internal target, then through a chain of post effects into `target`.
+    ///
+    /// The canvas is rendered as by [`Renderer::render_to_target`] into a target the size of `target`, which the renderer keeps between frames, and the post effects are applied as by [`Renderer::apply_post_effects`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_with_post_effects(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        #[cfg(feature = "text")] font_system: &mut cosmic_text::FontSystem,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::Texture,
+        canvas: &Canvas,
+        passes: &[post::PostPass],
+    ) -> Result<(), Error> {
+        let mut source = match self.post_source.take() {
+            Some(mut source) => {
+                source.resize(device, target.width(), target.height());
+                source
+            }
+            None => self.create_render_target_named(
+                device,
+                "canvasette: post_source",
+                target.width(),
+                target.height(),
+            ),
+        };
+        let result = self.render_to_target(
+            device,
+            queue,
+            #[cfg(feature = "text")]
+            font_system,
+            encoder,
+            &mut source,
+            canvas,
+        );
+        if result.is_ok() {
+            self.post.apply(
+                device,
+                self.renderer.pipeline_cache(),
+                queue,
+                encoder,
+                &source.texture,
+                target,
+                passes,
+            );
+        }
+        self.post_source = Some(source);
+        result
+    }
+
+    /// Creates an offscreen render target in the renderer's texture format.
+    pub fn create_render_target(
+        &self,
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+    ) -> RenderTarget {
+        self.create_render_target_named(device, "canvasette: render_target", width, height)
+    }
+
+    /// Creates an offscreen render target with a name, which labels its texture and the passes rendering to it in GPU captures.
+    pub fn create_render_target_named(
+        &self,
+        device: &wgpu::Device,
+        name: impl Into<String>,
+        width: u32,
+        height: u32,
+    ) -> RenderTarget {
+        let label = name.into();
+        let sample_count = self.renderer.sample_count();
+        RenderTarget {
+            texture: create_render_target_texture(
+                device,
+                &label,
+                self.renderer.texture_format(),
+                1,
+                width,
+                height,
+            ),
+            multisampled: (sample_count > 1).then(|| {
+                create_render_target_texture(
+                    device,
+                    &label,
+                    self.renderer.texture_format(),
+                    sample_count,
+                    width,
+                    height,
+                )
+            }),
+            frame: self.renderer.create_frame(device),
+            label,
+        }
+    }
+
+    /// Prepares a scene and records passes rendering it into a render target, which is cleared to transparent first.
+    ///
+    /// Unlike [`Renderer::render`], this also draws [`distortion::Distortion`] regions and materials that read the backdrop. The target keeps its own buffers, so this does not disturb a scene prepared with [`Renderer::prepare`], and may be recorded into the same encoder as the pass that later draws the target.
+    ///
+    /// Like [`Renderer::render`], materials with extra bind groups are skipped; use [`Renderer::render_to_target_with`] to draw them.
+    pub fn render_to_target(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        #[cfg(feature = "text")] font_system: &mut cosmic_text::FontSystem,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &mut RenderTarget,
+        canvas: &Canvas,
+    ) -> Result<(), Error> {
+        self.render_pass_to_target(
+            device,
+            queue,
+            #[cfg(feature = "text")]
+            font_system,
+            encoder,
+            target,
+            canvas,
+            false,
+            None,
+        )
+    }
+
+    /// Renders a scene into a render target like [`Renderer::render_to_target`], calling `hook` just before each batch is drawn, as [`Renderer::render_with`] does.
+    ///
+    /// This draws materials with extra bind groups, including those that read the backdrop, whose extra bind groups start at group 3.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_to_target_with(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        #[cfg(feature = "text")] font_system: &mut cosmic_text::FontSystem,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &mut RenderTarget,
+        canvas: &Canvas,
+        mut hook: impl FnMut(&BatchInfo, &mut wgpu::RenderPass<'_>),
+    ) -> Result<(), Error> {
+        self.render_pass_to_target(
+            device,
+            queue,
+            #[cfg(feature = "text")]
+            font_system,
+            encoder,
+            target,
+            canvas,
+            false,
+            Some(&mut hook),
+        )
+    }
+
+    /// Records passes rendering a scene's emissive buffer into a render target, e.g. as the input to a bloom pass.
+    ///
+    /// Items drawn with [`Drawable::emissive`] have their tint brightened by their intensity, saturating at white. All other items are drawn black, so they occlude emissive items behind them. Distortions are skipped. As with [`Renderer::render_to_target`], the target is cleared to transparent first and keeps its own buffers, and materials with extra bind groups are skipped.
+    pub fn render_emissive_to_target(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        #[cfg(feature = "text")] font_system: &mut cosmic_text::FontSystem,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &mut RenderTarget,
+        canvas: &Canvas,
+    ) -> Result<(), Error> {
+        self.render_pass_to_target(
+            device,
+            queue,
+            #[cfg(feature = "text")]
+            font_system,
+            encoder,
+            target,
+            canvas,
+            true,
+            None,
+        )
+    }
+
+    /// Renders a scene's emissive buffer into a render target like [`Renderer::render_emissive_to_target`], calling `hook` just before each batch is drawn, as [`Renderer::render_with`] does.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_emissive_to_target_with(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        #[cfg(feature = "text")] font_system: &mut cosmic_text::FontSystem,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &mut RenderTarget,
+        canvas: &Canvas,
+        mut hook: impl FnMut(&BatchInfo, &mut wgpu::RenderPass<'_>),
+    ) -> Result<(), Error> {
+        self.render_pass_to_target(
+            device,
+            queue,
+            #[cfg(feature = "text")]
+            font_system,
+            encoder,
+            target,
+            canvas,
+            true,
+            Some(&mut hook),
+        )
+    }
+
+    /// Prepares a scene, either as normal or as its emissive buffer, and records passes rendering it into a render target.
+    #[allow(clippy::too_many_arguments)]
+    fn render_pass_to_target(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        #[cfg(feature = "text")] font_system: &mut cosmic_text::FontSystem,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &mut RenderTarget,
+        canvas: &Canvas,
+        emissive: bool,
+        hook: Option<gpu::Hook<'_>>,
+    ) -> Result<(), Error> {
+        std::mem::swap(&mut self.renderer.frame, &mut target.frame);
+        let result = self.prepare_pass(
+            device,
+            queue,
+            #[cfg(feature = "text")]
+            font_system,
+            target.texture.size(),
+            canvas,
+            emissive,
+        );
+        if result.is_ok() {
+            self.renderer.render_to_texture(
+                encoder,
+                &target.label,
+                &target.texture,
+                target.multisampled.as_ref(),
+                wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                hook,
+            );
+        }
+        std::mem::swap(&mut self.renderer.frame, &mut target.frame);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn image(width: u32, height: u32) -> Image {
+        Image::new(
+            vec![],
+            wgpu::TextureDescriptor {
+                label: None,
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+        )
+    }
+
+    #[test]
+    fn replace_takes_new_size() {
+        let mut image = image(4, 4);
+        let size = wgpu::Extent3d {
+            width: 8,
+            height: 2,
+            depth_or_array_layers: 1,
+        };
+        let id = image.id;
+        image.replace(vec![0; 8 * 2 * 4], size);
+        assert_eq!(image.size(), size);
+        assert_eq!(image.desc.mip_level_count, 1);
+        assert_ne!(image.id, id);
+    }
+
+    #[test]
+    #[should_panic]
+    fn replace_rejects_short_pixels() {
+        let mut image = image(4, 4);
+        image.replace(
+            vec![0; 8 * 2 * 4 - 1],
+            wgpu::Extent3d {
+                width: 8,
+                height: 2,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    /// Prepares a canvas onto a 64×64 target.
+    fn prepare_with(
+        renderer: &mut Renderer,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        canvas: &Canvas,
+    ) -> Result<(), Error> {
+        renderer.prepare(
+            device,
+            queue,
+            #[cfg(feature = "text")]
+            &mut font::new_font_system(false),
+            wgpu::Extent3d {
+                width: 64,
+                height: 64,
+                depth_or_array_layers: 1,
+            },
+            canvas,
+        )
+    }
+
+    /// Prepares a canvas onto a 64×64 target with a new renderer, or returns [`None`] if there is no adapter to test on.
+    fn prepare(
+        canvas: &Canvas,
+        non_finite: NonFiniteDraws,
+    ) -> Option<Result<debug::FrameStats, Error>> {
+        let (device, queue) = atlas::tests::device()?;
+        let mut renderer = Renderer::with_options(
+            device,
+            queue,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            RendererOptions {
+                non_finite,
+                ..Default::default()
+            },
+        );
+        let result = prepare_with(&mut renderer, device, queue, canvas);
+        Some(result.map(|()| renderer.frame_stats()))
+    }
+
+    /// Checks that the single non-finite draw on a canvas is skipped or rejected, depending on the mode.
+    fn assert_non_finite(canvas: &Canvas) {
+        let Some(skipped) = prepare(canvas, NonFiniteDraws::Skip) else {
+            return;
+        };
+        assert_eq!(skipped.unwrap().skipped_draws, 1);
+        assert!(matches!(
+            prepare(canvas, NonFiniteDraws::Error),
+            Some(Err(Error::NonFiniteDraw { index: 0, .. }))
+        ));
+    }
+
+    fn opaque_image() -> Image {
+        let mut image = image(4, 4);
+        image.replace(vec![0xff; 4 * 4 * 4], image.size());
+        image
+    }
+
+    #[test]
+    fn wrap_copies_overlap_visible_area() {
+        let layer = ResolvedLayer {
+            view: Affine2::IDENTITY,
+            wrap: Some(Wrap {
+                width: 100.0,
+                visible_left: 0.0,
+                visible_right: 64.0,
+            }),
+        };
+        let offsets = |transform, size| {
+            layer
+                .transforms(transform, (Vec2::ZERO, size))
+                .map(|transform| transform.translation.x)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            offsets(Affine2::from_translation(Vec2::new(510.0, 0.0)), Vec2::ONE),
+            [10.0]
+        );
+        assert_eq!(
+            offsets(
+                Affine2::from_translation(Vec2::new(-40.0, 0.0)),
+                Vec2::splat(50.0)
+            ),
+            [-40.0, 60.0]
+        );
+        assert_eq!(offsets(Affine2::IDENTITY, Vec2::splat(100.0)).len(), 2);
+    }
+
+    #[test]
+    fn oversized_wrapped_item() {
+        let mut canvas = Canvas::new();
+        canvas.set_layer_wrap(0, Some(16.0));
+        canvas.draw(
+            shape::Shape::circle(1.0).filled(palette::WHITE),
+            Affine2::from_scale(Vec2::splat(1e30)),
+        );
+        let layers = canvas.resolve_layers(
+            wgpu::Extent3d {
+                width: 64,
+                height: 64,
+                depth_or_array_layers: 1,
+            },
+            1.0,
+        );
+        let Item { command, .. } = &canvas.items[0];
+        assert!(
+            layers[&0]
+                .transforms(command.transform(), command.bounds())
+                .count()
+                <= 5
+        );
+        if let Some(result) = prepare(&canvas, NonFiniteDraws::Error) {
+            result.unwrap();
+        }
+    }
+
+    #[test]
+    fn non_finite_view() {
+        let image = opaque_image();
+        let mut canvas = Canvas::new();
+        canvas.set_view(Affine2::from_scale(Vec2::splat(f32::NAN)));
+        canvas.draw(
+            TextureSlice::from_layer(&image, 0).unwrap(),
+            Affine2::IDENTITY,
+        );
+        assert_non_finite(&canvas);
+    }
+
+    #[test]
+    fn non_finite_group_content() {
+        let image = opaque_image();
+        let mut canvas = Canvas::new();
+        canvas.push_opacity(0.5);
+        canvas.draw(
+            TextureSlice::from_layer(&image, 0).unwrap(),
+            Affine2::from_translation(Vec2::new(f32::INFINITY, 0.0)),
+        );
+        // Keeps the group itself from being skipped as empty.
+        canvas.draw(
+            TextureSlice::from_layer(&image, 0).unwrap(),
+            Affine2::IDENTITY,
+        );
+        canvas.pop_opacity();
+        assert_non_finite(&canvas);
+    }
+
+    #[test]
+    fn non_finite_in_empty_clip() {
+        let image = opaque_image();
+        let mut canvas = Canvas::new();
+        canvas.push_clip(IVec2::ZERO, UVec2::ZERO);
+        canvas.draw(
+            TextureSlice::from_layer(&image, 0).unwrap(),
+            Affine2::from_translation(Vec2::new(f32::NAN, 0.0)),
+        );
+        canvas.pop_clip();
+        assert_non_finite(&canvas);
+    }
+
+    #[test]
+    fn non_finite_hdr_tint() {
+        let image = opaque_image();
+        let mut canvas = Canvas::new();
+        canvas.draw(
+            TextureSlice::from_layer(&image, 0)
+                .unwrap()
+                .hdr_tinted(Colorf::new(f32::NAN, 1.0, 1.0, 1.0)),
+            Affine2::IDENTITY,
+        );
+        assert_non_finite(&canvas);
+    }
+
+    #[test]
+    fn material_bind_groups_are_bound_by_hook_or_skipped() {
+        let Some((device, queue)) = atlas::tests::device() else {
+            return;
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: wgpu::BufferUsages::UNIFORM,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        let mut renderer = Renderer::new(device, queue, wgpu::TextureFormat::Rgba8UnormSrgb);
+        let material = renderer
+            .create_material(
+                device,
+                material::MaterialDescriptor {
+                    source: "
+                        @group(2) @binding(0) var<uniform> light: vec4<f32>;
+                        fn material(in: VertexOutput, color: vec4<f32>) -> vec4<f32> {
+                            return color * light;
+                        }
+                    ",
+                    bind_group_layouts: &[&layout],
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let image = opaque_image();
+        let slice = TextureSlice::from_layer(&image, 0).unwrap();
+        let mut canvas = Canvas::new();
+        canvas.draw(slice.with_material(material), Affine2::IDENTITY);
+        // Groups are rendered while preparing, where there is no hook.
+        canvas.push_opacity(0.5);
+        canvas.draw(slice.with_material(material), Affine2::IDENTITY);
+        canvas.pop_opacity();
+
+        #[cfg(feature = "text")]
+        let mut font_system = font::new_font_system(false);
+        let mut target = renderer.create_render_target(device, 16, 16);
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let mut encoder = device.create_command_encoder(&Default::default());
+        renderer
+            .render_to_target(
+                device,
+                queue,
+                #[cfg(feature = "text")]
+                &mut font_system,
+                &mut encoder,
+                &mut target,
+                &canvas,
+            )
+            .unwrap();
+        queue.submit(Some(encoder.finish()));
+        let mut bound = 0;
+        let mut encoder = device.create_command_encoder(&Default::default());
+        renderer
+            .render_to_target_with(
+                device,
+                queue,
+                #[cfg(feature = "text")]
+                &mut font_system,
+                &mut encoder,
+                &mut target,
+                &canvas,
+                |batch, rpass| {
+                    if batch.material == Some(material) {
+                        rpass.set_bind_group(2, &bind_group, &[]);
+                        bound += 1;
+                    }
+                },
+            )
+            .unwrap();
+        queue.submit(Some(encoder.finish()));
+        assert!(pollster::block_on(device.pop_error_scope()).is_none());
+        assert_eq!(bound, 1);
+    }
+
+    #[test]
+    fn zoomed_out_tilemap_is_drawn_as_chunks() {
+        let Some((device, queue)) = atlas::tests::device() else {
+            return;
+        };
+        let image = opaque_image();
+        let mut map = tilemap::Tilemap::new(
+            TextureSlice::from_layer(&image, 0).unwrap(),
+            UVec2::splat(2),
+            64,
+            64,
+        )
+        .unwrap();
+        map.tiles_mut().fill(Some(0));
+        map.set_lod(Some(tilemap::LodOptions {
+            chunk_size: 16,
+            max_level: 4,
+        }));
+        let mut renderer = Renderer::new(device, queue, wgpu::TextureFormat::Rgba8UnormSrgb);
+        let prepare = |renderer: &mut Renderer, map: &tilemap::Tilemap<Image>| {
+            let mut canvas = Canvas::new();
+            canvas.draw(map, Affine2::from_scale(Vec2::splat(0.25)));
+            prepare_with(renderer, device, queue, &canvas).unwrap();
+            renderer.describe_frame().vertices
+        };
+
+        // The 128×128 texel map is drawn at a quarter size, so as 16 quads of level 2 chunks rather than 4096 tiles.
+        assert_eq!(prepare(&mut renderer, &map), 16 * 4);
+        assert_eq!(renderer.tilemap_chunks.len(), 16);
+        assert!(renderer
+            .tilemap_chunks
+            .values()
+            .all(|chunk| chunk.levels.len() == 2 && chunk.valid == 2));
+
+        // Changing a cell renders its chunk again.
+        map.set(0, 0, None);
+        prepare(&mut renderer, &map);
+        let (_, chunk) = renderer
+            .tilemap_chunks
+            .iter()
+            .find(|((_, _, chunk), _)| *chunk == UVec2::ZERO)
+            .unwrap();
+        assert_eq!(chunk.tiles[0], None);
+        assert_eq!(chunk.valid, 2);
+    }
+
+    proptest! {
+        #[test]
+        fn slice_is_in_bounds(
+            width in 1..512u32,
+            height in 1..512u32,
+            offset in (-600..600i32, -600..600i32),
+            size in (0..600u32, 0..600u32),
+        ) {
+            let image = image(width, height);
+            let slice = TextureSlice::from_layer(&image, 0).unwrap();
+            let (offset, size) = (IVec2::new(offset.0, offset.1), UVec2::new(size.0, size.1));
+            let in_bounds = offset.cmpge(IVec2::ZERO).all()
+                && (offset + size.as_ivec2()).cmple(IVec2::new(width as i32, height as i32)).all();
+            match slice.slice(offset, size) {
+                Some(sliced) => {
+                    prop_assert!(in_bounds);
+                    prop_assert_eq!(sliced.rect(), geom::Rect { offset, size });
+                }
+                None => prop_assert!(!in_bounds),
+            }
+        }
+
+        #[test]
+        fn nested_slices_add_offsets(
+            outer in (0..64i32, 0..64i32, 0..64u32, 0..64u32),
+            inner in (0..64i32, 0..64i32, 0..64u32, 0..64u32),
+            flip in (any::<bool>(), any::<bool>()),
+        ) {
+            let image = image(128, 128);
+            let mut slice = TextureSlice::from_layer(&image, 0).unwrap();
+            if flip.0 {
+                slice = slice.flip_x();
+            }
+            if flip.1 {
+                slice = slice.flip_y();
+            }
+            let outer_slice = slice
+                .slice(IVec2::new(outer.0, outer.1), UVec2::new(outer.2, outer.3))
+                .unwrap();
+            let nested = outer_slice.slice(IVec2::new(inner.0, inner.1), UVec2::new(inner.2, inner.3));
+            let direct = slice.slice(IVec2::new(outer.0 + inner.0, outer.1 + inner.1), UVec2::new(inner.2, inner.3));
+            let fits = inner.0 as u32 + inner.2 <= outer.2 && inner.1 as u32 + inner.3 <= outer.3;
+            prop_assert_eq!(nested.is_some(), fits);
+            if let Some(nested) = nested {
+                let direct = direct.unwrap();
+                prop_assert_eq!(nested.rect(), direct.rect());
+                prop_assert_eq!(nested.flip, slice.flip);
+            }
+        }
+
+        #[test]
+        fn anchor_lands_on_translation(
+            size in (1.0..500.0f32, 1.0..500.0f32),
+            anchor in (-1.0..2.0f32, -1.0..2.0f32),
+            angle in -std::f32::consts::PI..std::f32::consts::PI,
+            scale in 0.25..4.0f32,
+            translation in (-1000.0..1000.0f32, -1000.0..1000.0f32),
+        ) {
+            let (size, anchor) = (Vec2::new(size.0, size.1), Vec2::new(anchor.0, anchor.1));
+            let transform = Affine2::from_scale_angle_translation(
+                Vec2::splat(scale),
+                angle,
+                Vec2::new(translation.0, translation.1),
+            );
+            let mut canvas = Canvas::new();
+            canvas.draw(shape::Shape::rect(size).anchored(anchor), transform);
+            prop_assert_eq!(canvas.items.len(), 1);
+            let anchored = canvas.items[0].command.transform().transform_point2(size * anchor);
+            prop_assert!(anchored.abs_diff_eq(transform.translation, 1e-2));
+        }
+    }
 }
@@ -0,0 +1,37 @@
+//! Custom materials: user-supplied fragment shaders for drawing.
+//!
+//! A material is WGSL source defining a function with the signature
+//!
+//! ```wgsl
+//! fn material(in: VertexOutput, color: vec4<f32>) -> vec4<f32>
+//! ```
+//!
+//! where `color` is the texel that would have been drawn, already multiplied by the tint, and the return value is the straight (non-premultiplied) color to blend into the target. `in.position.xy` is the fragment's position in target pixels, and `in.tex_coords` its texture coordinates in texels.
+//!
+//! Draw with a material by wrapping a drawable with [`crate::Drawable::with_material`], or by pushing it onto the canvas with [`crate::Canvas::push_material`] for every draw until it is popped. Draws with different materials are never batched together.
+//!
+//! Materials that opt into the backdrop may also call `sample_backdrop(position: vec2<f32>) -> vec4<f32>` to read the target as rendered so far at a position in target pixels, e.g. for refraction, frosted glass or custom blend math. Reading the backdrop requires copying the target mid-frame, so such materials are only drawn by [`crate::Renderer::render_to_target`] and [`crate::Renderer::render_to_target_with`]; [`crate::Renderer::render`] skips them.
+
+/// Handle to a material created with [`crate::Renderer::create_material`].
+///
+/// A handle is only meaningful to the renderer that created it; other renderers draw with no material.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Material(pub(crate) u32);
+
+/// Describes a material to create.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MaterialDescriptor<'a> {
+    /// Label of the material's shader and pipelines in GPU captures.
+    pub label: Option<&'a str>,
+
+    /// WGSL source defining the `material` function.
+    pub source: &'a str,
+
+    /// Whether the material reads the backdrop with `sample_backdrop`.
+    pub backdrop: bool,
+
+    /// Layouts of extra bind groups the material declares, e.g. for a lighting texture, bound by the hook given to [`crate::Renderer::render_with`], [`crate::Renderer::render_to_target_with`] or [`crate::Renderer::render_emissive_to_target_with`].
+    ///
+    /// They follow the built-in bind groups, so start at group 2, or group 3 if the material reads the backdrop. Draws with the material are skipped where there is no hook to bind them, including inside opacity and mask groups, which are rendered while preparing.
+    pub bind_group_layouts: &'a [&'a wgpu::BindGroupLayout],
+}
@@ -0,0 +1,38 @@
+use glam::*;
+
+use crate::{Color, Texture};
+
+/// A vertex of a [`MeshCommand`].
+#[derive(Debug, Clone, Copy)]
+pub struct MeshVertex {
+    /// Position in local coordinates.
+    pub position: Vec2,
+    /// Texture coordinates in texels. Ignored for untextured meshes.
+    pub tex_coords: Vec2,
+    /// Per-vertex tint, multiplied with the command's tint.
+    pub tint: Color,
+}
+
+/// Arbitrary textured triangles staged for drawing.
+pub struct MeshCommand<'a> {
+    /// Texture to draw with, or [`None`] to draw solid color.
+    pub texture: Option<&'a dyn Texture>,
+    pub src_layer: u32,
+    pub vertices: Vec<MeshVertex>,
+    pub indices: Vec<u32>,
+    pub transform: Affine2,
+    pub tint: Color,
+}
+
+impl<'a> MeshCommand<'a> {
+    /// Gets the local bounding box of the mesh as (min, max).
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        if self.vertices.is_empty() {
+            return (Vec2::ZERO, Vec2::ZERO);
+        }
+        self.vertices.iter().fold(
+            (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+            |(min, max), v| (min.min(v.position), max.max(v.position)),
+        )
+    }
+}
@@ -0,0 +1,139 @@
+//! Nine-slice (nine-patch) sprites for resizable UI elements.
+
+use glam::*;
+
+use crate::{Canvas, Color, Drawable, Texture, TextureSlice};
+
+/// Border widths, in texels, of the fixed-size edges of a [`NineSlice`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Insets {
+    /// Left border.
+    pub left: u32,
+    /// Top border.
+    pub top: u32,
+    /// Right border.
+    pub right: u32,
+    /// Bottom border.
+    pub bottom: u32,
+}
+
+impl Insets {
+    /// Creates new insets.
+    pub const fn new(left: u32, top: u32, right: u32, bottom: u32) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    /// Creates insets with the same width on every side.
+    pub const fn uniform(width: u32) -> Self {
+        Self::new(width, width, width, width)
+    }
+}
+
+/// A texture slice drawn at an arbitrary size by splitting it into a 3x3 grid.
+///
+/// Corners are drawn unscaled, edges are stretched along one axis and the center is stretched along both. If the target size is smaller than the borders, the borders are shrunk proportionally.
+pub struct NineSlice<'a, T> {
+    slice: TextureSlice<'a, T>,
+    insets: Insets,
+    size: Vec2,
+}
+
+impl<'a, T> Clone for NineSlice<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for NineSlice<'a, T> {}
+
+impl<'a, T> NineSlice<'a, T>
+where
+    T: Texture,
+{
+    /// Creates a new nine-slice drawn at `size`.
+    ///
+    /// Returns [`None`] if the insets do not fit within the slice.
+    pub fn new(slice: TextureSlice<'a, T>, insets: Insets, size: Vec2) -> Option<Self> {
+        let src_size = slice.size();
+        if insets.left + insets.right > src_size.x || insets.top + insets.bottom > src_size.y {
+            return None;
+        }
+        Some(Self {
+            slice,
+            insets,
+            size,
+        })
+    }
+
+    /// Returns a copy drawn at a different size.
+    pub fn with_size(&self, size: Vec2) -> Self {
+        Self { size, ..*self }
+    }
+
+    /// Gets the size the nine-slice is drawn at.
+    pub fn size(&self) -> Vec2 {
+        self.size
+    }
+}
+
+/// Splits a source extent and a destination extent into three spans each: start border, middle, end border.
+fn spans(src: u32, start: u32, end: u32, dst: f32) -> [(u32, u32, f32, f32); 3] {
+    let borders = (start + end) as f32;
+    let scale = if borders > dst && borders > 0.0 {
+        dst.max(0.0) / borders
+    } else {
+        1.0
+    };
+    let dst_start = start as f32 * scale;
+    let dst_end = end as f32 * scale;
+    [
+        (0, start, 0.0, dst_start),
+        (
+            start,
+            src - start - end,
+            dst_start,
+            dst - dst_start - dst_end,
+        ),
+        (src - end, end, dst - dst_end, dst_end),
+    ]
+}
+
+impl<'a, T> Drawable<'a> for NineSlice<'a, T>
+where
+    T: Texture,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: Affine2) {
+        let src_size = self.slice.size();
+        let columns = spans(src_size.x, self.insets.left, self.insets.right, self.size.x);
+        let rows = spans(src_size.y, self.insets.top, self.insets.bottom, self.size.y);
+
+        for (src_y, src_h, dst_y, dst_h) in rows {
+            for (src_x, src_w, dst_x, dst_w) in columns {
+                if src_w == 0 || src_h == 0 || dst_w <= 0.0 || dst_h <= 0.0 {
+                    continue;
+                }
+                let Some(cell) = self.slice.slice(
+                    IVec2::new(src_x as i32, src_y as i32),
+                    UVec2::new(src_w, src_h),
+                ) else {
+                    continue;
+                };
+                cell.draw(
+                    canvas,
+                    tint,
+                    transform
+                        * Affine2::from_scale_angle_translation(
+                            Vec2::new(dst_w / src_w as f32, dst_h / src_h as f32),
+                            0.0,
+                            Vec2::new(dst_x, dst_y),
+                        ),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,658 @@
+//! Named colors, color ramps and helpers for working with [`Color`].
+//!
+//! Helpers come in two flavors: the plain versions operate directly on the sRGB-encoded components, while the `_linear` versions decode to linear light first, which gives physically correct results for blending.
+
+use crate::Color;
+
+/// Creates an opaque color from a `0xRRGGBB` value.
+pub const fn hex(rgb: u32) -> Color {
+    Color::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 0xff)
+}
+
+/// Fully transparent black.
+pub const TRANSPARENT: Color = Color::new(0x00, 0x00, 0x00, 0x00);
+/// White.
+pub const WHITE: Color = hex(0xffffff);
+/// Black.
+pub const BLACK: Color = hex(0x000000);
+/// Gray.
+pub const GRAY: Color = hex(0x808080);
+/// Red.
+pub const RED: Color = hex(0xff0000);
+/// Green.
+pub const GREEN: Color = hex(0x00ff00);
+/// Blue.
+pub const BLUE: Color = hex(0x0000ff);
+/// Yellow.
+pub const YELLOW: Color = hex(0xffff00);
+/// Cyan.
+pub const CYAN: Color = hex(0x00ffff);
+/// Magenta.
+pub const MAGENTA: Color = hex(0xff00ff);
+/// Orange.
+pub const ORANGE: Color = hex(0xffa500);
+/// Purple.
+pub const PURPLE: Color = hex(0x800080);
+/// Pink.
+pub const PINK: Color = hex(0xffc0cb);
+/// Brown.
+pub const BROWN: Color = hex(0xa52a2a);
+
+/// A ramp of 11 shades of one hue, from lightest (50) to darkest (950).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ramp(pub [Color; 11]);
+
+impl Ramp {
+    const STEPS: [u16; 11] = [50, 100, 200, 300, 400, 500, 600, 700, 800, 900, 950];
+
+    /// Gets the shade at the given step (50, 100, 200, ..., 900, 950).
+    ///
+    /// Returns [`None`] if the step is not one of the above.
+    pub fn shade(&self, step: u16) -> Option<Color> {
+        Self::STEPS
+            .iter()
+            .position(|s| *s == step)
+            .map(|i| self.0[i])
+    }
+
+    /// Samples the ramp at `t`, where 0 is the lightest shade and 1 is the darkest, interpolating between neighboring shades in linear space.
+    pub fn sample(&self, t: f32) -> Color {
+        let x = t.clamp(0.0, 1.0) * (self.0.len() - 1) as f32;
+        let i = (x.floor() as usize).min(self.0.len() - 2);
+        mix_linear(self.0[i], self.0[i + 1], x - i as f32)
+    }
+}
+
+/// Tailwind-style color ramps.
+pub mod ramps {
+    use super::{hex, Ramp};
+
+    /// Slate ramp.
+    pub const SLATE: Ramp = Ramp([
+        hex(0xf8fafc),
+        hex(0xf1f5f9),
+        hex(0xe2e8f0),
+        hex(0xcbd5e1),
+        hex(0x94a3b8),
+        hex(0x64748b),
+        hex(0x475569),
+        hex(0x334155),
+        hex(0x1e293b),
+        hex(0x0f172a),
+        hex(0x020617),
+    ]);
+
+    /// Gray ramp.
+    pub const GRAY: Ramp = Ramp([
+        hex(0xf9fafb),
+        hex(0xf3f4f6),
+        hex(0xe5e7eb),
+        hex(0xd1d5db),
+        hex(0x9ca3af),
+        hex(0x6b7280),
+        hex(0x4b5563),
+        hex(0x374151),
+        hex(0x1f2937),
+        hex(0x111827),
+        hex(0x030712),
+    ]);
+
+    /// Red ramp.
+    pub const RED: Ramp = Ramp([
+        hex(0xfef2f2),
+        hex(0xfee2e2),
+        hex(0xfecaca),
+        hex(0xfca5a5),
+        hex(0xf87171),
+        hex(0xef4444),
+        hex(0xdc2626),
+        hex(0xb91c1c),
+        hex(0x991b1b),
+        hex(0x7f1d1d),
+        hex(0x450a0a),
+    ]);
+
+    /// Orange ramp.
+    pub const ORANGE: Ramp = Ramp([
+        hex(0xfff7ed),
+        hex(0xffedd5),
+        hex(0xfed7aa),
+        hex(0xfdba74),
+        hex(0xfb923c),
+        hex(0xf97316),
+        hex(0xea580c),
+        hex(0xc2410c),
+        hex(0x9a3412),
+        hex(0x7c2d12),
+        hex(0x431407),
+    ]);
+
+    /// Amber ramp.
+    pub const AMBER: Ramp = Ramp([
+        hex(0xfffbeb),
+        hex(0xfef3c7),
+        hex(0xfde68a),
+        hex(0xfcd34d),
+        hex(0xfbbf24),
+        hex(0xf59e0b),
+        hex(0xd97706),
+        hex(0xb45309),
+        hex(0x92400e),
+        hex(0x78350f),
+        hex(0x451a03),
+    ]);
+
+    /// Yellow ramp.
+    pub const YELLOW: Ramp = Ramp([
+        hex(0xfefce8),
+        hex(0xfef9c3),
+        hex(0xfef08a),
+        hex(0xfde047),
+        hex(0xfacc15),
+        hex(0xeab308),
+        hex(0xca8a04),
+        hex(0xa16207),
+        hex(0x854d0e),
+        hex(0x713f12),
+        hex(0x422006),
+    ]);
+
+    /// Lime ramp.
+    pub const LIME: Ramp = Ramp([
+        hex(0xf7fee7),
+        hex(0xecfccb),
+        hex(0xd9f99d),
+        hex(0xbef264),
+        hex(0xa3e635),
+        hex(0x84cc16),
+        hex(0x65a30d),
+        hex(0x4d7c0f),
+        hex(0x3f6212),
+        hex(0x365314),
+        hex(0x1a2e05),
+    ]);
+
+    /// Green ramp.
+    pub const GREEN: Ramp = Ramp([
+        hex(0xf0fdf4),
+        hex(0xdcfce7),
+        hex(0xbbf7d0),
+        hex(0x86efac),
+        hex(0x4ade80),
+        hex(0x22c55e),
+        hex(0x16a34a),
+        hex(0x15803d),
+        hex(0x166534),
+        hex(0x14532d),
+        hex(0x052e16),
+    ]);
+
+    /// Emerald ramp.
+    pub const EMERALD: Ramp = Ramp([
+        hex(0xecfdf5),
+        hex(0xd1fae5),
+        hex(0xa7f3d0),
+        hex(0x6ee7b7),
+        hex(0x34d399),
+        hex(0x10b981),
+        hex(0x059669),
+        hex(0x047857),
+        hex(0x065f46),
+        hex(0x064e3b),
+        hex(0x022c22),
+    ]);
+
+    /// Teal ramp.
+    pub const TEAL: Ramp = Ramp([
+        hex(0xf0fdfa),
+        hex(0xccfbf1),
+        hex(0x99f6e4),
+        hex(0x5eead4),
+        hex(0x2dd4bf),
+        hex(0x14b8a6),
+        hex(0x0d9488),
+        hex(0x0f766e),
+        hex(0x115e59),
+        hex(0x134e4a),
+        hex(0x042f2e),
+    ]);
+
+    /// Cyan ramp.
+    pub const CYAN: Ramp = Ramp([
+        hex(0xecfeff),
+        hex(0xcffafe),
+        hex(0xa5f3fc),
+        hex(0x67e8f9),
+        hex(0x22d3ee),
+        hex(0x06b6d4),
+        hex(0x0891b2),
+        hex(0x0e7490),
+        hex(0x155e75),
+        hex(0x164e63),
+        hex(0x083344),
+    ]);
+
+    /// Sky ramp.
+    pub const SKY: Ramp = Ramp([
+        hex(0xf0f9ff),
+        hex(0xe0f2fe),
+        hex(0xbae6fd),
+        hex(0x7dd3fc),
+        hex(0x38bdf8),
+        hex(0x0ea5e9),
+        hex(0x0284c7),
+        hex(0x0369a1),
+        hex(0x075985),
+        hex(0x0c4a6e),
+        hex(0x082f49),
+    ]);
+
+    /// Blue ramp.
+    pub const BLUE: Ramp = Ramp([
+        hex(0xeff6ff),
+        hex(0xdbeafe),
+        hex(0xbfdbfe),
+        hex(0x93c5fd),
+        hex(0x60a5fa),
+        hex(0x3b82f6),
+        hex(0x2563eb),
+        hex(0x1d4ed8),
+        hex(0x1e40af),
+        hex(0x1e3a8a),
+        hex(0x172554),
+    ]);
+
+    /// Indigo ramp.
+    pub const INDIGO: Ramp = Ramp([
+        hex(0xeef2ff),
+        hex(0xe0e7ff),
+        hex(0xc7d2fe),
+        hex(0xa5b4fc),
+        hex(0x818cf8),
+        hex(0x6366f1),
+        hex(0x4f46e5),
+        hex(0x4338ca),
+        hex(0x3730a3),
+        hex(0x312e81),
+        hex(0x1e1b4b),
+    ]);
+
+    /// Violet ramp.
+    pub const VIOLET: Ramp = Ramp([
+        hex(0xf5f3ff),
+        hex(0xede9fe),
+        hex(0xddd6fe),
+        hex(0xc4b5fd),
+        hex(0xa78bfa),
+        hex(0x8b5cf6),
+        hex(0x7c3aed),
+        hex(0x6d28d9),
+        hex(0x5b21b6),
+        hex(0x4c1d95),
+        hex(0x2e1065),
+    ]);
+
+    /// Purple ramp.
+    pub const PURPLE: Ramp = Ramp([
+        hex(0xfaf5ff),
+        hex(0xf3e8ff),
+        hex(0xe9d5ff),
+        hex(0xd8b4fe),
+        hex(0xc084fc),
+        hex(0xa855f7),
+        hex(0x9333ea),
+        hex(0x7e22ce),
+        hex(0x6b21a8),
+        hex(0x581c87),
+        hex(0x3b0764),
+    ]);
+
+    /// Fuchsia ramp.
+    pub const FUCHSIA: Ramp = Ramp([
+        hex(0xfdf4ff),
+        hex(0xfae8ff),
+        hex(0xf5d0fe),
+        hex(0xf0abfc),
+        hex(0xe879f9),
+        hex(0xd946ef),
+        hex(0xc026d3),
+        hex(0xa21caf),
+        hex(0x86198f),
+        hex(0x701a75),
+        hex(0x4a044e),
+    ]);
+
+    /// Pink ramp.
+    pub const PINK: Ramp = Ramp([
+        hex(0xfdf2f8),
+        hex(0xfce7f3),
+        hex(0xfbcfe8),
+        hex(0xf9a8d4),
+        hex(0xf472b6),
+        hex(0xec4899),
+        hex(0xdb2777),
+        hex(0xbe185d),
+        hex(0x9d174d),
+        hex(0x831843),
+        hex(0x500724),
+    ]);
+
+    /// Rose ramp.
+    pub const ROSE: Ramp = Ramp([
+        hex(0xfff1f2),
+        hex(0xffe4e6),
+        hex(0xfecdd3),
+        hex(0xfda4af),
+        hex(0xfb7185),
+        hex(0xf43f5e),
+        hex(0xe11d48),
+        hex(0xbe123c),
+        hex(0x9f1239),
+        hex(0x881337),
+        hex(0x4c0519),
+    ]);
+}
+
+/// Converts an sRGB-encoded component to linear light.
+pub fn srgb_to_linear(v: u8) -> f32 {
+    let v = v as f32 / 255.0;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a linear light component to sRGB encoding.
+pub fn linear_to_srgb(v: f32) -> u8 {
+    let v = v.clamp(0.0, 1.0);
+    let v = if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    (v * 255.0).round() as u8
+}
+
+fn to_unorm(v: f32) -> u8 {
+    (v.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Returns the color with its alpha replaced.
+pub const fn with_alpha(color: Color, alpha: u8) -> Color {
+    Color::new(color.r, color.g, color.b, alpha)
+}
+
+/// Mixes two colors in sRGB space. `t` of 0 gives `a`, `t` of 1 gives `b`.
+pub fn mix(a: Color, b: Color, t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    Color::new(
+        to_unorm(lerp(a.r as f32, b.r as f32, t) / 255.0),
+        to_unorm(lerp(a.g as f32, b.g as f32, t) / 255.0),
+        to_unorm(lerp(a.b as f32, b.b as f32, t) / 255.0),
+        to_unorm(lerp(a.a as f32, b.a as f32, t) / 255.0),
+    )
+}
+
+/// Mixes two colors in linear space. `t` of 0 gives `a`, `t` of 1 gives `b`.
+///
+/// Alpha is always interpolated linearly, as it is not gamma encoded.
+pub fn mix_linear(a: Color, b: Color, t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    Color::new(
+        linear_to_srgb(lerp(srgb_to_linear(a.r), srgb_to_linear(b.r), t)),
+        linear_to_srgb(lerp(srgb_to_linear(a.g), srgb_to_linear(b.g), t)),
+        linear_to_srgb(lerp(srgb_to_linear(a.b), srgb_to_linear(b.b), t)),
+        to_unorm(lerp(a.a as f32, b.a as f32, t) / 255.0),
+    )
+}
+
+/// Lightens a color by mixing it towards white in sRGB space, preserving alpha.
+pub fn lighten(color: Color, amount: f32) -> Color {
+    with_alpha(mix(color, WHITE, amount), color.a)
+}
+
+/// Darkens a color by mixing it towards black in sRGB space, preserving alpha.
+pub fn darken(color: Color, amount: f32) -> Color {
+    with_alpha(mix(color, BLACK, amount), color.a)
+}
+
+/// Lightens a color by mixing it towards white in linear space, preserving alpha.
+pub fn lighten_linear(color: Color, amount: f32) -> Color {
+    with_alpha(mix_linear(color, WHITE, amount), color.a)
+}
+
+/// Darkens a color by mixing it towards black in linear space, preserving alpha.
+pub fn darken_linear(color: Color, amount: f32) -> Color {
+    with_alpha(mix_linear(color, BLACK, amount), color.a)
+}
+
+fn lerp_hue(a: f32, b: f32, t: f32) -> f32 {
+    let delta = (b - a + 540.0).rem_euclid(360.0) - 180.0;
+    (a + delta * t).rem_euclid(360.0)
+}
+
+/// A color in the HSV (hue, saturation, value) model, based on sRGB-encoded components.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    /// Hue in degrees, in `[0, 360)`.
+    pub h: f32,
+    /// Saturation, in `[0, 1]`.
+    pub s: f32,
+    /// Value, in `[0, 1]`.
+    pub v: f32,
+    /// Alpha, in `[0, 1]`.
+    pub alpha: f32,
+}
+
+impl Hsv {
+    /// Creates a new HSV color.
+    pub const fn new(h: f32, s: f32, v: f32, alpha: f32) -> Self {
+        Self { h, s, v, alpha }
+    }
+
+    /// Converts a [`Color`] to HSV.
+    pub fn from_color(color: Color) -> Self {
+        let r = color.r as f32 / 255.0;
+        let g = color.g as f32 / 255.0;
+        let b = color.b as f32 / 255.0;
+
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+
+        let h = if chroma == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / chroma).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / chroma + 2.0)
+        } else {
+            60.0 * ((r - g) / chroma + 4.0)
+        };
+
+        Self {
+            h,
+            s: if max == 0.0 { 0.0 } else { chroma / max },
+            v: max,
+            alpha: color.a as f32 / 255.0,
+        }
+    }
+
+    /// Converts to a [`Color`].
+    pub fn to_color(&self) -> Color {
+        let h = self.h.rem_euclid(360.0) / 60.0;
+        let s = self.s.clamp(0.0, 1.0);
+        let v = self.v.clamp(0.0, 1.0);
+        let chroma = v * s;
+        let x = chroma * (1.0 - (h.rem_euclid(2.0) - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = v - chroma;
+        Color::new(
+            to_unorm(r + m),
+            to_unorm(g + m),
+            to_unorm(b + m),
+            to_unorm(self.alpha),
+        )
+    }
+
+    /// Interpolates between two HSV colors, taking the shortest path around the hue circle.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            h: lerp_hue(self.h, other.h, t),
+            s: lerp(self.s, other.s, t),
+            v: lerp(self.v, other.v, t),
+            alpha: lerp(self.alpha, other.alpha, t),
+        }
+    }
+}
+
+/// A color in the OKLab perceptual color space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklab {
+    /// Perceived lightness, in `[0, 1]`.
+    pub l: f32,
+    /// Green-red axis.
+    pub a: f32,
+    /// Blue-yellow axis.
+    pub b: f32,
+    /// Alpha, in `[0, 1]`.
+    pub alpha: f32,
+}
+
+impl Oklab {
+    /// Creates a new OKLab color.
+    pub const fn new(l: f32, a: f32, b: f32, alpha: f32) -> Self {
+        Self { l, a, b, alpha }
+    }
+
+    /// Converts a [`Color`] to OKLab.
+    pub fn from_color(color: Color) -> Self {
+        let r = srgb_to_linear(color.r);
+        let g = srgb_to_linear(color.g);
+        let b = srgb_to_linear(color.b);
+
+        let l = (0.41222147 * r + 0.53633254 * g + 0.05144599 * b).cbrt();
+        let m = (0.2119035 * r + 0.6806995 * g + 0.10739696 * b).cbrt();
+        let s = (0.08830246 * r + 0.28171884 * g + 0.6299787 * b).cbrt();
+
+        Self {
+            l: 0.21045426 * l + 0.7936178 * m - 0.00407205 * s,
+            a: 1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+            b: 0.02590404 * l + 0.78277177 * m - 0.80867577 * s,
+            alpha: color.a as f32 / 255.0,
+        }
+    }
+
+    /// Converts to a [`Color`], clipping out-of-gamut components.
+    pub fn to_color(&self) -> Color {
+        let l = (self.l + 0.39633778 * self.a + 0.21580376 * self.b).powi(3);
+        let m = (self.l - 0.10556135 * self.a - 0.06385417 * self.b).powi(3);
+        let s = (self.l - 0.08948418 * self.a - 1.2914855 * self.b).powi(3);
+
+        Color::new(
+            linear_to_srgb(4.0767417 * l - 3.3077116 * m + 0.23096993 * s),
+            linear_to_srgb(-1.268438 * l + 2.6097574 * m - 0.3413194 * s),
+            linear_to_srgb(-0.00419609 * l - 0.7034186 * m + 1.7076147 * s),
+            to_unorm(self.alpha),
+        )
+    }
+
+    /// Interpolates between two OKLab colors.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            l: lerp(self.l, other.l, t),
+            a: lerp(self.a, other.a, t),
+            b: lerp(self.b, other.b, t),
+            alpha: lerp(self.alpha, other.alpha, t),
+        }
+    }
+}
+
+/// A color in the OKLCH color space, the cylindrical form of [`Oklab`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklch {
+    /// Perceived lightness, in `[0, 1]`.
+    pub l: f32,
+    /// Chroma.
+    pub c: f32,
+    /// Hue in degrees, in `[0, 360)`.
+    pub h: f32,
+    /// Alpha, in `[0, 1]`.
+    pub alpha: f32,
+}
+
+impl Oklch {
+    /// Creates a new OKLCH color.
+    pub const fn new(l: f32, c: f32, h: f32, alpha: f32) -> Self {
+        Self { l, c, h, alpha }
+    }
+
+    /// Converts a [`Color`] to OKLCH.
+    pub fn from_color(color: Color) -> Self {
+        Self::from(Oklab::from_color(color))
+    }
+
+    /// Converts to a [`Color`], clipping out-of-gamut components.
+    pub fn to_color(&self) -> Color {
+        Oklab::from(*self).to_color()
+    }
+
+    /// Interpolates between two OKLCH colors, taking the shortest path around the hue circle.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            l: lerp(self.l, other.l, t),
+            c: lerp(self.c, other.c, t),
+            h: lerp_hue(self.h, other.h, t),
+            alpha: lerp(self.alpha, other.alpha, t),
+        }
+    }
+}
+
+impl From<Oklab> for Oklch {
+    fn from(lab: Oklab) -> Self {
+        Self {
+            l: lab.l,
+            c: lab.a.hypot(lab.b),
+            h: lab.b.atan2(lab.a).to_degrees().rem_euclid(360.0),
+            alpha: lab.alpha,
+        }
+    }
+}
+
+impl From<Oklch> for Oklab {
+    fn from(lch: Oklch) -> Self {
+        let (sin, cos) = lch.h.to_radians().sin_cos();
+        Self {
+            l: lch.l,
+            a: lch.c * cos,
+            b: lch.c * sin,
+            alpha: lch.alpha,
+        }
+    }
+}
+
+/// Mixes two colors in OKLab space, which keeps perceived lightness even across the blend.
+pub fn mix_oklab(a: Color, b: Color, t: f32) -> Color {
+    Oklab::from_color(a)
+        .lerp(&Oklab::from_color(b), t.clamp(0.0, 1.0))
+        .to_color()
+}
+
+/// Mixes two colors in OKLCH space, which additionally keeps saturation by rotating through hues rather than passing through gray.
+pub fn mix_oklch(a: Color, b: Color, t: f32) -> Color {
+    Oklch::from_color(a)
+        .lerp(&Oklch::from_color(b), t.clamp(0.0, 1.0))
+        .to_color()
+}
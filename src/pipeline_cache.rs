@@ -0,0 +1,56 @@
+//! Saving compiled pipelines to disk, so later runs do not hitch compiling them on their first frames.
+//!
+//! Load a cache with [`load`] before creating the renderer, pass it to [`crate::Renderer::with_pipeline_cache`], and write it back with [`save`] once the pipelines you need have been created, e.g. after the first frame or on exit.
+//!
+//! Pipeline caches are only supported on Vulkan, and only if the device was requested with [`wgpu::Features::PIPELINE_CACHE`]. Elsewhere [`load`] returns [`None`] and pipelines are compiled as usual.
+
+use std::path::{Path, PathBuf};
+
+/// Gets the path of the cache file for an adapter within `dir`.
+///
+/// Returns [`None`] if the adapter's backend does not support pipeline caches.
+pub fn path(adapter_info: &wgpu::AdapterInfo, dir: &Path) -> Option<PathBuf> {
+    Some(dir.join(wgpu::util::pipeline_cache_key(adapter_info)?))
+}
+
+/// Creates a pipeline cache, seeded from the file saved to `dir` for the adapter if there is one.
+///
+/// A missing or unreadable file gives an empty cache, as does one the driver rejects, e.g. after a driver update. Returns [`None`] if pipeline caches are not supported.
+///
+/// # Safety
+///
+/// The file, if any, must have been written by [`save`], as drivers do not fully validate cache data.
+pub unsafe fn load(
+    device: &wgpu::Device,
+    adapter_info: &wgpu::AdapterInfo,
+    dir: &Path,
+) -> Option<wgpu::PipelineCache> {
+    if !device.features().contains(wgpu::Features::PIPELINE_CACHE) {
+        return None;
+    }
+    let data = std::fs::read(path(adapter_info, dir)?).ok();
+    Some(unsafe {
+        device.create_pipeline_cache(&wgpu::PipelineCacheDescriptor {
+            label: Some("canvasette: pipeline_cache"),
+            data: data.as_deref(),
+            fallback: true,
+        })
+    })
+}
+
+/// Writes a pipeline cache to `dir` for [`load`] to read on a later run, creating the directory if needed.
+///
+/// The data is written to a temporary file that then replaces the cache file, so an interrupted write never leaves a truncated cache behind. Does nothing if pipeline caches are not supported.
+pub fn save(
+    cache: &wgpu::PipelineCache,
+    adapter_info: &wgpu::AdapterInfo,
+    dir: &Path,
+) -> std::io::Result<()> {
+    let (Some(path), Some(data)) = (path(adapter_info, dir), cache.get_data()) else {
+        return Ok(());
+    };
+    std::fs::create_dir_all(dir)?;
+    let temp = path.with_extension("tmp");
+    std::fs::write(&temp, data)?;
+    std::fs::rename(temp, path)
+}
@@ -0,0 +1,63 @@
+//! Post effects: full-screen shader passes run over a rendered scene, e.g. a vignette, CRT scanlines or color grading.
+//!
+//! A post effect is WGSL source defining a function with the signature
+//!
+//! ```wgsl
+//! fn post_effect(in: PostOutput, color: vec4<f32>) -> vec4<f32>
+//! ```
+//!
+//! where `color` is the premultiplied source pixel under the fragment, and the return value is the premultiplied color written to the target, replacing what was there. `in.position.xy` is the fragment's position in target pixels and `in.uv` its normalized texture coordinates. The source may be read elsewhere with `sample_source(uv: vec2<f32>) -> vec4<f32>`, e.g. for blur or chromatic aberration, and `post_uniforms.size` and `post_uniforms.params` hold the target size in pixels and the parameters of the [`PostPass`].
+//!
+//! Render a canvas through a chain of passes with [`crate::Renderer::render_with_post_effects`], or post-process an existing texture with [`crate::Renderer::apply_post_effects`].
+
+use glam::*;
+
+/// Handle to a post effect created with [`crate::Renderer::create_post_effect`].
+///
+/// A handle is only meaningful to the renderer that created it; other renderers copy the source unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PostEffect(pub(crate) u32);
+
+/// Describes a post effect to create.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PostEffectDescriptor<'a> {
+    /// Label of the effect's shader and pipeline in GPU captures.
+    pub label: Option<&'a str>,
+
+    /// WGSL source defining the `post_effect` function.
+    pub source: &'a str,
+
+    /// Layouts of extra bind groups the effect declares, e.g. for a color grading lookup texture, bound from [`PostPass::bind_groups`].
+    ///
+    /// They follow the built-in bind group, so start at group 1.
+    pub bind_group_layouts: &'a [&'a wgpu::BindGroupLayout],
+}
+
+/// A single full-screen pass of a post effect.
+#[derive(Debug, Clone, Copy)]
+pub struct PostPass<'a> {
+    /// Effect to run.
+    pub effect: PostEffect,
+
+    /// Parameters available to the shader as `post_uniforms.params`, e.g. a vignette's strength or the time for animated noise.
+    pub params: Vec4,
+
+    /// Extra bind groups, matching the effect's [`PostEffectDescriptor::bind_group_layouts`].
+    pub bind_groups: &'a [&'a wgpu::BindGroup],
+}
+
+impl PostPass<'_> {
+    /// Creates a pass with no parameters or extra bind groups.
+    pub fn new(effect: PostEffect) -> Self {
+        Self {
+            effect,
+            params: Vec4::ZERO,
+            bind_groups: &[],
+        }
+    }
+
+    /// Returns a copy with different parameters.
+    pub fn with_params(&self, params: Vec4) -> Self {
+        Self { params, ..*self }
+    }
+}
@@ -0,0 +1,37 @@
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+struct PostUniforms {
+    size: vec2<f32>,
+    params: vec4<f32>,
+}
+
+@group(0) @binding(2)
+var<uniform> post_uniforms: PostUniforms;
+
+struct PostOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// Covers the target with a single triangle.
+@vertex
+fn vs_post(@builtin(vertex_index) index: u32) -> PostOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: PostOutput;
+    out.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+// Samples the source at normalized texture coordinates.
+fn sample_source(uv: vec2<f32>) -> vec4<f32> {
+    return textureSample(source, source_sampler, uv);
+}
+
+@fragment
+fn fs_post(in: PostOutput) -> @location(0) vec4<f32> {
+    return post_effect(in, sample_source(in.uv));
+}
@@ -0,0 +1,79 @@
+//! Reflections: a layer redrawn mirrored about a horizontal line, for floors and water.
+//!
+//! A [`Reflection`] is attached to a layer with [`crate::Canvas::set_layer_reflection`]. Everything drawn in the layer is then drawn a second time, flipped about the reflection's axis and faded out with distance from it, just before the layer's first item in z-order, so the originals are drawn on top of their reflections.
+
+use glam::*;
+
+use crate::{Texture, TextureSlice};
+
+/// Settings for mirroring a layer about a horizontal line.
+///
+/// The axis and fade length are in the layer's world coordinates, before its view is applied.
+#[derive(Clone, Copy)]
+pub struct Reflection<'a> {
+    pub(crate) axis: f32,
+    pub(crate) opacity: f32,
+    pub(crate) fade: f32,
+    pub(crate) distortion: Option<ReflectionDistortion<'a>>,
+}
+
+/// Offset map rippling a reflection.
+#[derive(Clone, Copy)]
+pub(crate) struct ReflectionDistortion<'a> {
+    pub texture: &'a dyn Texture,
+    pub src_offset: IVec2,
+    pub src_size: UVec2,
+    pub src_layer: u32,
+    pub strength: f32,
+}
+
+impl<'a> Reflection<'a> {
+    /// Creates a reflection about the line `y = axis`, at half opacity and with no fade.
+    pub fn new(axis: f32) -> Self {
+        Self {
+            axis,
+            opacity: 0.5,
+            fade: 0.0,
+            distortion: None,
+        }
+    }
+
+    /// Sets the opacity the reflection starts at on the axis.
+    pub fn opacity(self, opacity: f32) -> Self {
+        Self { opacity, ..self }
+    }
+
+    /// Sets the distance below the axis over which the reflection fades out completely.
+    ///
+    /// A distance of 0 disables fading.
+    pub fn fade(self, fade: f32) -> Self {
+        Self { fade, ..self }
+    }
+
+    /// Ripples everything below the axis with an offset map, as with a [`crate::distortion::Distortion`].
+    ///
+    /// The map is stretched over the faded part of the reflection, or the whole visible area below the axis if there is no fade. Like other distortions, this is only drawn by [`crate::Renderer::render_to_target`].
+    pub fn distortion<T>(self, map: TextureSlice<'a, T>, strength: f32) -> Self
+    where
+        T: Texture,
+    {
+        Self {
+            distortion: Some(ReflectionDistortion {
+                texture: map.texture,
+                src_offset: map.rect.offset,
+                src_size: map.rect.size,
+                src_layer: map.layer,
+                strength,
+            }),
+            ..self
+        }
+    }
+
+    /// Gets the transform mirroring world coordinates about the axis.
+    pub(crate) fn mirror(&self) -> Affine2 {
+        Affine2::from_mat2_translation(
+            Mat2::from_diagonal(Vec2::new(1.0, -1.0)),
+            Vec2::new(0.0, 2.0 * self.axis),
+        )
+    }
+}
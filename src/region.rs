@@ -0,0 +1,124 @@
+//! Named regions of textures, so sprites can be looked up by name instead of by coordinates.
+
+use glam::*;
+
+/// A rectangle within a texture, in texels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Region {
+    /// Offset of the top-left corner.
+    pub offset: IVec2,
+    /// Size.
+    pub size: UVec2,
+    /// Array layer.
+    pub layer: u32,
+}
+
+impl Region {
+    /// Creates a new region on the first layer.
+    pub const fn new(offset: IVec2, size: UVec2) -> Self {
+        Self {
+            offset,
+            size,
+            layer: 0,
+        }
+    }
+
+    /// Creates a new region on the first layer from its left and top edges and size.
+    pub const fn from_rect(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Self::new(IVec2::new(x, y), UVec2::new(width, height))
+    }
+
+    /// Returns the region on a different layer.
+    pub const fn with_layer(self, layer: u32) -> Self {
+        Self { layer, ..self }
+    }
+}
+
+/// A registry of named regions, carried by [`crate::Image`] and [`crate::DynamicTexture`].
+///
+/// Regions are not checked against the texture when added: [`crate::Texture::region`] returns [`None`] for regions that do not fit.
+#[derive(Debug, Clone, Default)]
+pub struct Regions {
+    regions: std::collections::HashMap<String, Region>,
+}
+
+impl Regions {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a region, returning the region previously registered under the same name.
+    pub fn insert(&mut self, name: impl Into<String>, region: Region) -> Option<Region> {
+        self.regions.insert(name.into(), region)
+    }
+
+    /// Removes a region.
+    pub fn remove(&mut self, name: &str) -> Option<Region> {
+        self.regions.remove(name)
+    }
+
+    /// Gets a region by name.
+    pub fn get(&self, name: &str) -> Option<Region> {
+        self.regions.get(name).copied()
+    }
+
+    /// Gets the number of regions.
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    /// Gets whether there are no regions.
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// Removes all regions.
+    pub fn clear(&mut self) {
+        self.regions.clear();
+    }
+
+    /// Iterates over the regions and their names, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Region)> + '_ {
+        self.regions
+            .iter()
+            .map(|(name, region)| (name.as_str(), *region))
+    }
+}
+
+impl<S> FromIterator<(S, Region)> for Regions
+where
+    S: Into<String>,
+{
+    fn from_iter<I: IntoIterator<Item = (S, Region)>>(iter: I) -> Self {
+        Self {
+            regions: iter
+                .into_iter()
+                .map(|(name, region)| (name.into(), region))
+                .collect(),
+        }
+    }
+}
+
+impl<S> Extend<(S, Region)> for Regions
+where
+    S: Into<String>,
+{
+    fn extend<I: IntoIterator<Item = (S, Region)>>(&mut self, iter: I) {
+        self.regions
+            .extend(iter.into_iter().map(|(name, region)| (name.into(), region)));
+    }
+}
+
+/// An atlas packed ahead of time, e.g. by the `canvasette-bake` crate, which can be loaded with [`crate::Image::from_baked`].
+#[derive(Debug, Clone, Copy)]
+pub struct BakedAtlas {
+    /// Width in texels.
+    pub width: u32,
+    /// Height in texels.
+    pub height: u32,
+    /// Tightly packed sRGB RGBA pixels.
+    pub pixels: &'static [u8],
+    /// Named regions of the atlas.
+    pub regions: &'static [(&'static str, Region)],
+}
@@ -0,0 +1,235 @@
+//! Ropes: a texture repeated along a path, for chains, vines and wires.
+
+use glam::*;
+
+use crate::{mesh::MeshVertex, palette, Canvas, Color, Command, Drawable, Texture, TextureSlice};
+
+/// Maximum length of a joint's miter, as a multiple of half the rope's width.
+///
+/// Sharper joints are flattened rather than spiking out.
+const MAX_MITER: f32 = 4.0;
+
+/// Approximate length, in target pixels, of each line segment a smooth rope's curve is split into.
+const SUBDIVISION_LENGTH: f32 = 4.0;
+
+/// A texture slice repeated along a path.
+///
+/// The slice's width runs along the path and its height across it. The slice is scaled so that its height matches the rope's width, and repeated end to end as many times as needed, bending at each joint.
+pub struct Rope<'a, T> {
+    slice: TextureSlice<'a, T>,
+    points: Vec<Vec2>,
+    width: f32,
+    smooth: bool,
+}
+
+impl<'a, T> Clone for Rope<'a, T> {
+    fn clone(&self) -> Self {
+        Self {
+            slice: self.slice,
+            points: self.points.clone(),
+            width: self.width,
+            smooth: self.smooth,
+        }
+    }
+}
+
+impl<'a, T> Rope<'a, T>
+where
+    T: Texture,
+{
+    /// Creates a new rope of the given width along a polyline through `points`.
+    pub fn new(slice: TextureSlice<'a, T>, points: Vec<Vec2>, width: f32) -> Self {
+        Self {
+            slice,
+            points,
+            width,
+            smooth: false,
+        }
+    }
+
+    /// Sets whether the path is a Catmull-Rom spline through the points instead of a polyline.
+    pub fn smooth(self, smooth: bool) -> Self {
+        Self { smooth, ..self }
+    }
+
+    /// Gets the points the path passes through.
+    pub fn points(&self) -> &[Vec2] {
+        &self.points
+    }
+
+    /// Gets the points the path passes through, mutably.
+    pub fn points_mut(&mut self) -> &mut Vec<Vec2> {
+        &mut self.points
+    }
+}
+
+impl<'a, T> Drawable<'a> for Rope<'a, T>
+where
+    T: Texture,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: Affine2) {
+        if self.points.len() < 2 {
+            return;
+        }
+        canvas.push(Command::Rope(RopeCommand {
+            texture: self.slice.texture,
+            src_offset: self.slice.rect.offset,
+            src_size: self.slice.rect.size,
+            src_layer: self.slice.layer,
+            flip: self.slice.flip,
+            points: self.points.clone(),
+            width: self.width,
+            smooth: self.smooth,
+            transform,
+            tint,
+        }));
+    }
+}
+
+/// A rope staged for drawing.
+///
+/// The geometry is only generated at prepare time, once the scale the rope is drawn at is known.
+pub(crate) struct RopeCommand<'a> {
+    pub texture: &'a dyn Texture,
+    pub src_offset: IVec2,
+    pub src_size: UVec2,
+    pub src_layer: u32,
+    pub flip: BVec2,
+    pub points: Vec<Vec2>,
+    pub width: f32,
+    pub smooth: bool,
+    pub transform: Affine2,
+    pub tint: Color,
+}
+
+impl<'a> RopeCommand<'a> {
+    /// Gets the approximate local bounding box of the rope as (min, max).
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        let (min, max) = self.points.iter().fold(
+            (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+            |(min, max), p| (min.min(*p), max.max(*p)),
+        );
+        let pad = Vec2::splat(self.width * 0.5 * MAX_MITER);
+        (min - pad, max + pad)
+    }
+
+    /// Computes the points of the path, subdividing curves into line segments.
+    ///
+    /// `scale` is the approximate number of target pixels per local unit.
+    fn path(&self, scale: f32) -> Vec<Vec2> {
+        let mut path: Vec<Vec2> = Vec::with_capacity(self.points.len());
+        let mut push = |p: Vec2| {
+            if path.last() != Some(&p) {
+                path.push(p);
+            }
+        };
+
+        if !self.smooth {
+            self.points.iter().copied().for_each(push);
+            return path;
+        }
+
+        let n = self.points.len();
+        for i in 0..n - 1 {
+            let p0 = self.points[i.saturating_sub(1)];
+            let p1 = self.points[i];
+            let p2 = self.points[i + 1];
+            let p3 = self.points[(i + 2).min(n - 1)];
+            let steps = if scale.is_finite() {
+                ((p1.distance(p2) * scale / SUBDIVISION_LENGTH).ceil() as u32).clamp(1, 64)
+            } else {
+                1
+            };
+            for step in 0..steps {
+                push(catmull_rom(p0, p1, p2, p3, step as f32 / steps as f32));
+            }
+        }
+        push(self.points[n - 1]);
+        path
+    }
+
+    /// Generates the rope's triangles in local coordinates.
+    pub fn tessellate(&self, scale: f32) -> (Vec<MeshVertex>, Vec<u32>) {
+        let path = self.path(scale);
+        if path.len() < 2 || self.src_size.x == 0 || self.src_size.y == 0 {
+            return (vec![], vec![]);
+        }
+
+        let half_width = self.width * 0.5;
+        let src_offset = self.src_offset.as_vec2();
+        let src_size = self.src_size.as_vec2();
+        let repeat_length = src_size.x * self.width / src_size.y;
+        let tex_coords = |p: Vec2| src_offset + Vec2::select(self.flip, src_size - p, p);
+        if !repeat_length.is_finite() || repeat_length <= 0.0 {
+            return (vec![], vec![]);
+        }
+
+        // Offsets of each point of the path across the rope, mitered at the joints.
+        let edge_normals = path
+            .windows(2)
+            .map(|edge| (edge[1] - edge[0]).normalize_or_zero().perp())
+            .collect::<Vec<_>>();
+        let offsets = (0..path.len())
+            .map(|i| {
+                let prev = edge_normals[i.saturating_sub(1)];
+                let next = edge_normals[i.min(edge_normals.len() - 1)];
+                let miter = (prev + next).normalize_or(next);
+                let length = 1.0 / miter.dot(next).max(1.0 / MAX_MITER);
+                miter * (half_width * length)
+            })
+            .collect::<Vec<_>>();
+
+        let mut vertices = vec![];
+        let mut indices = vec![];
+        let mut distance = 0.0;
+        for (i, edge) in path.windows(2).enumerate() {
+            let length = edge[0].distance(edge[1]);
+            let end = distance + length;
+
+            // Split the edge wherever one repetition of the texture ends and the next begins.
+            let mut s0 = distance;
+            while s0 < end {
+                let repetition = (s0 / repeat_length).floor();
+                let s1 = ((repetition + 1.0) * repeat_length).min(end);
+                if s1 <= s0 {
+                    break;
+                }
+                let [t0, t1] = [s0, s1].map(|s| (s - distance) / length);
+                let [u0, u1] =
+                    [s0, s1].map(|s| (s / repeat_length - repetition).clamp(0.0, 1.0) * src_size.x);
+
+                let base = vertices.len() as u32;
+                for (t, u) in [(t0, u0), (t1, u1)] {
+                    let position = edge[0].lerp(edge[1], t);
+                    let offset = offsets[i].lerp(offsets[i + 1], t);
+                    vertices.push(MeshVertex {
+                        position: position + offset,
+                        tex_coords: tex_coords(Vec2::new(u, 0.0)),
+                        tint: palette::WHITE,
+                    });
+                    vertices.push(MeshVertex {
+                        position: position - offset,
+                        tex_coords: tex_coords(Vec2::new(u, src_size.y)),
+                        tint: palette::WHITE,
+                    });
+                }
+                indices.extend([base, base + 1, base + 2, base + 1, base + 3, base + 2]);
+
+                s0 = s1;
+            }
+            distance = end;
+        }
+
+        (vertices, indices)
+    }
+}
+
+/// Evaluates a uniform Catmull-Rom spline segment between `p1` and `p2`.
+fn catmull_rom(p0: Vec2, p1: Vec2, p2: Vec2, p3: Vec2, t: f32) -> Vec2 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
@@ -0,0 +1,122 @@
+@group(0) @binding(0)
+var t: texture_2d_array<f32>;
+@group(0) @binding(1)
+var s: sampler;
+
+struct TextureUniforms {
+    size: vec3<f32>,
+    is_mask: u32,
+    premultiplied: u32,
+    distortion_strength: f32,
+    fade_origin: vec2<f32>,
+    fade_direction: vec2<f32>,
+    palette_origin: vec2<u32>,
+    palette_layer: u32,
+    palette_width: u32,
+}
+
+@group(0) @binding(2)
+var<uniform> texture_uniforms: TextureUniforms;
+
+struct TargetUniforms {
+    size: vec3<f32>,
+}
+
+@group(1) @binding(0)
+var<uniform> target_uniforms: TargetUniforms;
+
+@group(2) @binding(0)
+var backdrop: texture_2d<f32>;
+@group(2) @binding(1)
+var palette: texture_2d_array<f32>;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) layer: u32,
+    @location(3) tint: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) @interpolate(flat) layer: u32,
+    @location(2) tint: vec4<f32>,
+};
+
+@vertex
+fn vs_main(model: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+
+    out.tint = model.tint;
+
+    // Normalize screen position to NDC position.
+    var pos = (model.position / target_uniforms.size.xy - 0.5) * 2.0;
+    pos.y = -pos.y;
+
+    out.tex_coords = model.tex_coords;
+    out.layer = model.layer;
+    out.position = vec4<f32>(pos, 0.0, 1.0);
+    return out;
+}
+
+fn sample_texture(in: VertexOutput) -> vec4<f32> {
+    let sample = textureSample(t, s, in.tex_coords / texture_uniforms.size.xy, in.layer);
+    if texture_uniforms.is_mask == 1 {
+        if texture_uniforms.premultiplied == 1 {
+            return vec4(sample.r);
+        }
+        return vec4(1.0, 1.0, 1.0, sample.r);
+    }
+    return sample;
+}
+
+// Converts a sample to straight alpha, if textures are premultiplied.
+fn unpremultiply(sample: vec4<f32>) -> vec4<f32> {
+    if texture_uniforms.premultiplied == 1 && sample.a > 0.0 {
+        return vec4(sample.rgb / sample.a, sample.a);
+    }
+    return sample;
+}
+
+// Samples the backdrop at a position in target pixels. Only available to pipelines that bind the backdrop.
+fn sample_backdrop(position: vec2<f32>) -> vec4<f32> {
+    return textureSample(backdrop, s, position / target_uniforms.size.xy);
+}
+
+// Applies the tint and fade to a sample.
+fn shade(in: VertexOutput, sample: vec4<f32>) -> vec4<f32> {
+    // A zero fade direction leaves the output unchanged.
+    let fade = clamp(1.0 - dot(in.position.xy - texture_uniforms.fade_origin, texture_uniforms.fade_direction), 0.0, 1.0);
+    if texture_uniforms.premultiplied == 1 {
+        return sample * vec4(in.tint.rgb * in.tint.a, in.tint.a) * fade;
+    }
+    let color = sample * in.tint;
+    return vec4(color.rgb * color.a, color.a) * fade;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return shade(in, sample_texture(in));
+}
+
+// Looks up the texel's index in the palette. Indices are read exactly, so the texture is never filtered.
+@fragment
+fn fs_palette(in: VertexOutput) -> @location(0) vec4<f32> {
+    let index = u32(round(textureLoad(t, vec2<i32>(floor(in.tex_coords)), in.layer, 0).r * 255.0));
+    let x = min(index, texture_uniforms.palette_width - 1u);
+    return shade(in, textureLoad(palette, texture_uniforms.palette_origin + vec2(x, 0u), texture_uniforms.palette_layer, 0));
+}
+
+// Draws the backdrop, which is bound to a group's contents, through the texture's alpha.
+@fragment
+fn fs_mask(in: VertexOutput) -> @location(0) vec4<f32> {
+    return sample_backdrop(in.position.xy) * sample_texture(in).a * in.tint.a;
+}
+
+@fragment
+fn fs_distort(in: VertexOutput) -> @location(0) vec4<f32> {
+    let offset = unpremultiply(sample_texture(in)).rg * 2.0 - 1.0;
+    let color = sample_backdrop(in.position.xy + offset * texture_uniforms.distortion_strength) * in.tint;
+    return vec4(color.rgb * color.a, color.a);
+}
@@ -0,0 +1,242 @@
+//! Solid-color primitive shapes.
+
+use glam::*;
+
+use crate::{Canvas, Color, Command, Drawable, Tint, Tinted};
+
+/// A primitive shape.
+///
+/// Shapes are drawn in white, multiplied by the tint. Use [`Shape::filled`] (or [`Drawable::tinted`]) to give them a color.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+    /// A rectangle with its top-left corner at the origin.
+    Rect {
+        /// Size of the rectangle.
+        size: Vec2,
+    },
+
+    /// A rectangle with rounded corners, with its top-left corner at the origin.
+    RoundedRect {
+        /// Size of the rectangle.
+        size: Vec2,
+        /// Radius of the corners. This is clamped to half of the shorter side.
+        radius: f32,
+    },
+
+    /// A circle centered at the origin.
+    Circle {
+        /// Radius of the circle.
+        radius: f32,
+    },
+
+    /// A line segment with square ends.
+    Line {
+        /// Start point.
+        from: Vec2,
+        /// End point.
+        to: Vec2,
+        /// Thickness of the line.
+        width: f32,
+    },
+}
+
+impl Shape {
+    /// Creates a rectangle.
+    pub fn rect(size: Vec2) -> Self {
+        Self::Rect { size }
+    }
+
+    /// Creates a rectangle with rounded corners.
+    pub fn rounded_rect(size: Vec2, radius: f32) -> Self {
+        Self::RoundedRect { size, radius }
+    }
+
+    /// Creates a circle.
+    pub fn circle(radius: f32) -> Self {
+        Self::Circle { radius }
+    }
+
+    /// Creates a line segment.
+    pub fn line(from: Vec2, to: Vec2, width: f32) -> Self {
+        Self::Line { from, to, width }
+    }
+
+    /// Fills the shape with a color.
+    pub fn filled<'a>(&self, color: impl Tint) -> impl Drawable<'a> {
+        Tinted {
+            drawable: *self,
+            tint: color.to_colorf(),
+        }
+    }
+
+    /// Gets the local bounding box of the shape as (min, max).
+    pub(crate) fn bounds(&self) -> (Vec2, Vec2) {
+        match *self {
+            Shape::Rect { size } | Shape::RoundedRect { size, .. } => (Vec2::ZERO, size),
+            Shape::Circle { radius } => (Vec2::splat(-radius), Vec2::splat(radius)),
+            Shape::Line { from, to, width } => {
+                let pad = Vec2::splat(width * std::f32::consts::FRAC_1_SQRT_2);
+                (from.min(to) - pad, from.max(to) + pad)
+            }
+        }
+    }
+
+    /// Computes the outline of the shape as a convex polygon.
+    ///
+    /// `scale` is the approximate number of target pixels per local unit, used to pick how finely curves are subdivided.
+    pub(crate) fn outline(&self, scale: f32) -> Vec<Vec2> {
+        match *self {
+            Shape::Rect { size } => vec![
+                Vec2::ZERO,
+                Vec2::new(size.x, 0.0),
+                size,
+                Vec2::new(0.0, size.y),
+            ],
+            Shape::RoundedRect { size, radius } => {
+                let radius = radius.clamp(0.0, size.x.min(size.y) * 0.5);
+                if radius == 0.0 {
+                    return Shape::Rect { size }.outline(scale);
+                }
+                let n = arc_segments(radius * scale, std::f32::consts::FRAC_PI_2);
+                [
+                    (
+                        Vec2::new(size.x - radius, radius),
+                        -std::f32::consts::FRAC_PI_2,
+                    ),
+                    (size - radius, 0.0),
+                    (
+                        Vec2::new(radius, size.y - radius),
+                        std::f32::consts::FRAC_PI_2,
+                    ),
+                    (Vec2::splat(radius), std::f32::consts::PI),
+                ]
+                .into_iter()
+                .flat_map(|(center, start)| {
+                    (0..=n).map(move |i| {
+                        center
+                            + Vec2::from_angle(
+                                start + std::f32::consts::FRAC_PI_2 * i as f32 / n as f32,
+                            ) * radius
+                    })
+                })
+                .collect()
+            }
+            Shape::Circle { radius } => {
+                let n = arc_segments(radius * scale, std::f32::consts::TAU);
+                (0..n)
+                    .map(|i| Vec2::from_angle(std::f32::consts::TAU * i as f32 / n as f32) * radius)
+                    .collect()
+            }
+            Shape::Line { from, to, width } => {
+                let dir = (to - from).normalize_or(Vec2::X);
+                let normal = dir.perp() * (width * 0.5);
+                let cap = dir * (width * 0.5);
+                vec![
+                    from - cap + normal,
+                    to + cap + normal,
+                    to + cap - normal,
+                    from - cap - normal,
+                ]
+            }
+        }
+    }
+}
+
+/// Number of segments to approximate an arc of the given pixel radius and angle with.
+fn arc_segments(radius: f32, angle: f32) -> u32 {
+    // Keep the maximum distance between the arc and its chords under a quarter pixel.
+    const TOLERANCE: f32 = 0.25;
+    if !radius.is_finite() || radius <= TOLERANCE {
+        return 1;
+    }
+    let step = 2.0 * (1.0 - TOLERANCE / radius).acos();
+    ((angle / step).ceil() as u32).clamp(1, 256)
+}
+
+/// A shape staged for drawing.
+pub(crate) struct ShapeCommand {
+    pub shape: Shape,
+    pub transform: Affine2,
+    pub tint: Color,
+}
+
+impl<'a> Drawable<'a> for Shape {
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: Affine2) {
+        canvas.push(Command::Shape(ShapeCommand {
+            shape: *self,
+            transform,
+            tint,
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_within_bounds(shape: Shape, scale: f32) {
+        let (min, max) = shape.bounds();
+        for p in shape.outline(scale) {
+            assert!(
+                p.cmpge(min - 1e-3).all() && p.cmple(max + 1e-3).all(),
+                "{p} outside {min}..{max} for {shape:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rect_outline_is_its_corners() {
+        let size = Vec2::new(30.0, 20.0);
+        assert_eq!(
+            Shape::rect(size).outline(1.0),
+            vec![Vec2::ZERO, Vec2::new(30.0, 0.0), size, Vec2::new(0.0, 20.0)]
+        );
+        assert_eq!(
+            Shape::rounded_rect(size, 0.0).outline(1.0),
+            Shape::rect(size).outline(1.0)
+        );
+    }
+
+    #[test]
+    fn circle_is_subdivided_by_scale() {
+        let circle = Shape::circle(10.0);
+        let small = circle.outline(1.0);
+        let large = circle.outline(8.0);
+        assert!(small.len() >= 3);
+        assert!(large.len() > small.len());
+        for p in large {
+            assert!((p.length() - 10.0).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn rounded_rect_has_four_arcs() {
+        let shape = Shape::rounded_rect(Vec2::new(40.0, 20.0), 100.0);
+        let outline = shape.outline(2.0);
+        // Each corner's arc has both of its end points, and the radius is clamped to half the shorter side.
+        assert_eq!(outline.len() % 4, 0);
+        assert!(outline.contains(&Vec2::new(10.0, 0.0)));
+        assert_within_bounds(shape, 2.0);
+    }
+
+    #[test]
+    fn outlines_are_within_bounds() {
+        for scale in [0.5, 1.0, 4.0] {
+            assert_within_bounds(Shape::rect(Vec2::new(5.0, 7.0)), scale);
+            assert_within_bounds(Shape::circle(12.0), scale);
+            assert_within_bounds(Shape::rounded_rect(Vec2::new(9.0, 30.0), 3.0), scale);
+            assert_within_bounds(
+                Shape::line(Vec2::new(-3.0, 4.0), Vec2::new(20.0, -8.0), 3.0),
+                scale,
+            );
+            assert_within_bounds(Shape::line(Vec2::ZERO, Vec2::ZERO, 2.0), scale);
+        }
+    }
+
+    #[test]
+    fn arc_segments_are_clamped() {
+        assert_eq!(arc_segments(f32::NAN, std::f32::consts::TAU), 1);
+        assert_eq!(arc_segments(f32::INFINITY, std::f32::consts::TAU), 1);
+        assert_eq!(arc_segments(1e9, std::f32::consts::TAU), 256);
+    }
+}
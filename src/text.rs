@@ -1,17 +1,49 @@
+use std::collections::HashMap;
+
 use imgref::ImgRef;
 use indexmap::IndexMap;
 
 use crate::atlas::Atlas;
-use crate::{font, Color};
+use crate::{font, multiply_tint, palette, Color};
 
+#[derive(Clone, Copy)]
 pub struct TextSprite {
     pub is_mask: bool,
+    /// Size class of the atlas the glyph is in.
+    pub size_class: usize,
+    /// Page of the size class's atlas the glyph is in.
+    pub page: usize,
     pub offset: glam::IVec2,
     pub size: glam::UVec2,
     pub transform: glam::Affine2,
     pub tint: Color,
 }
 
+/// How glyphs drawn with a transform are rasterized.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Raster {
+    /// Horizontal subpixel offset, in target pixels.
+    pub offset: f32,
+    /// Scale of the glyphs, so glyphs drawn scaled up are rasterized at the size they are drawn at rather than blurrily magnified.
+    pub scale: f32,
+}
+
+impl Raster {
+    /// Glyphs rasterized at their own size, without offset.
+    pub const UNSCALED: Self = Self {
+        offset: 0.0,
+        scale: 1.0,
+    };
+
+    /// Gets the scale a glyph of the given font size is rasterized at, limited so it is no larger than [`MAX_SCALED_FONT_SIZE`].
+    fn glyph_scale(&self, font_size: f32) -> f32 {
+        self.scale.min((MAX_SCALED_FONT_SIZE / font_size).max(1.0))
+    }
+}
+
+/// Font size glyphs are rasterized at most when scaled, past which they are magnified as sprites, so zoomed in text does not fill the atlas.
+const MAX_SCALED_FONT_SIZE: f32 = 256.0;
+
 pub struct Section {
     pub label: Label,
     pub transform: glam::Affine2,
@@ -20,16 +52,38 @@ pub struct Section {
 
 pub struct SpriteMaker {
     swash_cache: cosmic_text::SwashCache,
-    mask_atlas: Atlas<cosmic_text::CacheKey, u8>,
-    color_atlas: Atlas<cosmic_text::CacheKey, rgb::Rgba<u8>>,
+    /// Pages of the atlases for each size class, created on first use, so the color atlases are never allocated for text without color glyphs.
+    mask_atlases: Vec<Vec<Atlas<cosmic_text::CacheKey, u8>>>,
+    color_atlases: Vec<Vec<Atlas<cosmic_text::CacheKey, rgb::Rgba<u8>>>>,
+    atlas_options: crate::GlyphAtlasOptions,
+    /// Whether color glyphs are premultiplied by their alpha when added to the atlas.
+    premultiply: bool,
+    /// Whether glyphs are rasterized at subpixel offsets.
+    subpixel: bool,
+    /// Number of glyphs added to the atlases since the last prepare began.
+    pub rasterized: u32,
 
     draw_count: usize,
     last_draw_at: IndexMap<cosmic_text::CacheKey, usize>,
+    /// Number of pins held on each pinned glyph. Pinned glyphs are never evicted.
+    pins: HashMap<cosmic_text::CacheKey, usize>,
+    /// Glyphs pinned for each label, once per pin, so unpinning releases exactly what was pinned even if the raster has changed since.
+    pinned_labels: HashMap<u64, Vec<Vec<cosmic_text::CacheKey>>>,
 }
 
 /// Text that has been laid out and shaped.
 #[derive(Clone)]
-pub struct Label(cosmic_text::Buffer);
+pub struct Label(
+    cosmic_text::Buffer,
+    /// Identifies the label, and its clones, to the glyphs pinned for it.
+    u64,
+);
+
+/// Allocates an ID for a [`Label`].
+fn next_label_id() -> u64 {
+    static LABEL_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    LABEL_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+}
 
 impl Label {
     /// Creates a new run of text.
@@ -38,29 +92,292 @@ impl Label {
         contents: &str,
         metrics: font::Metrics,
         attrs: font::Attrs,
+    ) -> Self {
+        Self::with_options(font_system, contents, metrics, attrs, Default::default())
+    }
+
+    /// Creates a new paragraph of text, laid out with the given width, alignment and wrapping.
+    pub fn with_options(
+        font_system: &mut cosmic_text::FontSystem,
+        contents: &str,
+        metrics: font::Metrics,
+        attrs: font::Attrs,
+        options: font::TextOptions,
+    ) -> Self {
+        Self::layout(font_system, metrics, options, |font_system, buffer| {
+            buffer.set_text(
+                font_system,
+                contents,
+                attrs.to_cosmic(),
+                cosmic_text::Shaping::Advanced,
+            );
+        })
+    }
+
+    /// Creates a new paragraph of text made of spans with their own attributes and colors, e.g. for highlighting words.
+    ///
+    /// Span colors are multiplied with the tint the label is drawn with. Spans may contain line breaks.
+    pub fn rich(
+        font_system: &mut cosmic_text::FontSystem,
+        spans: &[(&str, font::Attrs, Color)],
+        metrics: font::Metrics,
+        options: font::TextOptions,
+    ) -> Self {
+        Self::layout(font_system, metrics, options, |font_system, buffer| {
+            buffer.set_rich_text(
+                font_system,
+                spans.iter().map(|(text, attrs, color)| {
+                    (
+                        *text,
+                        attrs
+                            .to_cosmic()
+                            .color(cosmic_text::Color::rgba(color.r, color.g, color.b, color.a)),
+                    )
+                }),
+                cosmic_text::Attrs::new(),
+                cosmic_text::Shaping::Advanced,
+            );
+        })
+    }
+
+    /// Measures the size text would have as a label, without keeping the layout around, e.g. to size UI boxes.
+    pub fn measure(
+        font_system: &mut cosmic_text::FontSystem,
+        contents: &str,
+        metrics: font::Metrics,
+        attrs: font::Attrs,
+        options: font::TextOptions,
+    ) -> glam::Vec2 {
+        Self::with_options(font_system, contents, metrics, attrs, options).size()
+    }
+
+    /// Gets the metrics of each laid out line, top to bottom.
+    ///
+    /// A paragraph wrapped over several lines yields one entry per visual line.
+    pub fn lines(&self) -> impl Iterator<Item = font::LineMetrics> + '_ {
+        let layout_lines = self
+            .0
+            .lines
+            .iter()
+            .flat_map(|line| line.layout_opt().iter().flatten());
+        self.0
+            .layout_runs()
+            .zip(layout_lines)
+            .map(|(run, layout_line)| font::LineMetrics {
+                left: run
+                    .glyphs
+                    .iter()
+                    .map(|glyph| glyph.x)
+                    .min_by(f32::total_cmp)
+                    .unwrap_or(0.0),
+                top: run.line_top,
+                baseline: run.line_y,
+                height: run.line_height,
+                width: run.line_w,
+                ascent: layout_line.max_ascent,
+                descent: layout_line.max_descent,
+            })
+    }
+
+    /// Gets the glyph clusters of each laid out line, top to bottom and in visual order within each line, e.g. for hit-testing clicks.
+    pub fn clusters(&self) -> impl Iterator<Item = font::Cluster> + '_ {
+        let paragraph_offsets = self.paragraph_offsets();
+        self.0.layout_runs().enumerate().flat_map(move |(i, run)| {
+            let paragraph_offset = paragraph_offsets[run.line_i];
+            let mut clusters = Vec::<font::Cluster>::new();
+            for glyph in run.glyphs {
+                // Glyphs of a grapheme or ligature share the same range, e.g. a letter and its combining accent.
+                if let Some(cluster) = clusters.last_mut().filter(|cluster| {
+                    cluster.start == paragraph_offset + glyph.start
+                        && cluster.end == paragraph_offset + glyph.end
+                }) {
+                    let min = cluster.offset.x.min(glyph.x);
+                    let max = (cluster.offset.x + cluster.size.x).max(glyph.x + glyph.w);
+                    cluster.offset.x = min;
+                    cluster.size.x = max - min;
+                    continue;
+                }
+                clusters.push(font::Cluster {
+                    start: paragraph_offset + glyph.start,
+                    end: paragraph_offset + glyph.end,
+                    line: i,
+                    offset: glam::Vec2::new(glyph.x, run.line_top),
+                    size: glam::Vec2::new(glyph.w, run.line_height),
+                    rtl: glyph.level.is_rtl(),
+                });
+            }
+            clusters
+        })
+    }
+
+    /// Gets the byte offset of the cursor position nearest to a point, in pixels from the top-left of the label, e.g. for placing the cursor where a click landed.
+    pub fn hit(&self, point: glam::Vec2) -> Option<usize> {
+        let cursor = self.0.hit(point.x, point.y)?;
+        Some(self.paragraph_offsets()[cursor.line] + cursor.index)
+    }
+
+    /// Gets where the cursor is drawn at a byte offset in the label's text.
+    ///
+    /// Returns [`None`] if the offset is past the end of the text, not on a character boundary, or on a line dropped by the layout's height.
+    pub fn cursor_rect(&self, offset: usize) -> Option<font::Caret> {
+        let cursor = self.cursor(offset)?;
+        let mut end = None;
+        for (i, run) in self
+            .0
+            .layout_runs()
+            .enumerate()
+            .filter(|(_, run)| run.line_i == cursor.line)
+        {
+            let caret = |x| font::Caret {
+                offset: glam::Vec2::new(x, run.line_top),
+                height: run.line_height,
+                line: i,
+            };
+            let Some(last) = run.glyphs.last() else {
+                return Some(caret(0.0));
+            };
+            for glyph in run.glyphs {
+                if (glyph.start..glyph.end).contains(&cursor.index) {
+                    // Cursors within a ligature are placed proportionally to their byte offset.
+                    let t = (cursor.index - glyph.start) as f32 / (glyph.end - glyph.start) as f32;
+                    let t = if glyph.level.is_rtl() { 1.0 - t } else { t };
+                    return Some(caret(glyph.x + glyph.w * t));
+                }
+            }
+            // The end of a wrapped line is the start of the next, so the end of the paragraph is only used if no later line has the cursor.
+            if cursor.index == last.end {
+                let x = if last.level.is_rtl() {
+                    last.x
+                } else {
+                    last.x + last.w
+                };
+                end = Some(caret(x));
+            }
+        }
+        end
+    }
+
+    /// Gets the highlights of each line covered by a byte range of the label's text, e.g. for drawing a selection.
+    pub fn selection(&self, range: std::ops::Range<usize>) -> Vec<font::Highlight> {
+        let (Some(start), Some(end)) = (self.cursor(range.start), self.cursor(range.end)) else {
+            return vec![];
+        };
+        self.0
+            .layout_runs()
+            .enumerate()
+            .filter_map(|(i, run)| {
+                let (x, width) = run.highlight(start, end)?;
+                (width > 0.0).then(|| font::Highlight {
+                    line: i,
+                    offset: glam::Vec2::new(x, run.line_top),
+                    size: glam::Vec2::new(width, run.line_height),
+                })
+            })
+            .collect()
+    }
+
+    /// Gets the byte offset of each paragraph in the label's text, as glyphs and cursors index into the text of their paragraph.
+    fn paragraph_offsets(&self) -> Vec<usize> {
+        self.0
+            .lines
+            .iter()
+            .scan(0, |offset, line| {
+                let start = *offset;
+                *offset += line.text().len() + line.ending().as_str().len();
+                Some(start)
+            })
+            .collect()
+    }
+
+    /// Gets the cursor at a byte offset in the label's text.
+    fn cursor(&self, offset: usize) -> Option<cosmic_text::Cursor> {
+        let mut start = 0;
+        for (i, line) in self.0.lines.iter().enumerate() {
+            let text = line.text();
+            if offset < start {
+                // The offset is within the previous line's ending.
+                return None;
+            }
+            if offset <= start + text.len() {
+                return text
+                    .is_char_boundary(offset - start)
+                    .then(|| cosmic_text::Cursor::new(i, offset - start));
+            }
+            start += text.len() + line.ending().as_str().len();
+        }
+        None
+    }
+
+    /// Gets the corners of the label's [`Label::size`] under a transform, clockwise from the top-left, e.g. for the bounds of rotated text.
+    pub fn corners(&self, transform: glam::Affine2) -> [glam::Vec2; 4] {
+        let size = self.size();
+        [
+            glam::Vec2::ZERO,
+            glam::Vec2::new(size.x, 0.0),
+            size,
+            glam::Vec2::new(0.0, size.y),
+        ]
+        .map(|corner| transform.transform_point2(corner))
+    }
+
+    fn layout(
+        font_system: &mut cosmic_text::FontSystem,
+        metrics: font::Metrics,
+        options: font::TextOptions,
+        set_text: impl FnOnce(&mut cosmic_text::FontSystem, &mut cosmic_text::Buffer),
     ) -> Self {
         let mut buffer = cosmic_text::Buffer::new(font_system, metrics);
-        buffer.set_text(
-            font_system,
-            contents,
-            cosmic_text::Attrs::new()
-                .family(attrs.family.as_family())
-                .stretch(attrs.stretch)
-                .style(attrs.style)
-                .weight(attrs.weight),
-            cosmic_text::Shaping::Advanced,
-        );
-        Self(buffer)
+        buffer.set_wrap(font_system, options.wrap);
+        buffer.set_size(font_system, options.width, options.height);
+        set_text(font_system, &mut buffer);
+        if options.align.is_some() {
+            for line in buffer.lines.iter_mut() {
+                line.set_align(options.align);
+            }
+            buffer.shape_until_scroll(font_system, false);
+        }
+        Self(buffer, next_label_id())
+    }
+
+    /// Gets the keys of the glyphs the label is drawn with at a raster, at every subpixel offset if `subpixel` is set and the glyphs are unscaled.
+    fn cache_keys(
+        &self,
+        raster: Raster,
+        subpixel: bool,
+    ) -> impl Iterator<Item = cosmic_text::CacheKey> + '_ {
+        let offsets: &[f32] = if subpixel && raster.scale == 1.0 {
+            &[0.0, 0.25, 0.5, 0.75]
+        } else {
+            &[0.0]
+        };
+        self.0.layout_runs().flat_map(move |run| {
+            let line_y = run.line_y;
+            run.glyphs.iter().flat_map(move |glyph| {
+                let scale = raster.glyph_scale(glyph.font_size);
+                offsets
+                    .iter()
+                    .map(move |offset| place_glyph(line_y, glyph, *offset, scale).0)
+            })
+        })
+    }
+
+    /// Whether the label has no glyphs to draw.
+    pub fn is_empty(&self) -> bool {
+        self.0.layout_runs().all(|run| run.glyphs.is_empty())
     }
 
     /// Computes the size of the text.
+    ///
+    /// If the text was laid out with a width, that width is used, so that aligned lines stay within the bounds.
     pub fn size(&self) -> glam::Vec2 {
         glam::Vec2::new(
-            self.0
-                .layout_runs()
-                .map(|run| run.line_w)
-                .max_by(f32::total_cmp)
-                .unwrap_or(0.0),
+            self.0.size().0.unwrap_or_else(|| {
+                self.0
+                    .layout_runs()
+                    .map(|run| run.line_w)
+                    .max_by(f32::total_cmp)
+                    .unwrap_or(0.0)
+            }),
             self.0
                 .layout_runs()
                 .last()
@@ -70,23 +387,180 @@ impl Label {
     }
 }
 
+/// Gets the cache key of a glyph rasterized at a horizontal subpixel offset and a scale, and its position relative to the label in scaled pixels.
+///
+/// The position is the glyph's pixel minus the offset, so the glyph lands on a whole pixel once drawn with a transform translated by the offset.
+fn place_glyph(
+    line_y: f32,
+    glyph: &cosmic_text::LayoutGlyph,
+    offset: f32,
+    scale: f32,
+) -> (cosmic_text::CacheKey, glam::Vec2) {
+    let physical_glyph = glyph.physical((offset, 0.), scale);
+    (
+        physical_glyph.cache_key,
+        glam::Vec2::new(
+            physical_glyph.x as f32 - offset,
+            physical_glyph.y as f32 + line_y * scale,
+        ),
+    )
+}
+
+fn new_atlas<Pixel>(
+    device: &wgpu::Device,
+    options: &crate::GlyphAtlasOptions,
+) -> Atlas<cosmic_text::CacheKey, Pixel>
+where
+    Pixel: Clone + bytemuck::NoUninit + crate::atlas::HasTextureFormat,
+{
+    let label = match Pixel::texture_format() {
+        wgpu::TextureFormat::R8Unorm => "canvasette: glyph_mask_atlas",
+        _ => "canvasette: glyph_color_atlas",
+    };
+    Atlas::new(
+        device,
+        label,
+        wgpu::Extent3d {
+            width: options.initial_size,
+            height: options.initial_size,
+            depth_or_array_layers: 1,
+        },
+        options.max_size,
+        etagere::AllocatorOptions {
+            alignment: etagere::size2(options.alignment.x as i32, options.alignment.y as i32),
+            vertical_shelves: false,
+            num_columns: options.columns as i32,
+        },
+    )
+}
+
+/// Finds a glyph in the pages of an atlas.
+fn find<Pixel>(
+    pages: &[Atlas<cosmic_text::CacheKey, Pixel>],
+    key: cosmic_text::CacheKey,
+) -> Option<(usize, etagere::Allocation)>
+where
+    Pixel: Clone + bytemuck::NoUninit + crate::atlas::HasTextureFormat,
+{
+    pages
+        .iter()
+        .enumerate()
+        .find_map(|(page, atlas)| Some((page, atlas.get(key)?)))
+}
+
+/// Finds a glyph in the pages of an atlas, or adds it to the first page with room for it, adding a page if none has.
+///
+/// Returns the page and allocation of the glyph, or [`None`] if it does not fit even into an empty page.
+fn find_or_add<Pixel>(
+    pages: &mut Vec<Atlas<cosmic_text::CacheKey, Pixel>>,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    options: &crate::GlyphAtlasOptions,
+    key: cosmic_text::CacheKey,
+    img: ImgRef<Pixel>,
+    added: &mut u32,
+) -> Option<(usize, etagere::Allocation)>
+where
+    Pixel: Clone + bytemuck::NoUninit + crate::atlas::HasTextureFormat,
+{
+    if let Some(found) = find(pages, key) {
+        return Some(found);
+    }
+    *added += 1;
+    if let Some(added) = pages
+        .iter_mut()
+        .enumerate()
+        .find_map(|(page, atlas)| Some((page, atlas.add(device, queue, key, img)?)))
+    {
+        return Some(added);
+    }
+    let mut atlas = new_atlas(device, options);
+    let allocation = atlas.add(device, queue, key, img)?;
+    pages.push(atlas);
+    Some((pages.len() - 1, allocation))
+}
+
 impl SpriteMaker {
-    pub fn new(device: &wgpu::Device) -> Self {
+    pub fn new(atlas_options: crate::GlyphAtlasOptions, premultiply: bool, subpixel: bool) -> Self {
         Self {
             swash_cache: cosmic_text::SwashCache::new(),
-            mask_atlas: Atlas::new(device),
-            color_atlas: Atlas::new(device),
+            mask_atlases: (0..=atlas_options.size_classes.len())
+                .map(|_| vec![])
+                .collect(),
+            color_atlases: (0..=atlas_options.size_classes.len())
+                .map(|_| vec![])
+                .collect(),
+            atlas_options,
+            premultiply,
+            subpixel,
+            rasterized: 0,
             draw_count: 0,
             last_draw_at: IndexMap::new(),
+            pins: HashMap::new(),
+            pinned_labels: HashMap::new(),
         }
     }
 
-    pub fn mask_texture(&self) -> &wgpu::Texture {
-        self.mask_atlas.texture()
+    /// Gets how glyphs drawn with a transform are rasterized.
+    ///
+    /// Glyphs are rasterized at the transform's scale, rounded to eighths of an octave so text being zoomed does not rasterize every glyph every frame. Only glyphs drawn without rotation or scaling are offset by subpixels, as glyphs that are resampled anyway gain nothing from it.
+    pub fn raster(&self, transform: glam::Affine2) -> Raster {
+        if transform.matrix2 == glam::Mat2::IDENTITY {
+            return Raster {
+                offset: if self.subpixel {
+                    transform.translation.x - transform.translation.x.floor()
+                } else {
+                    0.0
+                },
+                scale: 1.0,
+            };
+        }
+        let scale = transform.matrix2.determinant().abs().sqrt();
+        if !scale.is_normal() {
+            return Raster::UNSCALED;
+        }
+        Raster {
+            offset: 0.0,
+            scale: ((scale.log2() * 8.0).round() / 8.0).exp2(),
+        }
     }
 
-    pub fn color_texture(&self) -> &wgpu::Texture {
-        self.color_atlas.texture()
+    pub fn mask_texture(&self, size_class: usize, page: usize) -> Option<&wgpu::Texture> {
+        self.mask_atlases
+            .get(size_class)?
+            .get(page)
+            .map(|atlas| atlas.texture())
+    }
+
+    pub fn color_texture(&self, size_class: usize, page: usize) -> Option<&wgpu::Texture> {
+        self.color_atlases
+            .get(size_class)?
+            .get(page)
+            .map(|atlas| atlas.texture())
+    }
+
+    pub fn describe_atlases(
+        &self,
+    ) -> impl Iterator<Item = crate::debug::AtlasPageDescription> + '_ {
+        let mask = self
+            .mask_atlases
+            .iter()
+            .enumerate()
+            .flat_map(|(size_class, pages)| {
+                pages.iter().enumerate().map(move |(page, atlas)| {
+                    atlas.describe(crate::debug::AtlasKind::GlyphMask, size_class, page)
+                })
+            });
+        let color = self
+            .color_atlases
+            .iter()
+            .enumerate()
+            .flat_map(|(size_class, pages)| {
+                pages.iter().enumerate().map(move |(page, atlas)| {
+                    atlas.describe(crate::debug::AtlasKind::GlyphColor, size_class, page)
+                })
+            });
+        mask.chain(color)
     }
 
     pub fn make(
@@ -96,83 +570,103 @@ impl SpriteMaker {
         font_system: &mut cosmic_text::FontSystem,
         label: &Label,
         color: Color,
+        raster: Raster,
     ) -> Option<Vec<TextSprite>> {
         let mut text_sprites = vec![];
 
         for run in label.0.layout_runs() {
             for glyph in run.glyphs.iter() {
-                let physical_glyph = glyph.physical((0., 0.), 1.0);
-                let Some(image) = self
-                    .swash_cache
-                    .get_image(font_system, physical_glyph.cache_key)
-                    .as_ref()
+                let scale = raster.glyph_scale(glyph.font_size);
+                let (cache_key, position) = place_glyph(run.line_y, glyph, raster.offset, scale);
+                let Some(image) = self.swash_cache.get_image(font_system, cache_key).as_ref()
                 else {
                     continue;
                 };
 
                 self.last_draw_at
-                    .insert_before(0, physical_glyph.cache_key, self.draw_count);
+                    .insert_before(0, cache_key, self.draw_count);
 
                 if image.placement.width == 0 || image.placement.height == 0 {
                     continue;
                 }
 
-                let (is_mask, allocation, tint) = match image.content {
+                let size_class = self
+                    .atlas_options
+                    .size_classes
+                    .partition_point(|max| image.placement.height > *max);
+
+                let (is_mask, (page, allocation), tint) = match image.content {
                     cosmic_text::SwashContent::Mask | cosmic_text::SwashContent::SubpixelMask => (
                         true,
-                        if let Some(allocation) = self.mask_atlas.get(physical_glyph.cache_key) {
-                            allocation
-                        } else {
-                            self.mask_atlas.add(
-                                device,
-                                queue,
-                                physical_glyph.cache_key,
-                                ImgRef::new(
-                                    bytemuck::cast_slice(&image.data),
-                                    image.placement.width as usize,
-                                    image.placement.height as usize,
-                                ),
-                            )?
-                        },
+                        find_or_add(
+                            &mut self.mask_atlases[size_class],
+                            device,
+                            queue,
+                            &self.atlas_options,
+                            cache_key,
+                            ImgRef::new(
+                                bytemuck::cast_slice(&image.data),
+                                image.placement.width as usize,
+                                image.placement.height as usize,
+                            ),
+                            &mut self.rasterized,
+                        )?,
                         glyph
                             .color_opt
-                            .map(|v| Color::new(v.r(), v.g(), v.b(), v.a()))
+                            .map(|v| multiply_tint(color, Color::new(v.r(), v.g(), v.b(), v.a())))
                             .unwrap_or(color),
                     ),
-                    cosmic_text::SwashContent::Color => (
-                        false,
-                        if let Some(allocation) = self.color_atlas.get(physical_glyph.cache_key) {
-                            allocation
-                        } else {
-                            self.color_atlas.add(
+                    cosmic_text::SwashContent::Color => {
+                        // Glyphs are only premultiplied when they are first added.
+                        let premultiplied = (self.premultiply
+                            && find(&self.color_atlases[size_class], cache_key).is_none())
+                        .then(|| {
+                            crate::upload::premultiply(
+                                wgpu::TextureFormat::Rgba8UnormSrgb,
+                                &image.data,
+                            )
+                        })
+                        .flatten();
+                        (
+                            false,
+                            find_or_add(
+                                &mut self.color_atlases[size_class],
                                 device,
                                 queue,
-                                physical_glyph.cache_key,
+                                &self.atlas_options,
+                                cache_key,
                                 ImgRef::new(
-                                    bytemuck::cast_slice(&image.data),
+                                    bytemuck::cast_slice(
+                                        premultiplied.as_deref().unwrap_or(&image.data),
+                                    ),
                                     image.placement.width as usize,
                                     image.placement.height as usize,
                                 ),
-                            )?
-                        },
-                        Color::new(0xff, 0xff, 0xff, 0xff),
-                    ),
+                                &mut self.rasterized,
+                            )?,
+                            palette::WHITE,
+                        )
+                    }
                 };
 
                 text_sprites.push(TextSprite {
                     is_mask,
+                    size_class,
+                    page,
                     offset: glam::IVec2::new(
                         allocation.rectangle.min.x,
                         allocation.rectangle.min.y,
                     ),
-                    size: glam::UVec2::new(
-                        allocation.rectangle.width() as u32,
-                        allocation.rectangle.height() as u32,
-                    ),
-                    transform: glam::Affine2::from_translation(glam::Vec2::new(
-                        physical_glyph.x as f32 + image.placement.left as f32,
-                        physical_glyph.y as f32 + run.line_top - image.placement.top as f32,
-                    )),
+                    // Allocations may be padded for alignment, so only the glyph itself is drawn.
+                    size: glam::UVec2::new(image.placement.width, image.placement.height),
+                    transform: glam::Affine2::from_scale(glam::Vec2::splat(scale.recip()))
+                        * glam::Affine2::from_translation(
+                            position
+                                + glam::Vec2::new(
+                                    image.placement.left as f32,
+                                    -image.placement.top as f32,
+                                ),
+                        ),
                     tint,
                 })
             }
@@ -181,6 +675,39 @@ impl SpriteMaker {
         Some(text_sprites)
     }
 
+    /// Pins the glyphs of a label drawn at a raster, so they stay in the atlases until unpinned.
+    pub fn pin(&mut self, label: &Label, raster: Raster) {
+        let keys = label.cache_keys(raster, self.subpixel).collect::<Vec<_>>();
+        for key in keys.iter() {
+            *self.pins.entry(*key).or_default() += 1;
+        }
+        self.pinned_labels.entry(label.1).or_default().push(keys);
+    }
+
+    /// Releases the latest pin taken on a label, or one of its clones, with [`SpriteMaker::pin`].
+    pub fn unpin(&mut self, label: &Label) {
+        let Some(pinned) = self.pinned_labels.get_mut(&label.1) else {
+            return;
+        };
+        let keys = pinned.pop().unwrap_or_default();
+        if pinned.is_empty() {
+            self.pinned_labels.remove(&label.1);
+        }
+        for key in keys {
+            let Some(count) = self.pins.get_mut(&key) else {
+                continue;
+            };
+            *count -= 1;
+            if *count == 0 {
+                self.pins.remove(&key);
+                // Evicting skipped the glyph while it was pinned, so start its age over.
+                if !self.last_draw_at.contains_key(&key) {
+                    self.last_draw_at.insert_before(0, key, self.draw_count);
+                }
+            }
+        }
+    }
+
     fn remove_unused(&mut self, queue: &wgpu::Queue) {
         const MAX_CACHE_AGE: usize = 100;
 
@@ -205,8 +732,15 @@ impl SpriteMaker {
         };
 
         for (k, _) in self.last_draw_at.drain(i..) {
-            self.color_atlas.remove(queue, &k);
-            self.mask_atlas.remove(queue, &k);
+            if self.pins.contains_key(&k) {
+                continue;
+            }
+            for atlas in self.color_atlases.iter_mut().flatten() {
+                atlas.remove(queue, &k);
+            }
+            for atlas in self.mask_atlases.iter_mut().flatten() {
+                atlas.remove(queue, &k);
+            }
         }
     }
 
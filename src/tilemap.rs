@@ -0,0 +1,291 @@
+//! Grids of tiles drawn from a tileset.
+//!
+//! A [`Tilemap`] is drawn as a single item, and only the tiles that overlap the target are generated when the canvas is prepared, so large maps cost little more than the part of them on screen.
+//!
+//! Zoomed far out, even the visible part can be many thousands of tiles. A tilemap with [`LodOptions`] is then drawn as a few chunks instead, each a single quad of a downsampled texture of its tiles. The renderer generates and caches these textures like a mip chain: level 1 of a chunk is rendered from its tiles at half size, and every level after that from the previous one. A chunk's levels are rendered again when its cells change, and dropped once it has not been drawn for a while.
+
+use glam::*;
+
+use crate::{Canvas, Color, Command, Drawable, Texture, TextureSlice};
+
+/// A grid of tiles, each picked by index from a tileset.
+///
+/// Tiles in the tileset are numbered left to right, then top to bottom, starting from 0. Tiles outside the tileset and empty cells are not drawn. The tileset's flipping is ignored.
+pub struct Tilemap<'a, T> {
+    tileset: TextureSlice<'a, T>,
+    tile_size: UVec2,
+    width: u32,
+    tiles: Vec<Option<u32>>,
+    lod: Option<LodOptions>,
+    /// Identifies the map to the chunk textures cached for it.
+    id: u64,
+}
+
+impl<'a, T> Clone for Tilemap<'a, T> {
+    fn clone(&self) -> Self {
+        Self {
+            tileset: self.tileset,
+            tile_size: self.tile_size,
+            width: self.width,
+            tiles: self.tiles.clone(),
+            lod: self.lod,
+            id: next_tilemap_id(),
+        }
+    }
+}
+
+/// Allocates an ID for a [`Tilemap`].
+fn next_tilemap_id() -> u64 {
+    static TILEMAP_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    TILEMAP_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+}
+
+/// How a tilemap is drawn when zoomed out, see [`Tilemap::set_lod`].
+///
+/// The level is picked from the scale the map is drawn at in target pixels, as [`crate::camera::Camera::lod`] picks it from the zoom: level 1 once the map is drawn at half size or smaller, level 2 at a quarter, and so on. Maps drawn with a palette, a material or a blend mode other than alpha blending are always drawn as tiles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LodOptions {
+    /// Number of cells along each side of a chunk.
+    pub chunk_size: u32,
+
+    /// Coarsest level to draw chunks at. Level 0 draws the tiles themselves.
+    pub max_level: u32,
+}
+
+impl Default for LodOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: 16,
+            max_level: 4,
+        }
+    }
+}
+
+impl<'a, T> Tilemap<'a, T>
+where
+    T: Texture,
+{
+    /// Creates a new tilemap of `width` by `height` empty cells.
+    ///
+    /// Returns [`None`] if the tile size is zero along either axis or larger than the tileset.
+    pub fn new(
+        tileset: TextureSlice<'a, T>,
+        tile_size: UVec2,
+        width: u32,
+        height: u32,
+    ) -> Option<Self> {
+        if tile_size.cmpeq(UVec2::ZERO).any() || tile_size.cmpgt(tileset.size()).any() {
+            return None;
+        }
+        Some(Self {
+            tileset,
+            tile_size,
+            width,
+            tiles: vec![None; width as usize * height as usize],
+            lod: None,
+            id: next_tilemap_id(),
+        })
+    }
+
+    /// Gets the size of each tile, in texels.
+    pub fn tile_size(&self) -> UVec2 {
+        self.tile_size
+    }
+
+    /// Gets the width of the map, in cells.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Gets the height of the map, in cells.
+    pub fn height(&self) -> u32 {
+        self.tiles
+            .len()
+            .checked_div(self.width as usize)
+            .unwrap_or(0) as u32
+    }
+
+    /// Gets the tile in a cell, or [`None`] if the cell is empty or out of bounds.
+    pub fn get(&self, x: u32, y: u32) -> Option<u32> {
+        if x >= self.width {
+            return None;
+        }
+        *self
+            .tiles
+            .get(y as usize * self.width as usize + x as usize)?
+    }
+
+    /// Sets the tile in a cell. Does nothing if the cell is out of bounds.
+    pub fn set(&mut self, x: u32, y: u32, tile: Option<u32>) {
+        if x >= self.width {
+            return;
+        }
+        if let Some(cell) = self
+            .tiles
+            .get_mut(y as usize * self.width as usize + x as usize)
+        {
+            *cell = tile;
+        }
+    }
+
+    /// Gets the cells of the map, row by row.
+    pub fn tiles(&self) -> &[Option<u32>] {
+        &self.tiles
+    }
+
+    /// Gets the cells of the map mutably, row by row.
+    pub fn tiles_mut(&mut self) -> &mut [Option<u32>] {
+        &mut self.tiles
+    }
+
+    /// Gets how the map is drawn when zoomed out, or [`None`] if it is always drawn as tiles.
+    pub fn lod(&self) -> Option<LodOptions> {
+        self.lod
+    }
+
+    /// Sets how the map is drawn when zoomed out, or [`None`] to always draw it as tiles.
+    ///
+    /// Chunks are only rendered again when their cells change, so if the tileset's pixels change, call [`crate::Renderer::clear_tilemap_chunks`].
+    pub fn set_lod(&mut self, lod: Option<LodOptions>) {
+        self.lod = lod.filter(|lod| lod.chunk_size > 0);
+    }
+}
+
+/// A tilemap staged for drawing.
+#[derive(Clone, Copy)]
+pub(crate) struct TilemapCommand<'a> {
+    pub texture: &'a dyn Texture,
+    pub src_offset: IVec2,
+    pub src_layer: u32,
+    /// Number of tiles in the tileset along each axis.
+    pub tileset_size: UVec2,
+    pub tile_size: UVec2,
+    pub width: u32,
+    pub tiles: &'a [Option<u32>],
+    pub lod: Option<LodOptions>,
+    pub id: u64,
+    pub transform: Affine2,
+    pub tint: Color,
+}
+
+impl<'a> TilemapCommand<'a> {
+    /// Gets the local bounding box of the map as (min, max).
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        let height = self
+            .tiles
+            .len()
+            .checked_div(self.width as usize)
+            .unwrap_or(0) as u32;
+        (
+            Vec2::ZERO,
+            (UVec2::new(self.width, height) * self.tile_size).as_vec2(),
+        )
+    }
+
+    /// Gets the range of blocks of `cells` by `cells` cells overlapping a rectangle in target space, given the final transform of the map.
+    fn visible_blocks(
+        &self,
+        transform: Affine2,
+        min: Vec2,
+        max: Vec2,
+        cells: u32,
+    ) -> (UVec2, UVec2) {
+        let crate::geom::Rectf {
+            min: local_min,
+            max: local_max,
+        } = crate::geom::Rectf::new(min, max).transform(transform.inverse());
+        let (_, size) = self.bounds();
+        let block_size = (self.tile_size * cells).as_vec2();
+        (
+            (local_min.max(Vec2::ZERO) / block_size).floor().as_uvec2(),
+            (local_max.min(size) / block_size).ceil().as_uvec2(),
+        )
+    }
+
+    /// Gets the non-empty cells overlapping a rectangle in target space, as (cell, source offset of its tile), given the final transform of the map.
+    pub fn visible_tiles(
+        &self,
+        transform: Affine2,
+        min: Vec2,
+        max: Vec2,
+    ) -> impl Iterator<Item = (UVec2, IVec2)> + '_ {
+        let (first, last) = self.visible_blocks(transform, min, max, 1);
+        self.tiles_in(first, last)
+    }
+
+    /// Gets the chunks of `chunk_size` by `chunk_size` cells overlapping a rectangle in target space, given the final transform of the map.
+    pub fn visible_chunks(
+        &self,
+        transform: Affine2,
+        min: Vec2,
+        max: Vec2,
+        chunk_size: u32,
+    ) -> impl Iterator<Item = UVec2> {
+        let (first, last) = self.visible_blocks(transform, min, max, chunk_size);
+        (first.y..last.y).flat_map(move |y| (first.x..last.x).map(move |x| UVec2::new(x, y)))
+    }
+
+    /// Gets the range of cells in a chunk of `chunk_size` by `chunk_size` cells, clamped to the map.
+    pub fn chunk_cells(&self, chunk: UVec2, chunk_size: u32) -> (UVec2, UVec2) {
+        let (_, size) = self.bounds();
+        let cells = (size / self.tile_size.as_vec2()).as_uvec2();
+        (
+            (chunk * chunk_size).min(cells),
+            ((chunk + 1) * chunk_size).min(cells),
+        )
+    }
+
+    /// Gets the level a chunked map is drawn at, given its final transform, or 0 to draw it as tiles.
+    pub fn lod_level(&self, transform: Affine2) -> u32 {
+        self.lod.map_or(0, |lod| {
+            crate::camera::lod_level(transform.matrix2.determinant().abs().sqrt(), lod.max_level)
+        })
+    }
+
+    /// Gets the non-empty cells in a range, as (cell, source offset of its tile).
+    pub fn tiles_in(&self, first: UVec2, last: UVec2) -> impl Iterator<Item = (UVec2, IVec2)> + '_ {
+        (first.y..last.y)
+            .flat_map(move |y| (first.x..last.x).map(move |x| UVec2::new(x, y)))
+            .filter_map(move |cell| {
+                let tile = self.tiles[(cell.y * self.width + cell.x) as usize]?;
+                let tile = UVec2::new(tile % self.tileset_size.x, tile / self.tileset_size.x);
+                (tile.y < self.tileset_size.y)
+                    .then(|| (cell, self.src_offset + (tile * self.tile_size).as_ivec2()))
+            })
+    }
+}
+
+impl<'a, T> Drawable<'a> for &'a Tilemap<'a, T>
+where
+    T: Texture,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: Affine2) {
+        canvas.push(Command::Tilemap(TilemapCommand {
+            texture: self.tileset.texture,
+            src_offset: self.tileset.rect.offset,
+            src_layer: self.tileset.layer,
+            tileset_size: self.tileset.size() / self.tile_size,
+            tile_size: self.tile_size,
+            width: self.width,
+            tiles: &self.tiles,
+            lod: self.lod,
+            id: self.id,
+            transform,
+            tint,
+        }));
+    }
+}
+
+/// Downsampled levels of a chunk of a tilemap, cached by the renderer.
+pub(crate) struct Chunk {
+    /// Cells the levels were rendered from, to tell when they have changed.
+    pub tiles: Vec<Option<u32>>,
+    /// Whether every cell is empty, so there is nothing to render or draw.
+    pub empty: bool,
+    /// Targets of levels 1 and up, in order.
+    pub levels: Vec<crate::RenderTarget>,
+    /// Number of levels that are up to date with the cells.
+    pub valid: usize,
+    /// Number of the last scene prepared with the chunk.
+    pub last_used: u64,
+}
@@ -0,0 +1,171 @@
+//! Trails: ribbons that follow a moving point, such as sword swipes or projectile trails.
+//!
+//! A [`Trail`] keeps a rolling list of recently visited points, each of which ages until it reaches the trail's lifetime and is dropped. The points are drawn as a single textured ribbon whose width and opacity follow curves over each point's lifetime, so the trail tapers and fades towards its tail.
+
+use std::collections::VecDeque;
+
+use glam::*;
+
+use crate::{
+    mesh::{MeshCommand, MeshVertex},
+    palette, Canvas, Color, Command, Drawable, Texture, TextureSlice,
+};
+
+/// A point along a [`Trail`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrailPoint {
+    /// Position of the point.
+    pub position: Vec2,
+    /// Time since the point was added.
+    pub age: f32,
+}
+
+/// A tapered, textured ribbon following a rolling list of points.
+///
+/// The texture is stretched along the length of the ribbon, with its left edge at the head (the newest point) and its right edge at the tail.
+pub struct Trail<'a, T> {
+    slice: TextureSlice<'a, T>,
+    points: VecDeque<TrailPoint>,
+    lifetime: f32,
+    width: f32,
+    width_curve: fn(f32) -> f32,
+    alpha_curve: fn(f32) -> f32,
+}
+
+impl<'a, T> Clone for Trail<'a, T> {
+    fn clone(&self) -> Self {
+        Self {
+            slice: self.slice,
+            points: self.points.clone(),
+            lifetime: self.lifetime,
+            width: self.width,
+            width_curve: self.width_curve,
+            alpha_curve: self.alpha_curve,
+        }
+    }
+}
+
+impl<'a, T> Trail<'a, T>
+where
+    T: Texture,
+{
+    /// Creates an empty trail whose points last for `lifetime` and whose ribbon is `width` wide at the head.
+    ///
+    /// By default, both the width and the opacity fall off linearly to 0 over a point's lifetime.
+    pub fn new(slice: TextureSlice<'a, T>, lifetime: f32, width: f32) -> Self {
+        Self {
+            slice,
+            points: VecDeque::new(),
+            lifetime,
+            width,
+            width_curve: |t| 1.0 - t,
+            alpha_curve: |t| 1.0 - t,
+        }
+    }
+
+    /// Sets the curve the width is scaled by, as a function of a point's age divided by the lifetime.
+    pub fn set_width_curve(&mut self, curve: fn(f32) -> f32) {
+        self.width_curve = curve;
+    }
+
+    /// Sets the curve the opacity is scaled by, as a function of a point's age divided by the lifetime.
+    pub fn set_alpha_curve(&mut self, curve: fn(f32) -> f32) {
+        self.alpha_curve = curve;
+    }
+
+    /// Adds a new point at the head of the trail.
+    pub fn push(&mut self, position: Vec2) {
+        self.points.push_back(TrailPoint { position, age: 0.0 });
+    }
+
+    /// Ages all points by `dt`, dropping those that have outlived the lifetime.
+    pub fn update(&mut self, dt: f32) {
+        for point in self.points.iter_mut() {
+            point.age += dt;
+        }
+        while self
+            .points
+            .front()
+            .is_some_and(|point| point.age >= self.lifetime)
+        {
+            self.points.pop_front();
+        }
+    }
+
+    /// Removes all points.
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    /// Number of points currently held.
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Whether there are no points.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Iterates over points from the tail (oldest) to the head (newest).
+    pub fn iter(&self) -> impl Iterator<Item = &TrailPoint> {
+        self.points.iter()
+    }
+}
+
+impl<'a, T> Drawable<'a> for Trail<'a, T>
+where
+    T: Texture,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: Affine2) {
+        let n = self.points.len();
+        if n < 2 {
+            return;
+        }
+
+        let src_size = self.slice.rect.size.as_vec2();
+
+        let mut vertices = Vec::with_capacity(n * 2);
+        for (i, point) in self.points.iter().enumerate() {
+            let prev = self.points[i.saturating_sub(1)].position;
+            let next = self.points[(i + 1).min(n - 1)].position;
+            let normal = (next - prev).normalize_or_zero().perp();
+
+            let t = if self.lifetime > 0.0 {
+                (point.age / self.lifetime).clamp(0.0, 1.0)
+            } else {
+                1.0
+            };
+            let half_width = self.width * (self.width_curve)(t).max(0.0) * 0.5;
+            let alpha = (self.alpha_curve)(t).clamp(0.0, 1.0);
+            let tint = palette::with_alpha(palette::WHITE, (alpha * 255.0).round() as u8);
+
+            // Head is at the left edge of the slice, tail at the right edge.
+            let u = src_size.x * (n - 1 - i) as f32 / (n - 1) as f32;
+            vertices.push(MeshVertex {
+                position: point.position + normal * half_width,
+                tex_coords: self.slice.tex_coords(Vec2::new(u, 0.0)),
+                tint,
+            });
+            vertices.push(MeshVertex {
+                position: point.position - normal * half_width,
+                tex_coords: self.slice.tex_coords(Vec2::new(u, src_size.y)),
+                tint,
+            });
+        }
+
+        canvas.push(Command::Mesh(MeshCommand {
+            texture: Some(self.slice.texture),
+            src_layer: self.slice.layer,
+            vertices,
+            indices: (0..n as u32 - 1)
+                .flat_map(|i| {
+                    let a = i * 2;
+                    [a, a + 1, a + 2, a + 1, a + 3, a + 2]
+                })
+                .collect(),
+            transform,
+            tint,
+        }));
+    }
+}
@@ -0,0 +1,149 @@
+//! Loading supercompressed textures, e.g. Basis Universal `.basis` or KTX2 files, in the best format the GPU supports.
+//!
+//! canvasette does not include a transcoder itself. Implement [`Transcoder`] with one, e.g. from the `basis-universal` crate, and load textures with [`transcode`], which picks the transcoding [`Target`] from the device's features.
+
+use crate::Image;
+
+/// A GPU format supercompressed textures can be transcoded to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Target {
+    /// BC7, supported by most desktop GPUs.
+    Bc7,
+    /// ASTC with 4x4 blocks, supported by most mobile GPUs.
+    Astc4x4,
+    /// ETC2 with alpha, supported by most mobile GPUs and WebGL 2.
+    Etc2Rgba8,
+    /// Uncompressed RGBA, supported everywhere at four times the memory of the others.
+    Rgba8,
+}
+
+impl Target {
+    /// Gets the best target for a texture of the given size, given the device's features.
+    ///
+    /// Compressed targets are only picked if the width and height are multiples of 4, as wgpu requires whole blocks.
+    pub fn best(features: wgpu::Features, size: wgpu::Extent3d) -> Self {
+        if !(size.width.is_multiple_of(4) && size.height.is_multiple_of(4)) {
+            return Self::Rgba8;
+        }
+        if features.contains(wgpu::Features::TEXTURE_COMPRESSION_BC) {
+            Self::Bc7
+        } else if features.contains(wgpu::Features::TEXTURE_COMPRESSION_ASTC) {
+            Self::Astc4x4
+        } else if features.contains(wgpu::Features::TEXTURE_COMPRESSION_ETC2) {
+            Self::Etc2Rgba8
+        } else {
+            Self::Rgba8
+        }
+    }
+
+    /// Gets the texture format of the target.
+    pub fn format(self, srgb: bool) -> wgpu::TextureFormat {
+        let format = match self {
+            Self::Bc7 => wgpu::TextureFormat::Bc7RgbaUnorm,
+            Self::Astc4x4 => wgpu::TextureFormat::Astc {
+                block: wgpu::AstcBlock::B4x4,
+                channel: wgpu::AstcChannel::Unorm,
+            },
+            Self::Etc2Rgba8 => wgpu::TextureFormat::Etc2Rgba8Unorm,
+            Self::Rgba8 => wgpu::TextureFormat::Rgba8Unorm,
+        };
+        if srgb {
+            format.add_srgb_suffix()
+        } else {
+            format
+        }
+    }
+}
+
+/// Header information of a supercompressed texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Info {
+    /// Size of the first mip level. Array layers are stacked in `depth_or_array_layers`.
+    pub size: wgpu::Extent3d,
+    /// Whether the texture is sRGB-encoded.
+    pub srgb: bool,
+}
+
+/// A transcoder for a supercompressed texture format.
+pub trait Transcoder {
+    /// Error returned for invalid or unsupported data.
+    type Error;
+
+    /// Reads the header of a texture.
+    fn info(&self, data: &[u8]) -> Result<Info, Self::Error>;
+
+    /// Transcodes the first mip level of every array layer to a target, tightly packed, layer by layer.
+    fn transcode(&self, data: &[u8], target: Target) -> Result<Vec<u8>, Self::Error>;
+}
+
+/// Transcodes a texture to the best target for the device's features, see [`Target::best`], and creates an image from it.
+///
+/// Images in compressed formats get their own textures rather than being packed into the image atlas.
+pub fn transcode<T>(
+    transcoder: &T,
+    data: &[u8],
+    features: wgpu::Features,
+) -> Result<Image, T::Error>
+where
+    T: Transcoder,
+{
+    let info = transcoder.info(data)?;
+    let target = Target::best(features, info.size);
+    let pixels = transcoder.transcode(data, target)?;
+    Ok(Image::new(
+        pixels,
+        wgpu::TextureDescriptor {
+            label: None,
+            size: info.size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: target.format(info.srgb),
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size(width: u32, height: u32) -> wgpu::Extent3d {
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        }
+    }
+
+    #[test]
+    fn best_prefers_bc7_then_astc_then_etc2() {
+        let all = wgpu::Features::TEXTURE_COMPRESSION_BC
+            | wgpu::Features::TEXTURE_COMPRESSION_ASTC
+            | wgpu::Features::TEXTURE_COMPRESSION_ETC2;
+        assert_eq!(Target::best(all, size(64, 64)), Target::Bc7);
+        assert_eq!(
+            Target::best(all - wgpu::Features::TEXTURE_COMPRESSION_BC, size(64, 64)),
+            Target::Astc4x4
+        );
+        assert_eq!(
+            Target::best(wgpu::Features::TEXTURE_COMPRESSION_ETC2, size(64, 64)),
+            Target::Etc2Rgba8
+        );
+        assert_eq!(
+            Target::best(wgpu::Features::empty(), size(64, 64)),
+            Target::Rgba8
+        );
+    }
+
+    #[test]
+    fn best_is_uncompressed_for_partial_blocks() {
+        let all = wgpu::Features::TEXTURE_COMPRESSION_BC
+            | wgpu::Features::TEXTURE_COMPRESSION_ASTC
+            | wgpu::Features::TEXTURE_COMPRESSION_ETC2;
+        assert_eq!(Target::best(all, size(30, 64)), Target::Rgba8);
+        assert_eq!(Target::best(all, size(64, 2)), Target::Rgba8);
+        assert_eq!(Target::best(all, size(4, 4)), Target::Bc7);
+    }
+}
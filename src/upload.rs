@@ -0,0 +1,218 @@
+//! Texture uploads through staging buffers.
+
+use wgpu::util::DeviceExt;
+
+/// Uploads larger than this go through a staging buffer even if their rows are aligned, so the queue does not have to hold a second copy of them.
+const MAX_DIRECT_UPLOAD_SIZE: usize = 1 << 20;
+
+/// Creates a texture and uploads its pixels.
+///
+/// Textures with mipmaps are uploaded by [`wgpu::util::DeviceExt::create_texture_with_data`], with their data in the given order.
+pub fn create_texture_with_data(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    desc: &wgpu::TextureDescriptor,
+    order: wgpu::util::TextureDataOrder,
+    data: &[u8],
+) -> wgpu::Texture {
+    if desc.mip_level_count != 1 {
+        return device.create_texture_with_data(queue, desc, order, data);
+    }
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        usage: desc.usage | wgpu::TextureUsages::COPY_DST,
+        ..*desc
+    });
+    write_texture(device, queue, &texture, data);
+    texture
+}
+
+/// Replaces the pixels of the first mip level of a texture, which must have been created with [`wgpu::TextureUsages::COPY_DST`].
+///
+/// Pixels are tightly packed, layer by layer. Rows that are not a multiple of [`wgpu::COPY_BYTES_PER_ROW_ALIGNMENT`] bytes, and large uploads, are padded into a staging buffer and copied from there.
+pub fn write_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    data: &[u8],
+) {
+    let size = texture.size();
+    let (bytes_per_row, rows_per_image) = layout(texture.format(), size);
+    let rows = (rows_per_image * size.depth_or_array_layers) as usize;
+
+    if bytes_per_row.is_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+        && data.len() <= MAX_DIRECT_UPLOAD_SIZE
+    {
+        queue.write_texture(
+            texture.as_image_copy(),
+            data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: Some(rows_per_image),
+            },
+            size,
+        );
+        return;
+    }
+
+    let padded_bytes_per_row = bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("canvasette: texture_staging_buffer"),
+        size: padded_bytes_per_row as u64 * rows as u64,
+        usage: wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: true,
+    });
+    {
+        let mut mapped = buffer.slice(..).get_mapped_range_mut();
+        for (dst, src) in mapped
+            .chunks_exact_mut(padded_bytes_per_row as usize)
+            .zip(data.chunks_exact(bytes_per_row as usize))
+        {
+            dst[..bytes_per_row as usize].copy_from_slice(src);
+        }
+    }
+    buffer.unmap();
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("canvasette: write_texture"),
+    });
+    encoder.copy_buffer_to_texture(
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(rows_per_image),
+            },
+        },
+        texture.as_image_copy(),
+        size,
+    );
+    queue.submit(Some(encoder.finish()));
+}
+
+/// Gets the bytes per row and rows per layer of tightly packed pixels for the first mip level of a texture.
+fn layout(format: wgpu::TextureFormat, size: wgpu::Extent3d) -> (u32, u32) {
+    let (block_width, block_height) = format.block_dimensions();
+    (
+        size.width.div_ceil(block_width) * format.block_copy_size(None).unwrap_or(4),
+        size.height.div_ceil(block_height),
+    )
+}
+
+/// Gets the length of tightly packed pixels for the first mip level of a texture.
+pub fn data_len(format: wgpu::TextureFormat, size: wgpu::Extent3d) -> usize {
+    let (bytes_per_row, rows_per_image) = layout(format, size);
+    bytes_per_row as usize * rows_per_image as usize * size.depth_or_array_layers as usize
+}
+
+/// Gets the length of tightly packed pixels for every mip level of a texture.
+#[cfg(any(feature = "ktx2", feature = "dds"))]
+pub fn mip_chain_len(desc: &wgpu::TextureDescriptor) -> usize {
+    (0..desc.mip_level_count)
+        .filter_map(|level| desc.mip_level_size(level))
+        .map(|size| data_len(desc.format, size))
+        .sum()
+}
+
+/// Makes 8-bit RGBA or BGRA pixels of the key color transparent black, ignoring their alpha.
+///
+/// Returns [`None`] for other formats, which are left as they are.
+pub fn color_key(format: wgpu::TextureFormat, data: &[u8], key: crate::Color) -> Option<Vec<u8>> {
+    let key = match format {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => {
+            [key.r, key.g, key.b]
+        }
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => {
+            [key.b, key.g, key.r]
+        }
+        _ => return None,
+    };
+    let mut data = data.to_vec();
+    for pixel in data.chunks_exact_mut(4) {
+        if pixel[..3] == key {
+            pixel.fill(0);
+        }
+    }
+    Some(data)
+}
+
+/// Generates the mip chain of 8-bit RGBA or BGRA pixels with a single mip level, by averaging each 2×2 block of the level above, in linear space for sRGB formats.
+///
+/// Returns the pixels of every level, ordered layer by layer, and the number of levels, or [`None`] for other formats.
+pub fn mipmaps(
+    format: wgpu::TextureFormat,
+    size: wgpu::Extent3d,
+    data: &[u8],
+) -> Option<(Vec<u8>, u32)> {
+    let srgb = match format {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Bgra8Unorm => false,
+        wgpu::TextureFormat::Rgba8UnormSrgb | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+        _ => return None,
+    };
+    let levels = size.max_mips(wgpu::TextureDimension::D2);
+    let layer_len = (size.width * size.height * 4) as usize;
+    let mut out = Vec::with_capacity(data.len() * 4 / 3 + 4);
+    for layer in data.chunks_exact(layer_len) {
+        let (mut width, mut height) = (size.width as usize, size.height as usize);
+        let mut start = out.len();
+        out.extend_from_slice(layer);
+        for _ in 1..levels {
+            let (next_width, next_height) = ((width / 2).max(1), (height / 2).max(1));
+            let next_start = out.len();
+            for y in 0..next_height {
+                for x in 0..next_width {
+                    // Odd sizes drop their last row or column, as wgpu's own mip sizes round down.
+                    let texels = [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(dx, dy)| {
+                        let (x, y) = ((x * 2 + dx).min(width - 1), (y * 2 + dy).min(height - 1));
+                        start + (y * width + x) * 4
+                    });
+                    for c in 0..4 {
+                        let v = if srgb && c < 3 {
+                            crate::palette::linear_to_srgb(
+                                texels
+                                    .iter()
+                                    .map(|&i| crate::palette::srgb_to_linear(out[i + c]))
+                                    .sum::<f32>()
+                                    / 4.0,
+                            )
+                        } else {
+                            (texels.iter().map(|&i| out[i + c] as u32).sum::<u32>() as f32 / 4.0)
+                                .round() as u8
+                        };
+                        out.push(v);
+                    }
+                }
+            }
+            (width, height, start) = (next_width, next_height, next_start);
+        }
+    }
+    Some((out, levels))
+}
+
+/// Premultiplies 8-bit RGBA or BGRA pixels by their alpha, in linear space for sRGB formats.
+///
+/// Returns [`None`] for other formats, which are left as they are.
+pub fn premultiply(format: wgpu::TextureFormat, data: &[u8]) -> Option<Vec<u8>> {
+    let srgb = match format {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Bgra8Unorm => false,
+        wgpu::TextureFormat::Rgba8UnormSrgb | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+        _ => return None,
+    };
+    let mut data = data.to_vec();
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = pixel[3];
+        if alpha == 0xff {
+            continue;
+        }
+        let alpha = alpha as f32 / 255.0;
+        for v in &mut pixel[..3] {
+            *v = if srgb {
+                crate::palette::linear_to_srgb(crate::palette::srgb_to_linear(*v) * alpha)
+            } else {
+                (*v as f32 * alpha).round() as u8
+            };
+        }
+    }
+    Some(data)
+}
@@ -0,0 +1,94 @@
+//! Deterministic per-draw variation.
+//!
+//! A [`Variation`] derives small, stable perturbations of tint and transform from a user-supplied id, so large numbers of identical sprites (trees, crowds) can look different from each other without storing any per-entity state. The same id always produces the same result, on every frame and every machine.
+
+use glam::*;
+
+use crate::{palette, Color};
+
+/// Ranges of variation to apply to a draw, keyed by an id.
+///
+/// Each range is symmetric: a value of `x` means the perturbation is picked from `[-x, x]`. Rotation and scale are applied around the center of the drawn item's bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Variation {
+    /// Id to derive the variation from, e.g. an entity id or a hash of a tile position.
+    pub id: u64,
+
+    /// Brightness variation, as a fraction of the tint's value.
+    pub brightness: f32,
+
+    /// Hue variation, in degrees.
+    pub hue: f32,
+
+    /// Rotation variation, in radians.
+    pub rotation: f32,
+
+    /// Uniform scale variation, as a fraction of the original size.
+    pub scale: f32,
+}
+
+impl Variation {
+    /// Creates a new variation for the given id with no ranges set.
+    pub fn new(id: u64) -> Self {
+        Self {
+            id,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the brightness range.
+    pub fn brightness(self, brightness: f32) -> Self {
+        Self { brightness, ..self }
+    }
+
+    /// Sets the hue range.
+    pub fn hue(self, hue: f32) -> Self {
+        Self { hue, ..self }
+    }
+
+    /// Sets the rotation range.
+    pub fn rotation(self, rotation: f32) -> Self {
+        Self { rotation, ..self }
+    }
+
+    /// Sets the scale range.
+    pub fn scale(self, scale: f32) -> Self {
+        Self { scale, ..self }
+    }
+
+    /// Gets a value in `[-1, 1]` for the given channel.
+    fn sample(&self, channel: u64) -> f32 {
+        let bits = splitmix64(self.id ^ channel.wrapping_mul(0x9e3779b97f4a7c15));
+        (bits >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+    }
+
+    /// Applies the variation to a tint.
+    pub fn apply_tint(&self, tint: Color) -> Color {
+        if self.brightness == 0.0 && self.hue == 0.0 {
+            return tint;
+        }
+        let mut hsv = palette::Hsv::from_color(tint);
+        hsv.v *= 1.0 + self.brightness * self.sample(0);
+        hsv.h += self.hue * self.sample(1);
+        hsv.to_color()
+    }
+
+    /// Applies the variation to a transform, rotating and scaling around `center` in local coordinates.
+    pub fn apply_transform(&self, transform: Affine2, center: Vec2) -> Affine2 {
+        if self.rotation == 0.0 && self.scale == 0.0 {
+            return transform;
+        }
+        transform
+            * Affine2::from_translation(center)
+            * Affine2::from_angle(self.rotation * self.sample(2))
+            * Affine2::from_scale(Vec2::splat(1.0 + self.scale * self.sample(3)))
+            * Affine2::from_translation(-center)
+    }
+}
+
+pub(crate) fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
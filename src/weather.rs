@@ -0,0 +1,212 @@
+//! Screen-space weather: rain and snow.
+//!
+//! A [`Weather`] fills the viewport with falling particles, each drawn with the same texture slice. Particles live in a repeating tile slightly larger than the viewport and are offset by the camera's scroll position, so they stay put in the world as the camera moves and wrap seamlessly at the edges. Draw it in a layer without a view, on top of the scene.
+
+use glam::*;
+
+use crate::{
+    camera::Camera, palette, variation::splitmix64, Canvas, Color, Drawable, Texture, TextureSlice,
+};
+
+/// Kind of precipitation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeatherKind {
+    /// Fast, straight streaks, rotated to follow their direction of travel. The slice should be a vertical streak.
+    Rain,
+
+    /// Slow flakes that sway from side to side as they fall.
+    Snow,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Particle {
+    /// Unwrapped position in target pixels.
+    position: Vec2,
+    /// Multiplier for the fall speed, so nearer (larger) particles fall faster.
+    depth: f32,
+    /// Phase of the sideways sway, in radians.
+    phase: f32,
+}
+
+/// A field of falling particles covering the viewport.
+pub struct Weather<'a, T> {
+    slice: TextureSlice<'a, T>,
+    kind: WeatherKind,
+    density: f32,
+    wind: f32,
+    fall_speed: f32,
+    sway: f32,
+    particles: Vec<Particle>,
+    tile: Vec2,
+    scroll: Vec2,
+    time: f32,
+    seed: u64,
+}
+
+impl<'a, T> Clone for Weather<'a, T> {
+    fn clone(&self) -> Self {
+        Self {
+            slice: self.slice,
+            kind: self.kind,
+            density: self.density,
+            wind: self.wind,
+            fall_speed: self.fall_speed,
+            sway: self.sway,
+            particles: self.particles.clone(),
+            tile: self.tile,
+            scroll: self.scroll,
+            time: self.time,
+            seed: self.seed,
+        }
+    }
+}
+
+impl<'a, T> Weather<'a, T>
+where
+    T: Texture,
+{
+    /// Creates rain drawn with a vertical streak texture.
+    pub fn rain(slice: TextureSlice<'a, T>) -> Self {
+        Self::new(slice, WeatherKind::Rain, 600.0, 0.0)
+    }
+
+    /// Creates snow drawn with a flake texture.
+    pub fn snow(slice: TextureSlice<'a, T>) -> Self {
+        Self::new(slice, WeatherKind::Snow, 60.0, 20.0)
+    }
+
+    fn new(slice: TextureSlice<'a, T>, kind: WeatherKind, fall_speed: f32, sway: f32) -> Self {
+        Self {
+            slice,
+            kind,
+            density: 1.0,
+            wind: 0.0,
+            fall_speed,
+            sway,
+            particles: vec![],
+            tile: Vec2::ZERO,
+            scroll: Vec2::ZERO,
+            time: 0.0,
+            seed: 0,
+        }
+    }
+
+    /// Sets the number of particles per 100 × 100 target pixels.
+    pub fn density(self, density: f32) -> Self {
+        Self { density, ..self }
+    }
+
+    /// Sets the horizontal wind speed, in target pixels per second.
+    pub fn wind(self, wind: f32) -> Self {
+        Self { wind, ..self }
+    }
+
+    /// Sets the average fall speed, in target pixels per second.
+    pub fn fall_speed(self, fall_speed: f32) -> Self {
+        Self { fall_speed, ..self }
+    }
+
+    /// Sets how far snowflakes sway from side to side, in target pixels. Has no effect on rain.
+    pub fn sway(self, sway: f32) -> Self {
+        Self { sway, ..self }
+    }
+
+    /// Sets the seed particles are scattered with.
+    pub fn seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+
+    /// Gets the kind of precipitation.
+    pub fn kind(&self) -> WeatherKind {
+        self.kind
+    }
+
+    /// Sets the horizontal wind speed of an existing weather system, e.g. for gusts.
+    pub fn set_wind(&mut self, wind: f32) {
+        self.wind = wind;
+    }
+
+    /// Sets the density of an existing weather system, e.g. to ramp a storm up or down.
+    pub fn set_density(&mut self, density: f32) {
+        self.density = density;
+    }
+
+    /// Number of particles currently alive.
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// Whether there are no particles.
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    /// Advances the particles by `dt` seconds and follows the camera's scroll position.
+    ///
+    /// Particles are added or removed to match the density over the camera's viewport. The camera's rotation is ignored.
+    pub fn update(&mut self, dt: f32, camera: &Camera) {
+        self.tile = camera.viewport_size.max(Vec2::ZERO) + Vec2::splat(self.margin() * 2.0);
+        self.scroll = camera.position * camera.zoom;
+        self.time += dt;
+
+        let count = (self.density.max(0.0) * self.tile.x * self.tile.y / 10000.0).round() as usize;
+        self.particles.truncate(count);
+        while self.particles.len() < count {
+            let i = self.particles.len() as u64;
+            let sample = |channel: u64| {
+                (splitmix64(self.seed ^ i.wrapping_mul(0x9e3779b97f4a7c15) ^ channel) >> 40) as f32
+                    / (1u64 << 24) as f32
+            };
+            let particle = Particle {
+                position: self.scroll + Vec2::new(sample(0), sample(1)) * self.tile,
+                depth: 0.5 + sample(2) * 0.5,
+                phase: sample(3) * std::f32::consts::TAU,
+            };
+            self.particles.push(particle);
+        }
+
+        for particle in self.particles.iter_mut() {
+            particle.position += Vec2::new(self.wind, self.fall_speed) * particle.depth * dt;
+        }
+    }
+
+    /// Gets the distance the tile extends past each edge of the viewport, so particles leave the screen before wrapping.
+    fn margin(&self) -> f32 {
+        self.slice.size().as_vec2().max_element() + self.sway
+    }
+
+    /// Gets a particle's on-screen position, wrapped into the tile around the viewport.
+    fn screen_position(&self, particle: &Particle) -> Vec2 {
+        let mut position = particle.position;
+        if self.kind == WeatherKind::Snow {
+            position.x += self.sway * (self.time * 1.5 + particle.phase).sin();
+        }
+        (position - self.scroll).rem_euclid(self.tile.max(Vec2::ONE)) - Vec2::splat(self.margin())
+    }
+}
+
+impl<'a, T> Drawable<'a> for Weather<'a, T>
+where
+    T: Texture,
+{
+    fn draw(&self, canvas: &mut Canvas<'a>, tint: Color, transform: Affine2) {
+        let size = self.slice.size().as_vec2();
+        let angle = match self.kind {
+            // Streaks are vertical, so lean them into the wind.
+            WeatherKind::Rain => -self.wind.atan2(self.fall_speed),
+            WeatherKind::Snow => 0.0,
+        };
+        for particle in self.particles.iter() {
+            let alpha = (tint.a as f32 * particle.depth).round() as u8;
+            self.slice.draw(
+                canvas,
+                palette::with_alpha(tint, alpha),
+                transform
+                    * Affine2::from_translation(self.screen_position(particle))
+                    * Affine2::from_angle(angle)
+                    * Affine2::from_scale(Vec2::splat(particle.depth))
+                    * Affine2::from_translation(-size * 0.5),
+            );
+        }
+    }
+}